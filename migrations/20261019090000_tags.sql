@@ -0,0 +1,17 @@
+-- /migrations
+-- Adds many-to-many tags on cards
+
+CREATE TABLE IF NOT EXISTS tags (
+    id UUID NOT NULL,
+    PRIMARY KEY(id),
+    name TEXT UNIQUE NOT NULL,
+    created TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS card_tags (
+    card_id UUID NOT NULL,
+    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
+    tag_id UUID NOT NULL,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY(card_id, tag_id)
+);
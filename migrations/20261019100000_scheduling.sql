@@ -0,0 +1,8 @@
+-- /migrations
+-- Adds spaced-repetition scheduling state to cards
+
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS due TIMESTAMPTZ;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS interval_days INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS ease REAL NOT NULL DEFAULT 2.5;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS lapses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS suspended BOOLEAN NOT NULL DEFAULT FALSE;
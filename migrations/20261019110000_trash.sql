@@ -0,0 +1,16 @@
+-- /migrations
+-- Soft delete: trashed cards and decks keep their rows (and review history) until purged
+
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS deleted TIMESTAMPTZ;
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS deleted TIMESTAMPTZ;
+
+-- A trashed deck shouldn't stop a new deck from taking its name
+ALTER TABLE decks DROP CONSTRAINT IF EXISTS decks_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS decks_name_live_idx ON decks (name) WHERE deleted IS NULL;
+
+-- Purging a card permanently keeps its reviews: they stay in the log, no longer pointing at
+-- a card, so the activity heatmap, streaks and statistics still count them
+ALTER TABLE views DROP CONSTRAINT IF EXISTS views_card_id_fkey;
+ALTER TABLE views
+    ADD CONSTRAINT views_card_id_fkey
+    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE SET NULL;
@@ -0,0 +1,16 @@
+-- /migrations
+-- Append-only history of card text: each row is the text a card had before an edit
+
+CREATE TABLE IF NOT EXISTS card_revisions (
+    id UUID NOT NULL,
+    PRIMARY KEY(id),
+    card_id UUID NOT NULL,
+    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
+    front_text TEXT,
+    back_text TEXT,
+    source TEXT NOT NULL, -- what made the edit, e.g. 'tui'
+    author TEXT,
+    created TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS card_revisions_card_idx ON card_revisions (card_id, created);
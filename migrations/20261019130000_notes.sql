@@ -0,0 +1,24 @@
+-- /migrations
+-- Notes own the text shared by one or more cards, e.g. a forward card and its reverse
+
+CREATE TABLE IF NOT EXISTS notes (
+    id UUID NOT NULL,
+    PRIMARY KEY(id),
+    front_text TEXT,
+    back_text TEXT,
+    reverse BOOLEAN NOT NULL DEFAULT FALSE, -- also generate a back->front card
+    created TIMESTAMPTZ,
+    modified TIMESTAMPTZ
+);
+
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS note_id UUID REFERENCES notes(id) ON DELETE CASCADE;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS ordinal INTEGER NOT NULL DEFAULT 0; -- 0 forward, 1 reverse
+
+-- Every existing card becomes the forward card of its own note
+INSERT INTO notes (id, front_text, back_text, created, modified)
+SELECT id, front_text, back_text, created, modified FROM cards
+WHERE note_id IS NULL
+ON CONFLICT (id) DO NOTHING;
+UPDATE cards SET note_id = id WHERE note_id IS NULL;
+
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS reverse_by_default BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- /migrations
+-- Note types: 'basic' (front/back, optionally reversed) or 'cloze' (one card per cloze number)
+
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'basic';
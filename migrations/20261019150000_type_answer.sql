@@ -0,0 +1,7 @@
+-- /migrations
+-- Decks can require typing the answer; reviews keep what was typed and how close it was
+
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS type_answer BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE views ADD COLUMN IF NOT EXISTS typed_answer TEXT;
+ALTER TABLE views ADD COLUMN IF NOT EXISTS similarity REAL;
@@ -0,0 +1,9 @@
+-- /migrations
+-- Optional hint (revealed progressively in review), notes (shown after reveal) and source on cards;
+-- reviews record whether the hint was used
+
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS hint TEXT;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS notes TEXT;
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS source TEXT;
+
+ALTER TABLE views ADD COLUMN IF NOT EXISTS hint_used BOOLEAN NOT NULL DEFAULT FALSE;
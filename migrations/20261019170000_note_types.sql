@@ -0,0 +1,24 @@
+-- /migrations
+-- User-defined note types: named fields plus card templates; each template whose front
+-- shows a field with text generates one card, its ordinal being the template's
+
+CREATE TABLE IF NOT EXISTS note_types (
+    id UUID NOT NULL,
+    PRIMARY KEY(id),
+    name TEXT NOT NULL UNIQUE,
+    fields TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE TABLE IF NOT EXISTS card_templates (
+    note_type_id UUID NOT NULL REFERENCES note_types(id) ON DELETE CASCADE,
+    ordinal INTEGER NOT NULL,
+    PRIMARY KEY(note_type_id, ordinal),
+    name TEXT NOT NULL,
+    front TEXT NOT NULL,
+    back TEXT NOT NULL
+);
+
+-- Field values are stored by name, so reordering a type's fields doesn't mix them up
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS note_type_id UUID REFERENCES note_types(id);
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS field_names TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE notes ADD COLUMN IF NOT EXISTS field_values TEXT[] NOT NULL DEFAULT '{}';
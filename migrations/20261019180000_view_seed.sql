@@ -0,0 +1,4 @@
+-- /migrations
+-- Seed the values of a parameterised card were drawn from in a review, so they can be reproduced
+
+ALTER TABLE views ADD COLUMN IF NOT EXISTS seed BIGINT;
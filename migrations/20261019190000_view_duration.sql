@@ -0,0 +1,4 @@
+-- /migrations
+-- Time taken to answer each review, for the statistics
+
+ALTER TABLE views ADD COLUMN IF NOT EXISTS duration_ms INTEGER;
@@ -0,0 +1,4 @@
+-- /migrations
+-- Decks can be studied towards an exam date: intervals are capped and new cards spread to fit before it
+
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS exam_date DATE;
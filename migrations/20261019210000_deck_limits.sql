@@ -0,0 +1,7 @@
+-- /migrations
+-- Daily limits on new cards and reviews per deck (NULL for unlimited), and the order of the queue
+
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS new_per_day INTEGER DEFAULT 20;
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS reviews_per_day INTEGER DEFAULT 200;
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS new_order TEXT NOT NULL DEFAULT 'sequential';
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS interleave TEXT NOT NULL DEFAULT 'mixed';
@@ -0,0 +1,19 @@
+-- /migrations
+-- Named deck option presets; a deck linked to a preset takes its options from it
+
+CREATE TABLE IF NOT EXISTS presets (
+    id UUID NOT NULL,
+    PRIMARY KEY(id),
+    name TEXT NOT NULL UNIQUE,
+    scheduler TEXT NOT NULL DEFAULT 'sm2',
+    learning_steps INTEGER[] NOT NULL DEFAULT '{1,10}',
+    new_per_day INTEGER DEFAULT 20,
+    reviews_per_day INTEGER DEFAULT 200,
+    new_order TEXT NOT NULL DEFAULT 'sequential',
+    interleave TEXT NOT NULL DEFAULT 'mixed',
+    desired_retention REAL NOT NULL DEFAULT 0.9,
+    leech_threshold INTEGER NOT NULL DEFAULT 8,
+    review_mode TEXT NOT NULL DEFAULT 'reveal'
+);
+
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS preset_id UUID REFERENCES presets(id) ON DELETE SET NULL;
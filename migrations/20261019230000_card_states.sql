@@ -0,0 +1,9 @@
+-- /migrations
+-- Learning state of each card: new and forgotten cards go through learning steps
+-- (minutes apart) before they are reviewed at day intervals
+
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS state TEXT NOT NULL DEFAULT 'new';
+ALTER TABLE cards ADD COLUMN IF NOT EXISTS step INTEGER NOT NULL DEFAULT 0;
+
+-- Cards reviewed before learning steps existed have graduated
+UPDATE cards SET state = 'review' WHERE due IS NOT NULL;
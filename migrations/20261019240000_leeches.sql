@@ -0,0 +1,5 @@
+-- /migrations
+-- What happens to a card that becomes a leech: it is always tagged `leech`, and suspended
+-- unless the preset says to only tag it
+
+ALTER TABLE presets ADD COLUMN IF NOT EXISTS leech_action TEXT NOT NULL DEFAULT 'suspend';
@@ -4,10 +4,55 @@ use config::*;
 use secrecy::{ExposeSecret, Secret};
 use sqlx::postgres::PgConnectOptions;
 
+use crate::domain::{activity::DailyGoal, answer::AnswerOptions, note_type::NoteType, preset::Preset};
+
 #[derive(serde::Deserialize)]
 pub struct Settings {
-    //TODO: add app settings
     pub database: DatabaseSettings,
+    #[serde(default)]
+    pub app: AppSettings,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Trashed cards and decks are purged for good after this many days
+    pub trash_retention_days: i32,
+    /// Name recorded against card revisions; defaults to `$USER`
+    pub author: String,
+    /// How typed answers are compared in decks that require typing
+    pub answer: AnswerOptions,
+    /// Number the lines of code blocks in cards
+    pub code_line_numbers: bool,
+    /// Note types available in the card editor, stored on startup
+    pub note_types: Vec<NoteType>,
+    /// Study days start at this hour (local time), so late sessions count towards the day before
+    pub rollover_hour: u32,
+    /// Reviews and/or minutes to aim for each day
+    pub daily_goal: DailyGoal,
+    /// The deck list warns when a deck needs more reviews a day than this to be ready for its exam
+    pub exam_load_warning: u64,
+    /// Deck option presets, added on startup unless one of the same name is stored
+    pub presets: Vec<Preset>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            trash_retention_days: 30,
+            author: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
+            answer: AnswerOptions::default(),
+            code_line_numbers: false,
+            note_types: Vec::new(),
+            rollover_hour: 4,
+            daily_goal: DailyGoal {
+                reviews: Some(50),
+                minutes: None,
+            },
+            exam_load_warning: 150,
+            presets: Vec::new(),
+        }
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -37,5 +82,12 @@ pub fn get_config() -> Result<Settings, config::ConfigError> {
         .add_source(config::File::from(config_dir.join("config.yaml")))
         .build()?;
 
-    config.try_deserialize::<Settings>()
+    let settings = config.try_deserialize::<Settings>()?;
+    // Presets are stored on startup, so they are held to the same rules as ones edited in the app
+    for preset in &settings.app.presets {
+        preset
+            .validate()
+            .map_err(|e| ConfigError::Message(format!("preset '{}': {}", preset.name, e)))?;
+    }
+    Ok(settings)
 }
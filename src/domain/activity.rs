@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use sqlx::PgPool;
+
+/// The study day `time` falls on: days follow the local timezone but start at `rollover_hour`,
+/// so a late-night session still counts towards the day before
+pub fn day_of(time: DateTime<Utc>, rollover_hour: u32) -> NaiveDate {
+    (time.with_timezone(&Local) - Duration::hours(rollover_hour.min(23) as i64)).date_naive()
+}
+
+/// When the study day `day` starts: `rollover_hour` on that day, local time
+pub fn start_of(day: NaiveDate, rollover_hour: u32) -> DateTime<Utc> {
+    let start = day.and_hms_opt(rollover_hour.min(23), 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&start)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&start))
+}
+
+/// Reviews a day to aim for, as a number of reviews, minutes spent, or both
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct DailyGoal {
+    pub reviews: Option<u64>,
+    pub minutes: Option<u64>,
+}
+
+/// Reviews logged on one day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DayActivity {
+    pub reviews: u64,
+    pub millis: u64,
+}
+
+impl DayActivity {
+    pub fn minutes(&self) -> u64 {
+        self.millis / 60_000
+    }
+}
+
+/// Reviews per study day, over the whole review log
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Activity {
+    pub days: BTreeMap<NaiveDate, DayActivity>,
+    pub today: NaiveDate,
+}
+
+impl Activity {
+    pub async fn load(db: &PgPool, rollover_hour: u32) -> Result<Activity, sqlx::Error> {
+        let views = sqlx::query!(
+            r#"
+            SELECT time, duration_ms FROM views
+            WHERE time IS NOT NULL
+            "#
+        )
+        .fetch_all(db)
+        .await?;
+        let reviews = views
+            .into_iter()
+            .filter_map(|row| Some((day_of(row.time?, rollover_hour), row.duration_ms.unwrap_or(0))));
+        Ok(Activity::from_reviews(reviews, day_of(Utc::now(), rollover_hour)))
+    }
+
+    /// Tallies reviews, given as (day, milliseconds taken)
+    pub fn from_reviews(reviews: impl IntoIterator<Item = (NaiveDate, i32)>, today: NaiveDate) -> Activity {
+        let mut days: BTreeMap<NaiveDate, DayActivity> = BTreeMap::new();
+        for (day, millis) in reviews {
+            let activity = days.entry(day).or_default();
+            activity.reviews += 1;
+            activity.millis += millis.max(0) as u64;
+        }
+        Activity { days, today }
+    }
+
+    pub fn on(&self, day: NaiveDate) -> DayActivity {
+        self.days.get(&day).copied().unwrap_or_default()
+    }
+
+    /// Days in a row with reviews, up to today; a streak isn't broken until today ends
+    /// without a review, so one that ran up to yesterday still counts
+    pub fn current_streak(&self) -> u64 {
+        let mut day = match self.days.contains_key(&self.today) {
+            true => self.today,
+            false => self.today - Duration::days(1),
+        };
+        let mut streak = 0;
+        while self.days.contains_key(&day) {
+            streak += 1;
+            day -= Duration::days(1);
+        }
+        streak
+    }
+
+    pub fn longest_streak(&self) -> u64 {
+        let mut longest = 0;
+        let mut current = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for day in self.days.keys() {
+            current = match previous {
+                Some(previous) if *day - previous == Duration::days(1) => current + 1,
+                _ => 1,
+            };
+            longest = longest.max(current);
+            previous = Some(*day);
+        }
+        longest
+    }
+
+    /// How today is going against `goal`
+    pub fn progress(&self, goal: DailyGoal) -> DailyProgress {
+        DailyProgress {
+            today: self.on(self.today),
+            goal,
+            streak: self.current_streak(),
+        }
+    }
+}
+
+/// Today's reviews against the daily goal, and the current streak
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DailyProgress {
+    pub today: DayActivity,
+    pub goal: DailyGoal,
+    pub streak: u64,
+}
+
+impl DailyProgress {
+    /// Whether every target of the goal is reached; `false` if there is no goal
+    pub fn is_met(&self) -> bool {
+        let targets = [
+            self.goal.reviews.map(|reviews| self.today.reviews >= reviews),
+            self.goal.minutes.map(|minutes| self.today.minutes() >= minutes),
+        ];
+        targets.iter().any(Option::is_some) && targets.iter().flatten().all(|met| *met)
+    }
+}
+
+impl std::fmt::Display for DailyProgress {
+    /// e.g. "🔥 4 days | today: 23/50 reviews, 6/10 min"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reviews = match self.goal.reviews {
+            Some(goal) => format!("{}/{} reviews", self.today.reviews, goal),
+            None => format!("{} reviews", self.today.reviews),
+        };
+        let minutes = match self.goal.minutes {
+            Some(goal) => format!(", {}/{} min", self.today.minutes(), goal),
+            None => String::new(),
+        };
+        let done = if self.is_met() { " ✓" } else { "" };
+        write!(
+            f,
+            "🔥 {} day{} | today: {}{}{}",
+            self.streak,
+            if self.streak == 1 { "" } else { "s" },
+            reviews,
+            minutes,
+            done
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_rollover_hour() {
+        let late = Local
+            .from_local_datetime(&day(19).and_time(NaiveTime::from_hms_opt(2, 30, 0).unwrap()))
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(day(18), day_of(late, 4));
+        assert_eq!(day(19), day_of(late, 0));
+        assert_eq!(day(19), day_of(start_of(day(19), 4), 4));
+        assert_eq!(day(18), day_of(start_of(day(19), 4) - Duration::minutes(1), 4));
+    }
+
+    #[test]
+    fn test_streaks() {
+        let reviews = [1, 2, 3, 10, 11, 17, 18].map(|d| (day(d), 0));
+        let activity = Activity::from_reviews(reviews, day(19));
+        // Nothing yet today, but the streak up to yesterday is still going
+        assert_eq!(2, activity.current_streak());
+        assert_eq!(3, activity.longest_streak());
+        let activity = Activity::from_reviews(reviews, day(20));
+        assert_eq!(0, activity.current_streak());
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let reviews = vec![(day(19), 4 * 60_000); 30];
+        let activity = Activity::from_reviews(reviews, day(19));
+        let goal = DailyGoal {
+            reviews: Some(20),
+            minutes: Some(150),
+        };
+        let progress = activity.progress(goal);
+        assert!(!progress.is_met());
+        assert_eq!("🔥 1 day | today: 30/20 reviews, 120/150 min", progress.to_string());
+        assert!(activity.progress(DailyGoal { reviews: Some(20), minutes: None }).is_met());
+        assert!(!activity.progress(DailyGoal::default()).is_met());
+    }
+}
@@ -0,0 +1,143 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use super::card::Card;
+use super::cloze;
+use super::diff::{diff, DiffOp};
+
+/// Typed answers at least this similar to the expected one are suggested as correct
+pub const PASS_SIMILARITY: f32 = 0.9;
+
+/// What to disregard when checking a typed answer against the expected one
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct AnswerOptions {
+    pub ignore_case: bool,
+    /// Runs of whitespace count as a single space
+    pub ignore_whitespace: bool,
+    pub ignore_punctuation: bool,
+    /// e.g. `é` matches `e`
+    pub ignore_diacritics: bool,
+}
+
+impl Default for AnswerOptions {
+    fn default() -> Self {
+        Self {
+            ignore_case: true,
+            ignore_whitespace: true,
+            ignore_punctuation: false,
+            ignore_diacritics: false,
+        }
+    }
+}
+
+/// The result of checking a typed answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnswerCheck {
+    /// Edit script from the typed answer to the expected one, both normalised:
+    /// `Removed` characters were typed but not expected, `Added` ones were missing
+    pub diff: Vec<DiffOp<char>>,
+    /// 0.0 (nothing in common) to 1.0 (a match)
+    pub similarity: f32,
+}
+
+impl AnswerCheck {
+    pub fn is_exact(&self) -> bool {
+        self.diff.iter().all(|op| matches!(op, DiffOp::Same(_)))
+    }
+
+    /// The grade suggested by how close the answer was
+    pub fn suggested_grade(&self) -> bool {
+        self.similarity >= PASS_SIMILARITY
+    }
+}
+
+/// `text` with everything `options` disregards removed or folded
+pub fn normalize(text: &str, options: &AnswerOptions) -> String {
+    let mut text: String = match options.ignore_diacritics {
+        true => text.nfd().filter(|c| !is_combining_mark(*c)).nfc().collect(),
+        false => text.nfc().collect(),
+    };
+    if options.ignore_case {
+        text = text.to_lowercase();
+    }
+    if options.ignore_punctuation {
+        text.retain(|c| c.is_alphanumeric() || c.is_whitespace());
+    }
+    if options.ignore_whitespace {
+        text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    }
+    text.trim().to_string()
+}
+
+/// Compares `typed` with `expected` character by character
+pub fn check(typed: &str, expected: &str, options: &AnswerOptions) -> AnswerCheck {
+    let typed: Vec<char> = normalize(typed, options).chars().collect();
+    let expected: Vec<char> = normalize(expected, options).chars().collect();
+    let diff = diff(&typed, &expected);
+    let same = diff.iter().filter(|op| matches!(op, DiffOp::Same(_))).count();
+    let similarity = match typed.len() + expected.len() {
+        0 => 1.0,
+        total => (2 * same) as f32 / total as f32,
+    };
+    AnswerCheck { diff, similarity }
+}
+
+/// The text to type for `card`: its cloze deletion, or else its back
+pub fn expected_answer(card: &Card) -> String {
+    let back = card.back_text.clone().unwrap_or_default();
+    let number = card.ordinal.max(0) as u32;
+    match cloze::answers(&back, number) {
+        answers if answers.is_empty() => back,
+        answers => answers.join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_options() {
+        let all = AnswerOptions {
+            ignore_case: true,
+            ignore_whitespace: true,
+            ignore_punctuation: true,
+            ignore_diacritics: true,
+        };
+        assert_eq!("el nino esta aqui", normalize("  ¡El  Niño está\taquí! ", &all));
+        let none = AnswerOptions {
+            ignore_case: false,
+            ignore_whitespace: false,
+            ignore_punctuation: false,
+            ignore_diacritics: false,
+        };
+        assert_eq!("El  Niño", normalize(" El  Niño ", &none));
+    }
+
+    #[test]
+    fn test_check_similarity_and_grade() {
+        let options = AnswerOptions::default();
+        let exact = check("Bonjour", "bonjour", &options);
+        assert!(exact.is_exact());
+        assert_eq!(1.0, exact.similarity);
+
+        let typo = check("mitocondria", "mitochondria", &options);
+        assert!(!typo.is_exact());
+        let changes: Vec<&DiffOp<char>> = typo.diff.iter().filter(|op| !matches!(op, DiffOp::Same(_))).collect();
+        assert_eq!(vec![&DiffOp::Added('h')], changes);
+        assert!(typo.suggested_grade());
+
+        assert!(!check("chat", "chien", &options).suggested_grade());
+        assert_eq!(0.0, check("", "chien", &options).similarity);
+    }
+
+    #[test]
+    fn test_expected_answer_of_cloze_card() {
+        let card = Card {
+            back_text: Some("{{c1::Paris}} is in {{c2::France}}\n\ncapital".to_string()),
+            ordinal: 2,
+            ..Card::default()
+        };
+        assert_eq!("France", expected_answer(&card));
+    }
+}
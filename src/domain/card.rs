@@ -1,14 +1,40 @@
 use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+use super::scheduler::{CardState, Schedule, DEFAULT_EASE};
+use super::deck::{Deck, RenamedDeck};
+use super::note::Note;
+use super::tag::Tag;
+
+/// `Card::ordinal` of the card asking front -> back
+pub const FORWARD: i32 = 0;
+/// `Card::ordinal` of the generated card asking back -> front.
+/// Cards of cloze notes use their cloze number instead.
+pub const REVERSE: i32 = 1;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct Card {
     pub id: Uuid,
     pub front_text: Option<String>,
     pub back_text: Option<String>,
+    pub hint: Option<String>,   // revealed a bit at a time during review
+    pub notes: Option<String>,  // extra info shown once the answer is revealed
+    pub source: Option<String>, // where the material comes from
     pub deck_id: Option<Uuid>,
     pub created: Option<DateTime<Utc>>,
     pub modified: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    // Scheduling state, see `Schedule`
+    pub due: Option<DateTime<Utc>>,
+    pub interval_days: i32,
+    pub ease: f32,
+    pub lapses: i32,
+    pub suspended: bool,
+    pub state: CardState,
+    pub step: i32,
+    // The note owning this card's text, and which of its cards this is
+    pub note_id: Option<Uuid>,
+    pub ordinal: i32,
 }
 
 impl Default for Card {
@@ -17,46 +43,156 @@ impl Default for Card {
             id: Uuid::new_v4(), 
             front_text: None, 
             back_text: None, 
+            hint: None,
+            notes: None,
+            source: None,
             deck_id: None, 
             created: None, 
-            modified: None
+            modified: None,
+            tags: Vec::new(),
+            due: None,
+            interval_days: 0,
+            ease: DEFAULT_EASE,
+            lapses: 0,
+            suspended: false,
+            state: CardState::New,
+            step: 0,
+            note_id: None,
+            ordinal: FORWARD,
         } 
     }
 }
 
 impl Card {
+    /// Moves the card to the trash; see `Trash`
     pub async fn delete_from_db(connection_pool: &PgPool, card_id: Uuid) -> Result<(), sqlx::Error> {
+        Card::delete_many_from_db(connection_pool, &[card_id]).await
+    }
+
+    /// Moves the cards to the trash, keeping their review history
+    pub async fn delete_many_from_db(connection_pool: &PgPool, card_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET deleted = $2
+            WHERE id = ANY($1) AND deleted IS NULL
+            "#,
+            card_ids,
+            Utc::now(),
+        ).execute(connection_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Takes the cards out of the trash, along with any trashed deck they belong to
+    /// (renamed if a live deck has taken its name, see `Deck::untrash`)
+    pub async fn restore_from_trash(connection_pool: &PgPool, card_ids: &[Uuid]) -> Result<Vec<RenamedDeck>, sqlx::Error> {
+        let deck_ids = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM decks
+            WHERE deleted IS NOT NULL
+            AND id IN (SELECT deck_id FROM cards WHERE id = ANY($1))
+            "#,
+            card_ids
+        ).fetch_all(connection_pool)
+        .await?;
+        let mut renamed = Vec::new();
+        for deck_id in deck_ids {
+            renamed.extend(Deck::untrash(connection_pool, deck_id).await?);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET deleted = NULL
+            WHERE id = ANY($1)
+            "#,
+            card_ids
+        ).execute(connection_pool)
+        .await?;
+
+        Ok(renamed)
+    }
+
+    /// Permanently deletes the cards along with their tags, and the notes left without cards;
+    /// their reviews stay in `views`, no longer linked to a card
+    pub async fn purge_many(connection_pool: &PgPool, card_ids: &[Uuid]) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
             DELETE FROM cards
-            WHERE id = ($1)
+            WHERE id = ANY($1)
+            "#,
+            card_ids
+        ).execute(connection_pool)
+        .await?;
+
+        Note::delete_orphans(connection_pool).await
+    }
+
+    pub async fn move_to_deck(connection_pool: &PgPool, card_ids: &[Uuid], deck_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET deck_id = $2, modified = $3
+            WHERE id = ANY($1)
             "#,
-            card_id
+            card_ids,
+            deck_id,
+            Utc::now(),
         ).execute(connection_pool)
         .await?;
 
         Ok(())
     }
 
+    /// Suspended cards are left out of reviews until they are unsuspended
+    pub async fn set_suspended(connection_pool: &PgPool, card_ids: &[Uuid], suspended: bool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET suspended = $2
+            WHERE id = ANY($1)
+            "#,
+            card_ids,
+            suspended,
+        ).execute(connection_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Loads the current state of each card in `card_ids` that still exists
+    pub async fn load_many(connection_pool: &PgPool, card_ids: &[Uuid]) -> Result<Vec<Card>, sqlx::Error> {
+        sqlx::query_as!(
+            Card,
+            r#"
+            SELECT id, front_text, back_text, deck_id, created, modified,
+                ARRAY(
+                    SELECT t.name FROM tags t
+                    JOIN card_tags ct ON ct.tag_id = t.id
+                    WHERE ct.card_id = cards.id
+                    ORDER BY t.name
+                ) AS "tags!",
+                due, interval_days, ease, lapses, suspended, note_id, ordinal, hint, notes, source,
+                state AS "state: CardState", step
+            FROM cards
+            WHERE id = ANY($1)
+            "#,
+            card_ids,
+        )
+        .fetch_all(connection_pool)
+        .await
+    }
+
     pub fn new() -> Self {
-        Card {
-            id: Uuid::new_v4(),
-            front_text: None,
-            back_text: None,
-            deck_id: None,
-            created: None,
-            modified: None,
-        }
+        Card::default()
     }
 
     pub fn new_with_deck(deck_id: Uuid) -> Self {
         Card {
-            id: Uuid::new_v4(),
-            front_text: None,
-            back_text: None,
             deck_id: Some(deck_id),
-            created: None,
-            modified: None,
+            ..Card::default()
         }
     }
 
@@ -68,6 +204,44 @@ impl Card {
         self.back_text = Some(text);
     }
 
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        Schedule::from(self)
+    }
+
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.due = schedule.due;
+        self.interval_days = schedule.interval_days;
+        self.ease = schedule.ease;
+        self.lapses = schedule.lapses;
+        self.state = schedule.state;
+        self.step = schedule.step;
+    }
+
+    /// Persists only the scheduling state of the card
+    pub async fn save_schedule(&self, connection_pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET due = $2, interval_days = $3, ease = $4, lapses = $5, state = $6, step = $7
+            WHERE id = $1
+            "#,
+            self.id,
+            self.due,
+            self.interval_days,
+            self.ease,
+            self.lapses,
+            self.state as CardState,
+            self.step,
+        )
+        .execute(connection_pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn save(&self, connection_pool: &PgPool) -> Result<(), sqlx::Error> {
         let deck_id_print: String = match &self.deck_id {
             Some(id) => id.to_string(),
@@ -77,12 +251,18 @@ impl Card {
 
         sqlx::query!(
             r#"
-            INSERT INTO cards (id, front_text, back_text, deck_id, created, modified)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO cards (id, front_text, back_text, deck_id, created, modified, note_id, ordinal,
+                hint, notes, source)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             ON CONFLICT (id) DO UPDATE
             SET front_text = EXCLUDED.front_text,
             back_text = EXCLUDED.back_text,
-            modified = EXCLUDED.modified
+            modified = EXCLUDED.modified,
+            note_id = EXCLUDED.note_id,
+            ordinal = EXCLUDED.ordinal,
+            hint = EXCLUDED.hint,
+            notes = EXCLUDED.notes,
+            source = EXCLUDED.source
             "#,
             self.id,
             self.front_text,
@@ -90,9 +270,67 @@ impl Card {
             self.deck_id,
             Utc::now(),
             Utc::now(), // `modified` will be overwritten where card exists in db
+            self.note_id,
+            self.ordinal,
+            self.hint,
+            self.notes,
+            self.source,
         )
         .execute(connection_pool)
         .await?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Writes every field of the card (including tags and scheduling state) back to the db,
+    /// recreating it if it was purged and taking it out of the trash
+    pub async fn restore(&self, connection_pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO cards (id, front_text, back_text, deck_id, created, modified,
+                due, interval_days, ease, lapses, suspended, note_id, ordinal, hint, notes, source,
+                state, step)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+            ON CONFLICT (id) DO UPDATE
+            SET front_text = EXCLUDED.front_text,
+            back_text = EXCLUDED.back_text,
+            deck_id = EXCLUDED.deck_id,
+            created = EXCLUDED.created,
+            modified = EXCLUDED.modified,
+            due = EXCLUDED.due,
+            interval_days = EXCLUDED.interval_days,
+            ease = EXCLUDED.ease,
+            lapses = EXCLUDED.lapses,
+            suspended = EXCLUDED.suspended,
+            note_id = EXCLUDED.note_id,
+            ordinal = EXCLUDED.ordinal,
+            hint = EXCLUDED.hint,
+            notes = EXCLUDED.notes,
+            source = EXCLUDED.source,
+            state = EXCLUDED.state,
+            step = EXCLUDED.step,
+            deleted = NULL
+            "#,
+            self.id,
+            self.front_text,
+            self.back_text,
+            self.deck_id,
+            self.created,
+            self.modified,
+            self.due,
+            self.interval_days,
+            self.ease,
+            self.lapses,
+            self.suspended,
+            self.note_id,
+            self.ordinal,
+            self.hint,
+            self.notes,
+            self.source,
+            self.state as CardState,
+            self.step,
+        )
+        .execute(connection_pool)
+        .await?;
+        Tag::set_for_card(connection_pool, self.id, &self.tags).await
+    }
+}
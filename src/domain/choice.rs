@@ -0,0 +1,119 @@
+use rand::{seq::SliceRandom, Rng};
+
+use super::answer::expected_answer;
+use super::card::Card;
+
+/// Options shown per multiple-choice question: the answer and up to three distractors
+pub const N_OPTIONS: usize = 4;
+
+/// A multiple-choice question: the card's answer shuffled in with distractors
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choices {
+    pub options: Vec<String>,
+    pub correct: usize,
+    pub picked: Option<usize>,
+}
+
+impl Choices {
+    /// Builds the options for `card`, drawing distractors from the answers of the other cards
+    /// in `pool` that share its deck. Answers close in length and sharing tags are preferred.
+    pub fn new(card: &Card, pool: &[Card], rng: &mut impl Rng) -> Self {
+        let answer = expected_answer(card);
+        let mut candidates: Vec<(usize, String)> = Vec::new();
+        for other in pool {
+            if other.id == card.id || other.deck_id != card.deck_id {
+                continue;
+            }
+            let text = expected_answer(other);
+            if text.trim().is_empty() || text == answer || candidates.iter().any(|(_, t)| *t == text) {
+                continue;
+            }
+            candidates.push((distance(card, &answer, other, &text), text));
+        }
+        // Shuffle first so equally good candidates are picked at random
+        candidates.shuffle(rng);
+        candidates.sort_by_key(|(distance, _)| *distance);
+
+        let mut options: Vec<String> = candidates
+            .into_iter()
+            .take(N_OPTIONS - 1)
+            .map(|(_, text)| text)
+            .collect();
+        let correct = rng.gen_range(0..=options.len());
+        options.insert(correct, answer);
+        Choices {
+            options,
+            correct,
+            picked: None,
+        }
+    }
+
+    /// Picks option `index` (0-based), if there is one and nothing was picked yet
+    pub fn pick(&mut self, index: usize) -> bool {
+        if self.picked.is_some() || index >= self.options.len() {
+            return false;
+        }
+        self.picked = Some(index);
+        true
+    }
+
+    pub fn is_correct(&self) -> Option<bool> {
+        self.picked.map(|picked| picked == self.correct)
+    }
+}
+
+/// How unlike `answer` a distractor is: the difference in length, less a bonus per shared tag
+fn distance(card: &Card, answer: &str, other: &Card, text: &str) -> usize {
+    let length = answer.chars().count().abs_diff(text.chars().count());
+    let shared_tags = card.tags.iter().filter(|tag| other.tags.contains(tag)).count();
+    (length + 10).saturating_sub(5 * shared_tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use uuid::Uuid;
+
+    fn card(deck_id: Uuid, back: &str, tags: &[&str]) -> Card {
+        Card {
+            back_text: Some(back.to_string()),
+            deck_id: Some(deck_id),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn test_distractors_come_from_same_deck() {
+        let (deck, other_deck) = (Uuid::new_v4(), Uuid::new_v4());
+        let question = card(deck, "cat", &["animal"]);
+        let pool = vec![
+            question.clone(),
+            card(deck, "dog", &["animal"]),
+            card(deck, "cow", &[]),
+            card(deck, "a very long definition of something", &[]),
+            card(deck, "dog", &[]),
+            card(deck, "pig", &["animal"]),
+            card(other_deck, "owl", &["animal"]),
+        ];
+        let choices = Choices::new(&question, &pool, &mut StdRng::seed_from_u64(7));
+        assert_eq!(N_OPTIONS, choices.options.len());
+        assert_eq!("cat", choices.options[choices.correct]);
+        let mut distractors: Vec<&String> = choices.options.iter().filter(|option| *option != "cat").collect();
+        distractors.sort();
+        assert_eq!(vec!["cow", "dog", "pig"], distractors);
+    }
+
+    #[test]
+    fn test_pick() {
+        let deck = Uuid::new_v4();
+        let question = card(deck, "cat", &[]);
+        let mut choices = Choices::new(&question, std::slice::from_ref(&question), &mut StdRng::seed_from_u64(1));
+        assert_eq!(vec!["cat".to_string()], choices.options);
+        assert!(!choices.pick(1));
+        assert!(choices.pick(0));
+        assert_eq!(Some(true), choices.is_correct());
+        assert!(!choices.pick(0));
+    }
+}
@@ -0,0 +1,152 @@
+/// Cloze deletions, e.g. `The {{c1::mitochondria}} is the {{c2::powerhouse::role}} of the cell`
+///
+/// Each cloze number becomes its own card: on its front that number's deletions are hidden as
+/// `[...]` (or `[hint]`), while the other numbers are shown as plain text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Cloze {
+        number: u32,
+        answer: String,
+        hint: Option<String>,
+    },
+}
+
+/// Splits `text` into plain text and cloze deletions; malformed markers are kept as text
+pub fn parse(text: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{c") {
+        plain.push_str(&rest[..start]);
+        let after = &rest[start..];
+        match parse_cloze(after) {
+            Some((segment, len)) => {
+                if !plain.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut plain)));
+                }
+                segments.push(segment);
+                rest = &after[len..];
+            }
+            None => {
+                plain.push_str("{{c");
+                rest = &after[3..];
+            }
+        }
+    }
+    plain.push_str(rest);
+    if !plain.is_empty() {
+        segments.push(Segment::Text(plain));
+    }
+    segments
+}
+
+/// Parses a `{{cN::answer}}` or `{{cN::answer::hint}}` at the start of `text`,
+/// returning it with its length in bytes
+fn parse_cloze(text: &str) -> Option<(Segment, usize)> {
+    let body = text.strip_prefix("{{c")?;
+    let end = body.find("}}")?;
+    let (number, content) = body[..end].split_once("::")?;
+    let number = number.parse::<u32>().ok().filter(|n| *n > 0)?;
+    let (answer, hint) = match content.split_once("::") {
+        Some((answer, hint)) => (answer, Some(hint.to_string())),
+        None => (content, None),
+    };
+    let segment = Segment::Cloze {
+        number,
+        answer: answer.to_string(),
+        hint,
+    };
+    Some((segment, "{{c".len() + end + "}}".len()))
+}
+
+/// The distinct cloze numbers in `text`, ascending
+pub fn numbers(text: &str) -> Vec<u32> {
+    let mut numbers: Vec<u32> = parse(text)
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Cloze { number, .. } => Some(*number),
+            Segment::Text(_) => None,
+        })
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+/// The question for cloze `number`
+pub fn render_front(text: &str, number: u32) -> String {
+    parse(text)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text,
+            Segment::Cloze { number: n, hint, .. } if n == number => {
+                format!("[{}]", hint.unwrap_or_else(|| "...".to_string()))
+            }
+            Segment::Cloze { answer, .. } => answer,
+        })
+        .collect()
+}
+
+/// The answer for cloze `number`, as (text, is the deletion being asked) pieces
+pub fn render_back(text: &str, number: u32) -> Vec<(String, bool)> {
+    parse(text)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => (text, false),
+            Segment::Cloze { number: n, answer, .. } => (answer, n == number),
+        })
+        .collect()
+}
+
+/// The answers of cloze `number`, in order
+pub fn answers(text: &str, number: u32) -> Vec<String> {
+    parse(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Cloze { number: n, answer, .. } if n == number => Some(answer),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "The {{c1::mitochondria}} is the {{c2::powerhouse::role}} of the {{c1::cell}}";
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(vec![1, 2], numbers(TEXT));
+        assert!(numbers("no clozes {{c0::here}} or {{c1:bad}}").is_empty());
+    }
+
+    #[test]
+    fn test_render_front_hides_only_its_number() {
+        assert_eq!("The [...] is the powerhouse of the [...]", render_front(TEXT, 1));
+        assert_eq!("The mitochondria is the [role] of the cell", render_front(TEXT, 2));
+    }
+
+    #[test]
+    fn test_render_back_marks_answer() {
+        let back = render_back(TEXT, 2);
+        assert_eq!(("powerhouse".to_string(), true), back[3]);
+        assert_eq!(("mitochondria".to_string(), false), back[1]);
+        assert_eq!(
+            "The mitochondria is the powerhouse of the cell",
+            back.iter().map(|(text, _)| text.as_str()).collect::<String>()
+        );
+    }
+
+    #[test]
+    fn test_answers() {
+        assert_eq!(vec!["mitochondria", "cell"], answers(TEXT, 1));
+        assert!(answers(TEXT, 3).is_empty());
+    }
+
+    #[test]
+    fn test_malformed_markers_are_text() {
+        assert_eq!(vec![Segment::Text("{{c1::open".to_string())], parse("{{c1::open"));
+    }
+}
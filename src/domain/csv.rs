@@ -0,0 +1,247 @@
+//! Decks as CSV, for `flashy export` and `flashy import`: one row per note, with a header
+//! naming the columns (see `COLUMNS`). Fields holding commas, quotes or line breaks are quoted.
+
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::card::Card;
+use super::deck::Deck;
+use super::note::{Note, NoteKind};
+use super::tag::Tag;
+
+/// Every column, in the order they are exported; only `front` is required on import
+pub const COLUMNS: [&str; 7] = ["front", "back", "hint", "notes", "source", "tags", "reverse"];
+
+/// A note as it is exported: cloze notes keep their source text, typed notes the text of
+/// their first card
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Row {
+    pub front: String,
+    pub back: String,
+    pub hint: Option<String>,
+    pub notes: Option<String>,
+    pub source: Option<String>,
+    pub tags: Vec<String>,
+    pub reverse: bool,
+}
+
+impl Row {
+    fn fields(&self) -> [String; 7] {
+        [
+            self.front.clone(),
+            self.back.clone(),
+            self.hint.clone().unwrap_or_default(),
+            self.notes.clone().unwrap_or_default(),
+            self.source.clone().unwrap_or_default(),
+            self.tags.join(" "),
+            self.reverse.to_string(),
+        ]
+    }
+}
+
+/// `rows` as CSV, header first
+pub fn to_csv(rows: &[Row]) -> String {
+    let mut csv = format!("{}\n", COLUMNS.join(","));
+    for row in rows {
+        let fields: Vec<String> = row.fields().iter().map(|field| quote(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn quote(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+/// Parses CSV whose header names some of `COLUMNS`, in any order
+pub fn parse(text: &str) -> Result<Vec<Row>, String> {
+    let mut records = records(text)?.into_iter();
+    let header = records.next().ok_or_else(|| "Empty CSV".to_string())?;
+    let mut columns: Vec<usize> = Vec::new();
+    for name in &header {
+        let column = COLUMNS
+            .iter()
+            .position(|column| column.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("Unknown column `{}`", name))?;
+        columns.push(column);
+    }
+    if !columns.contains(&0) {
+        return Err("Missing column `front`".to_string());
+    }
+
+    let mut rows = Vec::new();
+    for (line, record) in records.enumerate() {
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let mut row = Row::default();
+        for (column, field) in columns.iter().zip(record) {
+            let optional = Some(field.clone()).filter(|field| !field.trim().is_empty());
+            match column {
+                0 => row.front = field,
+                1 => row.back = field,
+                2 => row.hint = optional,
+                3 => row.notes = optional,
+                4 => row.source = optional,
+                5 => row.tags = Tag::parse_names(&field),
+                _ => {
+                    row.reverse = match field.trim() {
+                        "" | "false" => false,
+                        "true" => true,
+                        _ => return Err(format!("Row {}: `reverse` must be true or false", line + 1)),
+                    }
+                }
+            }
+        }
+        if row.front.trim().is_empty() {
+            return Err(format!("Row {}: `front` is empty", line + 1));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Splits `text` into records of fields, following RFC 4180 quoting
+fn records(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unclosed quote".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// The live cards of deck `deck`, one row per note
+pub async fn export_deck(db: &PgPool, deck: &str) -> Result<Vec<Row>, sqlx::Error> {
+    let deck = Deck::new_from_db(deck, db).await?;
+    let mut cards = deck.cards.unwrap_or_default();
+    cards.sort_by_key(|card| (card.created, card.ordinal));
+
+    let mut seen_notes: HashSet<Uuid> = HashSet::new();
+    let mut rows = Vec::new();
+    for card in cards {
+        if card.note_id.is_some_and(|note_id| !seen_notes.insert(note_id)) {
+            continue;
+        }
+        let note = match card.note_id {
+            Some(note_id) => Note::load(db, note_id).await?,
+            None => None,
+        };
+        let (front, back, reverse) = match note.filter(|note| note.kind != NoteKind::Typed) {
+            Some(note) => (note.front_text, note.back_text, note.reverse),
+            None => (card.front_text, card.back_text, false),
+        };
+        rows.push(Row {
+            front: front.unwrap_or_default(),
+            back: back.unwrap_or_default(),
+            hint: card.hint,
+            notes: card.notes,
+            source: card.source,
+            tags: card.tags,
+            reverse,
+        });
+    }
+    Ok(rows)
+}
+
+/// Adds a note for each of `rows` to deck `deck`, which is created if there is none.
+/// Returns the number of notes added.
+pub async fn import_deck(db: &PgPool, deck: &str, rows: &[Row], author: &str) -> Result<usize, sqlx::Error> {
+    let deck_id = match Deck::new_from_db(deck, db).await {
+        Ok(deck) => deck.id,
+        Err(sqlx::Error::RowNotFound) => {
+            let new_deck = Deck::new(deck);
+            new_deck.save_to_db(db).await?;
+            new_deck.id
+        }
+        Err(e) => return Err(e),
+    };
+    for row in rows {
+        let mut card = Card {
+            front_text: Some(row.front.clone()),
+            back_text: Some(row.back.clone()),
+            hint: row.hint.clone(),
+            notes: row.notes.clone(),
+            source: row.source.clone(),
+            tags: row.tags.clone(),
+            ..Card::new_with_deck(deck_id)
+        };
+        Note::save_with_card(db, &mut card, Some(row.reverse), author).await?;
+        Tag::set_for_card(db, card.id, &card.tags).await?;
+    }
+    Ok(rows.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let rows = vec![
+            Row {
+                front: "hola, \"amigo\"".to_string(),
+                back: "hello\nfriend".to_string(),
+                hint: Some("h...".to_string()),
+                notes: Some("informal".to_string()),
+                source: Some("ch. 1".to_string()),
+                tags: vec!["spanish".to_string(), "greetings".to_string()],
+                reverse: true,
+            },
+            Row {
+                front: "{{c1::Paris}} is in France".to_string(),
+                ..Row::default()
+            },
+        ];
+        let csv = to_csv(&rows);
+        assert!(csv.starts_with("front,back,hint,notes,source,tags,reverse\n\"hola, \"\"amigo\"\"\",\"hello\nfriend\""));
+        assert_eq!(Ok(rows), parse(&csv));
+    }
+
+    #[test]
+    fn test_parse_columns_in_any_order() {
+        let rows = parse("Back,front\r\nhello,hola\r\n\r\n").unwrap();
+        assert_eq!(
+            vec![Row {
+                front: "hola".to_string(),
+                back: "hello".to_string(),
+                ..Row::default()
+            }],
+            rows
+        );
+        assert!(parse("back\nhello\n").is_err());
+        assert!(parse("front,colour\nhola,red\n").is_err());
+        assert!(parse("front\n\"hola\n").is_err());
+    }
+}
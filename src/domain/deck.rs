@@ -1,13 +1,49 @@
-use sqlx::{types::chrono::Utc, PgPool};
+use sqlx::{types::chrono::{NaiveDate, Utc}, PgPool};
 use uuid::Uuid;
 use super::deckset::RawDeck;
 use super::card::Card;
+use super::note::Note;
+use super::limits::{DeckLimits, Interleave, NewOrder};
+use super::preset::{Preset, ReviewMode};
+use super::scheduler::CardState;
+use super::tag_expr::TagExpr;
+
+/// A deck restored from the trash under a new name, because a live deck had taken its name
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenamedDeck {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+impl RenamedDeck {
+    /// Alert text for the decks renamed by a restore, if any
+    pub fn describe(renamed: &[RenamedDeck]) -> Option<String> {
+        let names: Vec<String> = renamed
+            .iter()
+            .map(|deck| format!("'{}' as '{}'", deck.old_name, deck.new_name))
+            .collect();
+        (!names.is_empty()).then(|| format!("Name taken, restored deck {}", names.join(", ")))
+    }
+}
+
+/// The `n`th name tried for a restored deck whose name is taken, e.g. "spanish (restored 2)"
+fn restored_name(name: &str, n: u32) -> String {
+    match n {
+        1 => format!("{} (restored)", name),
+        _ => format!("{} (restored {})", name, n),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Deck {
     pub id: Uuid,
     pub name: String,
     pub cards: Option<Vec<Card>>,
+    pub reverse_by_default: bool, // new cards also get a reverse card
+    pub type_answer: bool,        // reviews require typing the answer
+    pub exam_date: Option<NaiveDate>, // studied towards an exam on this day
+    pub limits: DeckLimits,
+    pub preset: Option<Preset>, // shared options overriding the deck's own
 }
 
 impl From<&RawDeck> for Deck {
@@ -18,6 +54,16 @@ impl From<&RawDeck> for Deck {
             id: value.id,
             name: value.name.clone(),
             cards: None,
+            reverse_by_default: value.reverse_by_default,
+            type_answer: value.type_answer,
+            exam_date: value.exam_date,
+            limits: DeckLimits {
+                new_per_day: value.new_per_day.map(|n| n.max(0) as u32),
+                reviews_per_day: value.reviews_per_day.map(|n| n.max(0) as u32),
+                new_order: NewOrder::parse(&value.new_order).unwrap_or_default(),
+                interleave: Interleave::parse(&value.interleave).unwrap_or_default(),
+            },
+            preset: None,
         }
     }
 }
@@ -30,6 +76,11 @@ impl Default for Deck {
             id,
             name: "default".to_string(),
             cards: None,
+            reverse_by_default: false,
+            type_answer: false,
+            exam_date: None,
+            limits: DeckLimits::default(),
+            preset: None,
         }
     }
 }
@@ -40,16 +91,46 @@ impl Deck {
         Deck {
             id,
             name: name.to_string(),
-            cards: None
+            cards: None,
+            reverse_by_default: false,
+            type_answer: false,
+            exam_date: None,
+            limits: DeckLimits::default(),
+            preset: None,
         }
     }
 
+    /// Takes the options of `preset`, which the deck is linked to
+    pub fn link(&mut self, preset: Preset) {
+        self.limits = preset.limits;
+        self.type_answer = preset.review_mode == ReviewMode::Type;
+        self.preset = Some(preset);
+    }
+
+    /// The deck's options: those of its preset, or the defaults with its own limits
+    pub fn options(&self) -> Preset {
+        self.preset.clone().unwrap_or_else(|| Preset {
+            limits: self.limits,
+            review_mode: if self.type_answer { ReviewMode::Type } else { ReviewMode::Reveal },
+            ..Preset::new(&self.name)
+        })
+    }
+
     pub async fn load_cards(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
         let cards: Vec<Card> = sqlx::query_as!(
             Card,
             r#"
-            SELECT * FROM cards
-            WHERE deck_id = $1
+            SELECT id, front_text, back_text, deck_id, created, modified,
+                ARRAY(
+                    SELECT t.name FROM tags t
+                    JOIN card_tags ct ON ct.tag_id = t.id
+                    WHERE ct.card_id = cards.id
+                    ORDER BY t.name
+                ) AS "tags!",
+                due, interval_days, ease, lapses, suspended, note_id, ordinal, hint, notes, source,
+                state AS "state: CardState", step
+            FROM cards
+            WHERE deck_id = $1 AND deleted IS NULL
             "#,
             self.id,
         )
@@ -67,7 +148,7 @@ impl Deck {
         let id: Uuid = sqlx::query_scalar::<_, Uuid>(
             r#"
             SELECT id FROM decks
-            WHERE name = ($1)
+            WHERE name = ($1) AND deleted IS NULL
             "#,
         )
         .bind(name)
@@ -78,6 +159,11 @@ impl Deck {
             id,
             name: name.to_string(),
             cards: None,
+            reverse_by_default: false,
+            type_answer: false,
+            exam_date: None,
+            limits: DeckLimits::default(),
+            preset: None,
         };
 
         deck.load_cards(db).await.expect("failed to load cards for deck");
@@ -107,6 +193,203 @@ impl Deck {
         Ok(())
     }
 
+    pub async fn set_reverse_by_default(db: &PgPool, deck_id: Uuid, reverse: bool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET reverse_by_default = $2, modified = $3
+            WHERE id = $1
+            "#,
+            deck_id,
+            reverse,
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_type_answer(db: &PgPool, deck_id: Uuid, type_answer: bool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET type_answer = $2, modified = $3
+            WHERE id = $1
+            "#,
+            deck_id,
+            type_answer,
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Sets the exam date the deck is studied towards; `None` turns exam mode off
+    pub async fn set_exam_date(db: &PgPool, deck_id: Uuid, exam_date: Option<NaiveDate>) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET exam_date = $2, modified = $3
+            WHERE id = $1
+            "#,
+            deck_id,
+            exam_date,
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_limits(db: &PgPool, deck_id: Uuid, limits: &DeckLimits) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET new_per_day = $2, reviews_per_day = $3, new_order = $4, interleave = $5, modified = $6
+            WHERE id = $1
+            "#,
+            deck_id,
+            limits.new_per_day.map(|n| n as i32),
+            limits.reviews_per_day.map(|n| n as i32),
+            limits.new_order.as_str(),
+            limits.interleave.as_str(),
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Links deck `deck_id` to preset `preset_id`, or unlinks it
+    pub async fn set_preset(db: &PgPool, deck_id: Uuid, preset_id: Option<Uuid>) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET preset_id = $2, modified = $3
+            WHERE id = $1
+            "#,
+            deck_id,
+            preset_id,
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Moves deck `deck_id` and its cards to the trash.
+    /// Its cards share the deck's `deleted` time, so they can be restored together.
+    pub async fn delete_with_cards(db: &PgPool, deck_id: Uuid) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        // TRASH ALL CARDS IN DECK
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET deleted = $2
+            WHERE deck_id = ($1) AND deleted IS NULL
+            "#,
+            deck_id,
+            now,
+        ).execute(db)
+        .await?;
+
+        // TRASH DECK
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET deleted = $2
+            WHERE id = ($1)
+            "#,
+            deck_id,
+            now,
+        ).execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Takes deck `deck_id` out of the trash along with the cards trashed with it,
+    /// renaming it if a live deck has taken its name since
+    pub async fn restore_from_trash(db: &PgPool, deck_id: Uuid) -> Result<Vec<RenamedDeck>, sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET deleted = NULL
+            FROM decks
+            WHERE cards.deck_id = decks.id
+            AND decks.id = $1
+            AND cards.deleted = decks.deleted
+            "#,
+            deck_id
+        ).execute(db)
+        .await?;
+
+        Ok(Deck::untrash(db, deck_id).await?.into_iter().collect())
+    }
+
+    /// Clears `deleted` on deck `deck_id`. Deck names are only unique among live decks, so if
+    /// another deck now has its name the restored deck gets the first free "<name> (restored)".
+    pub(crate) async fn untrash(db: &PgPool, deck_id: Uuid) -> Result<Option<RenamedDeck>, sqlx::Error> {
+        let name = sqlx::query_scalar!("SELECT name FROM decks WHERE id = $1", deck_id)
+            .fetch_one(db)
+            .await?;
+        let mut new_name = name.clone();
+        let mut n = 0;
+        while Deck::live_name_taken(db, deck_id, &new_name).await? {
+            n += 1;
+            new_name = restored_name(&name, n);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE decks
+            SET deleted = NULL, name = $2
+            WHERE id = $1
+            "#,
+            deck_id,
+            new_name,
+        ).execute(db)
+        .await?;
+
+        Ok((new_name != name).then_some(RenamedDeck { old_name: name, new_name }))
+    }
+
+    async fn live_name_taken(db: &PgPool, deck_id: Uuid, name: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM decks WHERE name = $1 AND id != $2 AND deleted IS NULL) AS "taken!"
+            "#,
+            name,
+            deck_id,
+        )
+        .fetch_one(db)
+        .await
+    }
+
+    /// Permanently deletes deck `deck_id` and every card in it, and the notes left without cards
+    pub async fn purge(db: &PgPool, deck_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM cards
+            WHERE deck_id = ($1)
+            "#,
+            deck_id
+        ).execute(db)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM decks
+            WHERE id = ($1)
+            "#,
+            deck_id
+        ).execute(db)
+        .await?;
+
+        Note::delete_orphans(db).await
+    }
+
     // TODO: fix this
     // pub fn iter(&self) -> impl Iterator<Item = &RawCard> {
     //     self.cards.iter()
@@ -119,11 +402,43 @@ impl Deck {
                 Ok(())
             },
             // TODO: fix error variant
-            None => Err(std::io::Error::new(std::io::ErrorKind::Other, "No cards available")),
+            None => Err(std::io::Error::other("No cards available")),
         }
     }
 
     pub fn cards_iter(&self) -> Option<impl Iterator<Item = Card>> {
         self.cards.clone().map(|cards| cards.into_iter())
     }
+
+    /// Cards whose tags satisfy `filter`, or every loaded card if there is no filter
+    pub fn filtered_cards(&self, filter: Option<&TagExpr>) -> Vec<Card> {
+        match &self.cards {
+            Some(cards) => cards
+                .iter()
+                .filter(|card| filter.is_none_or(|expr| expr.matches(&card.tags)))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restored_name() {
+        assert_eq!("spanish (restored)", restored_name("spanish", 1));
+        assert_eq!("spanish (restored 3)", restored_name("spanish", 3));
+        let renamed = vec![RenamedDeck {
+            old_name: "spanish".to_string(),
+            new_name: "spanish (restored)".to_string(),
+        }];
+        assert_eq!(
+            Some("Name taken, restored deck 'spanish' as 'spanish (restored)'".to_string()),
+            RenamedDeck::describe(&renamed)
+        );
+        assert_eq!(None, RenamedDeck::describe(&[]));
+    }
 }
@@ -1,8 +1,9 @@
 
-use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use sqlx::{types::chrono::{DateTime, NaiveDate, Utc}, PgPool};
 use uuid::Uuid;
 
 use super::deck::Deck;
+use super::preset::Preset;
 
 
 
@@ -18,6 +19,14 @@ pub struct RawDeck {
     pub name: String,
     pub created: Option<DateTime<Utc>>,
     pub modified: Option<DateTime<Utc>>,
+    pub reverse_by_default: bool,
+    pub type_answer: bool,
+    pub exam_date: Option<NaiveDate>,
+    pub new_per_day: Option<i32>,
+    pub reviews_per_day: Option<i32>,
+    pub new_order: String,
+    pub interleave: String,
+    pub preset_id: Option<Uuid>,
 }
 
 
@@ -31,13 +40,16 @@ impl DeckSet {
         let raw: Vec<RawDeck> = sqlx::query_as!(
             RawDeck,
             r#"
-            SELECT * FROM decks
+            SELECT id, name, created, modified, reverse_by_default, type_answer, exam_date,
+                new_per_day, reviews_per_day, new_order, interleave, preset_id
+            FROM decks
+            WHERE deleted IS NULL
             "#,
         )
         .fetch_all(db)
         .await?;
 
-        let decks: Vec<Deck> = raw.iter().map(Deck::from).collect();
+        let decks: Vec<Deck> = link_presets(db, &raw).await?;
 
         Ok(
             DeckSet {
@@ -54,39 +66,38 @@ impl DeckSet {
         let raw: Vec<RawDeck> = sqlx::query_as!(
             RawDeck,
             r#"
-            SELECT * FROM decks
+            SELECT id, name, created, modified, reverse_by_default, type_answer, exam_date,
+                new_per_day, reviews_per_day, new_order, interleave, preset_id
+            FROM decks
+            WHERE deleted IS NULL
             "#,
         )
         .fetch_all(db)
         .await?;
 
-        self.decks = raw.iter().map(Deck::from).collect();
+        self.decks = link_presets(db, &raw).await?;
         Ok(())
     }
 
     pub async fn delete_deck_with_cards(&mut self, db: &PgPool, deck_id: Uuid) -> Result<(), sqlx::Error> {
-        // DELETE ALL CARDS IN DECK
-        sqlx::query!(
-            r#"
-            DELETE FROM cards
-            WHERE deck_id = ($1)
-            "#,
-            deck_id
-        ).execute(db)
-        .await?;
-
-        // DELETE DECK
-        sqlx::query!(
-            r#"
-            DELETE FROM decks
-            WHERE id = ($1)
-            "#,
-            deck_id
-        ).execute(db)
-        .await?;
-
+        Deck::delete_with_cards(db, deck_id).await?;
         self.reload(db).await?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Decks from `raw`, with the options of the presets they are linked to
+async fn link_presets(db: &PgPool, raw: &[RawDeck]) -> Result<Vec<Deck>, sqlx::Error> {
+    let presets = Preset::load_all(db).await?;
+    Ok(raw
+        .iter()
+        .map(|raw| {
+            let mut deck = Deck::from(raw);
+            if let Some(preset) = presets.iter().find(|preset| Some(preset.id) == raw.preset_id) {
+                deck.link(preset.clone());
+            }
+            deck
+        })
+        .collect())
+}
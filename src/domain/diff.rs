@@ -0,0 +1,74 @@
+/// One step of an edit script turning `old` into `new`
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffOp<T> {
+    Same(T),
+    Added(T),
+    Removed(T),
+}
+
+/// Longest-common-subsequence diff; where both sides change, removals come before additions
+pub fn diff<T: PartialEq + Clone>(old: &[T], new: &[T]) -> Vec<DiffOp<T>> {
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<DiffOp<T>> = Vec::new();
+    let (mut i, mut j) = (0usize, 0usize);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Same(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(DiffOp::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().cloned().map(DiffOp::Removed));
+    ops.extend(new[j..].iter().cloned().map(DiffOp::Added));
+    ops
+}
+
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffOp<String>> {
+    let old: Vec<String> = old.lines().map(String::from).collect();
+    let new: Vec<String> = new.lines().map(String::from).collect();
+    diff(&old, &new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let ops = line_diff("hola\nadios\ngracias", "hola\nchao\ngracias\nde nada");
+        assert_eq!(
+            vec![
+                DiffOp::Same("hola".to_string()),
+                DiffOp::Removed("adios".to_string()),
+                DiffOp::Added("chao".to_string()),
+                DiffOp::Same("gracias".to_string()),
+                DiffOp::Added("de nada".to_string()),
+            ],
+            ops
+        );
+    }
+
+    #[test]
+    fn test_diff_of_empty() {
+        assert_eq!(vec![DiffOp::Added('a')], diff(&[], &['a']));
+        assert_eq!(vec![DiffOp::Removed('a')], diff(&['a'], &[]));
+        assert!(diff::<char>(&[], &[]).is_empty());
+    }
+}
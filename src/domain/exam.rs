@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::activity::{day_of, start_of};
+use super::card::Card;
+
+/// What a deck with an exam date still needs before the exam
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExamPlan {
+    pub exam_date: NaiveDate,
+    /// Study days left before the exam, including today; 0 on the day itself
+    pub days_left: i64,
+    /// Cards not reviewed yet
+    pub new_cards: u64,
+    /// Cards reviewed for the first time today
+    pub introduced_today: u64,
+    /// Cards reviewed before (including those introduced today)
+    pub reviewed_cards: u64,
+}
+
+impl ExamPlan {
+    /// Plans for every deck with an exam date, by deck; trashed and suspended cards are left out
+    pub async fn load_all(db: &PgPool, rollover_hour: u32) -> Result<HashMap<Uuid, ExamPlan>, sqlx::Error> {
+        let today = day_of(Utc::now(), rollover_hour);
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                decks.id,
+                decks.exam_date AS "exam_date!",
+                COUNT(cards.id) FILTER (WHERE cards.due IS NULL) AS "new_cards!",
+                COUNT(cards.id) FILTER (WHERE cards.due IS NOT NULL) AS "reviewed_cards!",
+                COUNT(cards.id) FILTER (
+                    WHERE cards.due IS NOT NULL
+                    AND (SELECT MIN(views.time) FROM views WHERE views.card_id = cards.id) >= $1
+                ) AS "introduced_today!"
+            FROM decks
+            LEFT JOIN cards ON cards.deck_id = decks.id AND cards.deleted IS NULL AND NOT cards.suspended
+            WHERE decks.deleted IS NULL AND decks.exam_date IS NOT NULL
+            GROUP BY decks.id
+            "#,
+            start_of(today, rollover_hour),
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let plan = ExamPlan {
+                    exam_date: row.exam_date,
+                    days_left: (row.exam_date - today).num_days(),
+                    new_cards: row.new_cards as u64,
+                    introduced_today: row.introduced_today as u64,
+                    reviewed_cards: row.reviewed_cards as u64,
+                };
+                (row.id, plan)
+            })
+            .collect())
+    }
+
+    /// Whether there are study days left before the exam
+    pub fn is_active(&self) -> bool {
+        self.days_left > 0
+    }
+
+    /// New cards to introduce per day so they are all seen in time, counting the ones
+    /// introduced today
+    fn new_per_day(&self) -> u64 {
+        (self.new_cards + self.introduced_today).div_ceil(self.days_left.max(1) as u64)
+    }
+
+    /// New cards that may still be introduced today; `None` once the exam is here
+    pub fn new_quota(&self) -> Option<u64> {
+        self.is_active()
+            .then(|| self.new_per_day().saturating_sub(self.introduced_today))
+    }
+
+    /// Reviews needed a day to introduce every new card and see every other card once more
+    /// before the exam; a lower bound, since new cards come back more than once
+    pub fn daily_load(&self) -> Option<u64> {
+        let reviewed = self.reviewed_cards.saturating_sub(self.introduced_today);
+        self.is_active()
+            .then(|| self.new_per_day() + reviewed.div_ceil(self.days_left as u64))
+    }
+
+    /// e.g. " 📅 exam in 12d, ~85/day", flagged with ⚠ when the load is over `load_warning`
+    pub fn summary(&self, load_warning: u64) -> String {
+        match self.daily_load() {
+            Some(load) if load > load_warning => {
+                format!(" ⚠ exam in {}d needs ~{}/day", self.days_left, load)
+            }
+            Some(load) => format!(" 📅 exam in {}d, ~{}/day", self.days_left, load),
+            None if self.days_left == 0 => " 📅 exam today".to_string(),
+            None => " 📅 exam passed".to_string(),
+        }
+    }
+}
+
+/// Holds back the new cards of exam decks beyond what they need today, keeping the cards' order
+pub fn spread_new_cards(cards: Vec<Card>, plans: &HashMap<Uuid, ExamPlan>) -> Vec<Card> {
+    let mut quotas: HashMap<Uuid, u64> = plans
+        .iter()
+        .filter_map(|(deck_id, plan)| Some((*deck_id, plan.new_quota()?)))
+        .collect();
+    cards
+        .into_iter()
+        .filter(|card| {
+            if card.due.is_some() {
+                return true;
+            }
+            match card.deck_id.and_then(|deck_id| quotas.get_mut(&deck_id)) {
+                Some(0) => false,
+                Some(quota) => {
+                    *quota -= 1;
+                    true
+                }
+                None => true,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(days_left: i64, new_cards: u64, introduced_today: u64, reviewed_cards: u64) -> ExamPlan {
+        ExamPlan {
+            exam_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+            days_left,
+            new_cards,
+            introduced_today,
+            reviewed_cards,
+        }
+    }
+
+    #[test]
+    fn test_new_cards_are_spread_until_exam() {
+        assert_eq!(Some(10), plan(10, 100, 0, 0).new_quota());
+        // Already introduced today count towards today's share
+        assert_eq!(Some(6), plan(10, 96, 4, 4).new_quota());
+        assert_eq!(Some(0), plan(10, 90, 10, 10).new_quota());
+        assert_eq!(None, plan(0, 100, 0, 0).new_quota());
+        assert_eq!(Some(10 + 5), plan(10, 100, 0, 50).daily_load());
+        assert_eq!(None, plan(-3, 100, 0, 50).daily_load());
+        assert_eq!(" 📅 exam in 10d, ~15/day", plan(10, 100, 0, 50).summary(15));
+        assert_eq!(" ⚠ exam in 10d needs ~15/day", plan(10, 100, 0, 50).summary(14));
+    }
+
+    #[test]
+    fn test_spread_keeps_reviews_and_other_decks() {
+        let exam_deck = Uuid::new_v4();
+        let card = |deck_id: Uuid, reviewed: bool| Card {
+            deck_id: Some(deck_id),
+            due: reviewed.then(Utc::now),
+            ..Card::default()
+        };
+        let cards = vec![
+            card(exam_deck, false),
+            card(exam_deck, true),
+            card(exam_deck, false),
+            card(Uuid::new_v4(), false),
+        ];
+        let plans = HashMap::from([(exam_deck, plan(10, 10, 0, 1))]);
+        let kept = spread_new_cards(cards.clone(), &plans);
+        let kept_ids: Vec<Uuid> = kept.iter().map(|card| card.id).collect();
+        assert_eq!(vec![cards[0].id, cards[1].id, cards[3].id], kept_ids);
+    }
+}
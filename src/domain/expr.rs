@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+
+/// Longest expression accepted, in characters
+const MAX_LENGTH: usize = 200;
+/// Deepest nesting of parentheses, calls and unary minus accepted
+const MAX_DEPTH: usize = 32;
+/// Widest range `rand` draws from
+const MAX_RAND_SPAN: f64 = 1e9;
+
+/// Arithmetic expression in a parameterised card, e.g. `rand(2, 12)` or `round(c * 9/5 + 32, 1)`
+///
+/// Grammar (`×` and `÷` may be used for `*` and `/`):
+///     expr  := term ( ("+" | "-") term )*
+///     term  := unary ( ("*" | "/" | "%") unary )*
+///     unary := "-" unary | power
+///     power := atom ( "^" unary )?
+///     atom  := NUMBER | NAME | NAME "(" expr ( "," expr )* ")" | "(" expr ")"
+///
+/// Evaluation is sandboxed: it can only read the variables it is given and draw from the
+/// random number generator, expressions are bounded in length and depth, and a result
+/// that isn't a finite number is an error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(char),
+}
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if input.chars().count() > MAX_LENGTH {
+            return Err(format!("Expression longer than {} characters", MAX_LENGTH));
+        }
+        let tokens = tokenise(input)?;
+        if tokens.is_empty() {
+            return Err("Empty expression".to_string());
+        }
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let expr = parser.expr()?;
+        match parser.tokens.get(parser.pos) {
+            Some(token) => Err(format!("Unexpected {:?} in expression", token)),
+            None => Ok(expr),
+        }
+    }
+
+    pub fn eval(&self, vars: &HashMap<String, f64>, rng: &mut impl Rng) -> Result<f64, String> {
+        let value = match self {
+            Expr::Number(n) => *n,
+            Expr::Var(name) => *vars.get(name).ok_or_else(|| format!("Unknown variable `{}`", name))?,
+            Expr::Neg(inner) => -inner.eval(vars, rng)?,
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(vars, rng)?, rhs.eval(vars, rng)?);
+                match op {
+                    '+' => lhs + rhs,
+                    '-' => lhs - rhs,
+                    '*' => lhs * rhs,
+                    '/' if rhs == 0.0 => return Err("Division by zero".to_string()),
+                    '/' => lhs / rhs,
+                    '%' if rhs == 0.0 => return Err("Division by zero".to_string()),
+                    '%' => lhs.rem_euclid(rhs),
+                    _ => lhs.powf(rhs),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(vars, rng))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                call(name, &args, rng)?
+            }
+        };
+        match value.is_finite() {
+            true => Ok(value),
+            false => Err("Result is not a finite number".to_string()),
+        }
+    }
+}
+
+fn call(name: &str, args: &[f64], rng: &mut impl Rng) -> Result<f64, String> {
+    let value = match (name, args) {
+        // A whole number from `lo` to `hi`, inclusive
+        ("rand", [lo, hi]) => {
+            let (lo, hi) = (lo.ceil(), hi.floor());
+            if lo > hi || hi - lo > MAX_RAND_SPAN {
+                return Err(format!("Invalid range for rand({}, {})", lo, hi));
+            }
+            rng.gen_range(lo as i64..=hi as i64) as f64
+        }
+        ("choice", [_, ..]) => *args.choose(rng).expect("not empty"),
+        ("round", [x]) => x.round(),
+        ("round", [x, digits]) if (0.0..=12.0).contains(digits) => {
+            let factor = 10f64.powi(*digits as i32);
+            (x * factor).round() / factor
+        }
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("abs", [x]) => x.abs(),
+        ("sqrt", [x]) if *x >= 0.0 => x.sqrt(),
+        ("min", [_, ..]) => args.iter().copied().fold(f64::INFINITY, f64::min),
+        ("max", [_, ..]) => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        _ => return Err(format!("Invalid call to `{}` with {} argument(s)", name, args.len())),
+    };
+    Ok(value)
+}
+
+fn tokenise(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(c);
+                chars.next();
+            }
+            let value = number.parse().map_err(|_| format!("Invalid number `{}`", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else {
+            let op = match c {
+                '×' => '*',
+                '÷' => '/',
+                '+' | '-' | '*' | '/' | '%' | '^' | '(' | ')' | ',' => c,
+                _ => return Err(format!("Unexpected `{}` in expression", c)),
+            };
+            tokens.push(Token::Op(op));
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(*op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        match self.peek_op() {
+            Some(found) if found == op => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(format!("Expected `{}` in expression", op)),
+        }
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("Expression nested too deeply".to_string());
+        }
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek_op() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek_op() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek_op() == Some('-') {
+            self.pos += 1;
+            return self.nested(|parser| Ok(Expr::Neg(Box::new(parser.unary()?))));
+        }
+        let base = self.atom()?;
+        if self.peek_op() == Some('^') {
+            self.pos += 1;
+            let exponent = self.nested(Self::unary)?;
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Expr::Number(n))
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 1;
+                if self.peek_op() != Some('(') {
+                    return Ok(Expr::Var(name));
+                }
+                self.pos += 1;
+                let args = self.nested(|parser| {
+                    let mut args = vec![parser.expr()?];
+                    while parser.peek_op() == Some(',') {
+                        parser.pos += 1;
+                        args.push(parser.expr()?);
+                    }
+                    Ok(args)
+                })?;
+                self.expect(')')?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Op('(')) => {
+                self.pos += 1;
+                let inner = self.nested(Self::expr)?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(token) => Err(format!("Unexpected {:?} in expression", token)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn eval(input: &str, vars: &[(&str, f64)]) -> Result<f64, String> {
+        let vars = vars.iter().map(|(name, value)| (name.to_string(), *value)).collect();
+        Expr::parse(input)?.eval(&vars, &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn test_precedence_and_functions() {
+        assert_eq!(Ok(14.0), eval("2 + 3 × 4", &[]));
+        assert_eq!(Ok(-8.0), eval("-2^3", &[]));
+        assert_eq!(Ok(1.0), eval("-7 % 4", &[]));
+        assert_eq!(Ok(42.0), eval("a*b", &[("a", 6.0), ("b", 7.0)]));
+        assert_eq!(Ok(98.6), eval("round(c * 9/5 + 32, 1)", &[("c", 37.0)]));
+        assert_eq!(Ok(3.0), eval("max(1, 3, 2)", &[]));
+    }
+
+    #[test]
+    fn test_rand_stays_in_range() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let expr = Expr::parse("rand(2, 12)").unwrap();
+        for _ in 0..100 {
+            let value = expr.eval(&HashMap::new(), &mut rng).unwrap();
+            assert!((2.0..=12.0).contains(&value) && value.fract() == 0.0);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsafe_input() {
+        assert!(eval("1/0", &[]).is_err());
+        assert!(eval("10^400", &[]).is_err());
+        assert!(eval("x + 1", &[]).is_err());
+        assert!(eval("rand(12, 2)", &[]).is_err());
+        assert!(eval("system(1)", &[]).is_err());
+        assert!(Expr::parse(&"(".repeat(40)).is_err());
+        assert!(Expr::parse(&"1+".repeat(150)).is_err());
+        assert!(Expr::parse("c1::Paris").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{
+    card::Card,
+    deck::{Deck, RenamedDeck},
+    note::Note,
+    revision::{CardRevision, RevisionSource},
+    tag::Tag,
+    view::View,
+};
+
+/// Maximum number of commands kept for undo
+pub const HISTORY_CAPACITY: usize = 100;
+
+/// A reversible mutation, holding enough state to undo and redo it
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum Command {
+    /// A new card, with the note it was created with and the cards generated alongside it
+    CreateCard { card: Card, note: Option<Note>, siblings: Vec<Card> },
+    /// An edited card, with its note as it was before and after the edit
+    EditCard { before: Card, after: Card, before_note: Option<Note>, after_note: Option<Note> },
+    // Deletions move to the trash, so undoing one only needs to know what was trashed
+    DeleteCards(Vec<Uuid>),
+    DeleteDeck(Deck),
+    MoveCards { before: Vec<Card>, deck_id: Uuid, deck_name: String },
+    GradeReview { view: View, before: Card, after: Card },
+}
+
+impl Command {
+    /// Short description, e.g. "delete 3 card(s)"
+    pub fn description(&self) -> String {
+        match self {
+            Command::CreateCard { card, .. } => format!("create card '{}'", front_preview(card)),
+            Command::EditCard { after, .. } => format!("edit card '{}'", front_preview(after)),
+            Command::DeleteCards(card_ids) => format!("delete {} card(s)", card_ids.len()),
+            Command::DeleteDeck(deck) => format!("delete deck '{}'", deck.name),
+            Command::MoveCards { before, deck_name, .. } => {
+                format!("move {} card(s) to '{}'", before.len(), deck_name)
+            }
+            Command::GradeReview { view, before, .. } => format!(
+                "grade '{}' as {}",
+                front_preview(before),
+                if view.result == Some(true) { "correct" } else { "incorrect" }
+            ),
+        }
+    }
+
+    /// Reverts the command, returning any trashed deck that had to be restored under a new name.
+    /// `author` is recorded on revisions of cards whose text changes.
+    pub async fn undo(&self, db: &PgPool, author: &str) -> Result<Vec<RenamedDeck>, sqlx::Error> {
+        match self {
+            Command::CreateCard { card, siblings, .. } => {
+                // Purging every card of the note deletes the note too
+                let mut card_ids = vec![card.id];
+                card_ids.extend(siblings.iter().map(|sibling| sibling.id));
+                Card::purge_many(db, &card_ids).await?;
+            }
+            Command::EditCard { before, before_note, .. } => {
+                restore_with_note(db, before, before_note.as_ref(), author).await?
+            }
+            Command::DeleteCards(card_ids) => return Card::restore_from_trash(db, card_ids).await,
+            Command::DeleteDeck(deck) => return Deck::restore_from_trash(db, deck.id).await,
+            Command::MoveCards { before, .. } => {
+                for card in before {
+                    card.restore(db).await?;
+                }
+            }
+            Command::GradeReview { view, before, .. } => {
+                View::delete_from_db(db, view.id).await?;
+                before.restore(db).await?;
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    pub async fn redo(&self, db: &PgPool, author: &str) -> Result<(), sqlx::Error> {
+        match self {
+            Command::CreateCard { card, note, siblings } => {
+                if let Some(note) = note {
+                    note.save(db).await?;
+                }
+                for card in std::iter::once(card).chain(siblings) {
+                    card.restore(db).await?;
+                    Tag::set_for_card(db, card.id, &card.tags).await?;
+                }
+                Ok(())
+            }
+            Command::EditCard { after, after_note, .. } => {
+                restore_with_note(db, after, after_note.as_ref(), author).await
+            }
+            Command::DeleteCards(card_ids) => Card::delete_many_from_db(db, card_ids).await,
+            Command::DeleteDeck(deck) => Deck::delete_with_cards(db, deck.id).await,
+            Command::MoveCards { before, deck_id, .. } => {
+                let card_ids: Vec<Uuid> = before.iter().map(|card| card.id).collect();
+                Card::move_to_deck(db, &card_ids, *deck_id).await
+            }
+            Command::GradeReview { view, after, .. } => {
+                view.save(db).await?;
+                after.restore(db).await
+            }
+        }
+    }
+}
+
+/// Restores an edited card and its note, and brings the other cards of the note back in sync.
+/// The text being replaced is kept as a revision, as for any other edit.
+async fn restore_with_note(db: &PgPool, card: &Card, note: Option<&Note>, author: &str) -> Result<(), sqlx::Error> {
+    if let Some(current) = Card::load_many(db, &[card.id]).await?.pop() {
+        CardRevision::record(db, &current, card, RevisionSource::Tui, author).await?;
+    }
+    card.restore(db).await?;
+    match note {
+        Some(note) => note.restore_for_card(db, &mut card.clone(), author).await,
+        None => Ok(()),
+    }
+}
+
+fn front_preview(card: &Card) -> String {
+    let front = card.front_text.clone().unwrap_or_default().replace('\n', " ");
+    match front.char_indices().nth(20) {
+        Some((i, _)) => format!("{}…", &front[..i]),
+        None => front,
+    }
+}
+
+/// Session-scoped undo/redo stacks, capped at `capacity` commands
+#[derive(Debug)]
+pub struct History {
+    undo_stack: VecDeque<Command>,
+    redo_stack: Vec<Command>,
+    capacity: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(HISTORY_CAPACITY)
+    }
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Records a command that has just been applied; this discards anything that could be redone
+    pub fn push(&mut self, command: Command) {
+        self.redo_stack.clear();
+        self.push_undo(command);
+    }
+
+    fn push_undo(&mut self, command: Command) {
+        self.undo_stack.push_back(command);
+        while self.undo_stack.len() > self.capacity {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Reverts the most recent command, returning it if there was one, along with any deck
+    /// restored under a new name. On failure the command stays on the undo stack.
+    pub async fn undo(&mut self, db: &PgPool, author: &str) -> Result<Option<(&Command, Vec<RenamedDeck>)>, sqlx::Error> {
+        let command = match self.undo_stack.pop_back() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+        let renamed = match command.undo(db, author).await {
+            Ok(renamed) => renamed,
+            Err(e) => {
+                self.undo_stack.push_back(command);
+                return Err(e);
+            }
+        };
+        self.redo_stack.push(command);
+        Ok(self.redo_stack.last().map(|command| (command, renamed)))
+    }
+
+    /// Re-applies the most recently undone command, returning it if there was one.
+    /// On failure the command stays on the redo stack.
+    pub async fn redo(&mut self, db: &PgPool, author: &str) -> Result<Option<&Command>, sqlx::Error> {
+        let command = match self.redo_stack.pop() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+        if let Err(e) = command.redo(db, author).await {
+            self.redo_stack.push(command);
+            return Err(e);
+        }
+        self.push_undo(command);
+        Ok(self.undo_stack.back())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create(front: &str) -> Command {
+        Command::CreateCard {
+            card: Card {
+                front_text: Some(front.to_string()),
+                ..Card::default()
+            },
+            note: None,
+            siblings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_push_is_capped() {
+        let mut history = History::new(2);
+        history.push(create("a"));
+        history.push(create("b"));
+        history.push(create("c"));
+        let kept: Vec<String> = history.undo_stack.iter().map(|c| c.description()).collect();
+        assert_eq!(vec!["create card 'b'", "create card 'c'"], kept);
+    }
+
+    #[test]
+    fn test_push_clears_redo() {
+        let mut history = History::new(2);
+        history.redo_stack.push(create("a"));
+        assert!(history.can_redo());
+        history.push(create("b"));
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn test_description_truncates_front() {
+        let command = create("a very long question that goes on");
+        assert_eq!("create card 'a very long question…'", command.description());
+    }
+}
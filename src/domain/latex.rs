@@ -0,0 +1,381 @@
+//! LaTeX maths (`$...$` and `$$...$$`) as plain Unicode, for display in a terminal,
+//! e.g. `$\sum_{i=1}^{n} x_i^2 \leq \frac{1}{2}$` becomes `∑ᵢ₌₁ⁿ xᵢ² ≤ ½`.
+//!
+//! Only a common subset is understood: Greek letters, operators, arrows, set notation,
+//! sub/superscripts, fractions and roots. An expression using anything else (or a script
+//! with no Unicode form) is left as its raw source.
+
+/// `text` with every maths expression in it converted.
+/// `$` pairs up as it does in markdown: an opening `$` can't be followed by whitespace, nor a
+/// closing `$` preceded by it, so prices like "$5 and $10" are left alone.
+pub fn to_unicode(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = find_dollar(rest) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let delimiter = if after.starts_with("$$") { "$$" } else { "$" };
+        let body = &after[delimiter.len()..];
+        if delimiter == "$" && body.chars().next().is_none_or(char::is_whitespace) {
+            out.push('$');
+            rest = body;
+            continue;
+        }
+        match find_closing(body, delimiter) {
+            Some(end) => {
+                let source = &body[..end];
+                match render_math(source) {
+                    Some(math) => out.push_str(&math),
+                    None => out.push_str(&after[..delimiter.len() * 2 + end]),
+                }
+                rest = &body[end + delimiter.len()..];
+            }
+            None => {
+                out.push_str(after);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Byte index of the first `$` in `text` that isn't escaped as `\$`
+fn find_dollar(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '$' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn find_closing(body: &str, delimiter: &str) -> Option<usize> {
+    let mut offset = 0usize;
+    while let Some(i) = find_dollar(&body[offset..]) {
+        let after_space = body[..offset + i].chars().next_back().is_none_or(char::is_whitespace);
+        if body[offset + i..].starts_with(delimiter) && !(delimiter == "$" && after_space) {
+            return Some(offset + i);
+        }
+        offset += i + 1;
+    }
+    None
+}
+
+/// Converts the maths `source` (without its `$` delimiters); `None` if it uses anything unsupported
+pub fn render_math(source: &str) -> Option<String> {
+    let mut parser = MathParser {
+        chars: source.chars().collect(),
+        pos: 0,
+    };
+    let math = parser.sequence()?;
+    match parser.pos == parser.chars.len() {
+        true => Some(math.trim().to_string()),
+        false => None, // an unmatched '}'
+    }
+}
+
+struct MathParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl MathParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Everything up to the end of the current group
+    fn sequence(&mut self) -> Option<String> {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' => break,
+                '^' | '_' => {
+                    self.pos += 1;
+                    let script = self.argument()?;
+                    out.push_str(&script_text(c, &script)?);
+                }
+                _ => out.push_str(&self.atom()?),
+            }
+        }
+        Some(out)
+    }
+
+    fn atom(&mut self) -> Option<String> {
+        match self.next()? {
+            '{' => {
+                let group = self.sequence()?;
+                match self.next() {
+                    Some('}') => Some(group),
+                    _ => None,
+                }
+            }
+            '\\' => self.command(),
+            '~' => Some(" ".to_string()),
+            '}' | '&' | '#' | '%' => None,
+            c => Some(c.to_string()),
+        }
+    }
+
+    /// The argument of a command or script: a group or a single token
+    fn argument(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        self.atom()
+    }
+
+    fn command(&mut self) -> Option<String> {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_alphabetic) {
+            name.push(c);
+            self.pos += 1;
+        }
+        if name.is_empty() {
+            // A control symbol, e.g. `\,` or `\{`
+            return match self.next()? {
+                ',' | ':' | ';' | ' ' => Some(" ".to_string()),
+                '!' => Some(String::new()),
+                '|' => Some("‖".to_string()),
+                c @ ('{' | '}' | '$' | '%' | '&' | '#' | '_') => Some(c.to_string()),
+                _ => None,
+            };
+        }
+        if let Some(symbol) = symbol(&name) {
+            return Some(symbol.to_string());
+        }
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument()?;
+                let denominator = self.argument()?;
+                Some(fraction(&numerator, &denominator))
+            }
+            "sqrt" => {
+                let index = match self.peek() {
+                    Some('[') => {
+                        let end = self.chars[self.pos..].iter().position(|c| *c == ']')?;
+                        let index: String = self.chars[self.pos + 1..self.pos + end].iter().collect();
+                        self.pos += end + 1;
+                        index.trim().to_string()
+                    }
+                    _ => String::new(),
+                };
+                let sign = match index.as_str() {
+                    "" | "2" => "√",
+                    "3" => "∛",
+                    "4" => "∜",
+                    _ => return None,
+                };
+                Some(format!("{}{}", sign, grouped(&self.argument()?)))
+            }
+            "mathbb" => {
+                let letter = match self.argument()?.as_str() {
+                    "N" => "ℕ",
+                    "Z" => "ℤ",
+                    "Q" => "ℚ",
+                    "R" => "ℝ",
+                    "C" => "ℂ",
+                    "P" => "ℙ",
+                    _ => return None,
+                };
+                Some(letter.to_string())
+            }
+            "text" | "textrm" | "textit" | "textbf" | "mathrm" | "mathit" | "mathbf" | "mathsf" | "mathtt"
+            | "operatorname" => self.argument(),
+            "left" | "right" | "bigl" | "bigr" | "big" | "Big" | "bigg" | "Bigg" => {
+                // The delimiter that follows is printed as is; `.` means none
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+                Some(String::new())
+            }
+            "displaystyle" | "textstyle" | "limits" | "nolimits" => Some(String::new()),
+            "quad" => Some("  ".to_string()),
+            "qquad" => Some("    ".to_string()),
+            "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan" | "sinh" | "cosh"
+            | "tanh" | "log" | "ln" | "lg" | "exp" | "lim" | "max" | "min" | "sup" | "inf" | "det" | "gcd"
+            | "deg" | "dim" | "ker" | "arg" | "Pr" => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// `text` in parentheses if it is more than a single number or word
+fn grouped(text: &str) -> String {
+    match text.chars().count() > 1 && !text.chars().all(char::is_alphanumeric) {
+        true => format!("({})", text),
+        false => text.to_string(),
+    }
+}
+
+fn fraction(numerator: &str, denominator: &str) -> String {
+    let vulgar = match (numerator, denominator) {
+        ("1", "2") => Some("½"),
+        ("1", "3") => Some("⅓"),
+        ("2", "3") => Some("⅔"),
+        ("1", "4") => Some("¼"),
+        ("3", "4") => Some("¾"),
+        ("1", "5") => Some("⅕"),
+        ("1", "6") => Some("⅙"),
+        ("1", "8") => Some("⅛"),
+        _ => None,
+    };
+    match vulgar {
+        Some(vulgar) => vulgar.to_string(),
+        None => format!("{}/{}", grouped(numerator), grouped(denominator)),
+    }
+}
+
+/// `text` as a superscript (`kind` '^') or subscript ('_'), if every character has one
+fn script_text(kind: char, text: &str) -> Option<String> {
+    text.chars()
+        .map(|c| match kind {
+            '^' => superscript(c),
+            _ => subscript(c),
+        })
+        .collect()
+}
+
+fn superscript(c: char) -> Option<char> {
+    let script = match c {
+        '0' => '⁰', '1' => '¹', '2' => '²', '3' => '³', '4' => '⁴',
+        '5' => '⁵', '6' => '⁶', '7' => '⁷', '8' => '⁸', '9' => '⁹',
+        '+' => '⁺', '-' | '−' => '⁻', '=' => '⁼', '(' => '⁽', ')' => '⁾',
+        'a' => 'ᵃ', 'b' => 'ᵇ', 'c' => 'ᶜ', 'd' => 'ᵈ', 'e' => 'ᵉ', 'f' => 'ᶠ', 'g' => 'ᵍ',
+        'h' => 'ʰ', 'i' => 'ⁱ', 'j' => 'ʲ', 'k' => 'ᵏ', 'l' => 'ˡ', 'm' => 'ᵐ', 'n' => 'ⁿ',
+        'o' => 'ᵒ', 'p' => 'ᵖ', 'r' => 'ʳ', 's' => 'ˢ', 't' => 'ᵗ', 'u' => 'ᵘ', 'v' => 'ᵛ',
+        'w' => 'ʷ', 'x' => 'ˣ', 'y' => 'ʸ', 'z' => 'ᶻ',
+        'A' => 'ᴬ', 'B' => 'ᴮ', 'D' => 'ᴰ', 'E' => 'ᴱ', 'G' => 'ᴳ', 'H' => 'ᴴ', 'I' => 'ᴵ',
+        'J' => 'ᴶ', 'K' => 'ᴷ', 'L' => 'ᴸ', 'M' => 'ᴹ', 'N' => 'ᴺ', 'O' => 'ᴼ', 'P' => 'ᴾ',
+        'R' => 'ᴿ', 'T' => 'ᵀ', 'U' => 'ᵁ', 'V' => 'ⱽ', 'W' => 'ᵂ',
+        '∘' => '°', '′' => '′', '*' => '*', ' ' => ' ',
+        _ => return None,
+    };
+    Some(script)
+}
+
+fn subscript(c: char) -> Option<char> {
+    let script = match c {
+        '0' => '₀', '1' => '₁', '2' => '₂', '3' => '₃', '4' => '₄',
+        '5' => '₅', '6' => '₆', '7' => '₇', '8' => '₈', '9' => '₉',
+        '+' => '₊', '-' | '−' => '₋', '=' => '₌', '(' => '₍', ')' => '₎',
+        'a' => 'ₐ', 'e' => 'ₑ', 'h' => 'ₕ', 'i' => 'ᵢ', 'j' => 'ⱼ', 'k' => 'ₖ', 'l' => 'ₗ',
+        'm' => 'ₘ', 'n' => 'ₙ', 'o' => 'ₒ', 'p' => 'ₚ', 'r' => 'ᵣ', 's' => 'ₛ', 't' => 'ₜ',
+        'u' => 'ᵤ', 'v' => 'ᵥ', 'x' => 'ₓ',
+        'β' => 'ᵦ', 'γ' => 'ᵧ', 'ρ' => 'ᵨ', 'φ' => 'ᵩ', 'χ' => 'ᵪ', ' ' => ' ',
+        _ => return None,
+    };
+    Some(script)
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    let symbol = match name {
+        // Greek
+        "alpha" => "α", "beta" => "β", "gamma" => "γ", "delta" => "δ", "epsilon" => "ϵ",
+        "varepsilon" => "ε", "zeta" => "ζ", "eta" => "η", "theta" => "θ", "vartheta" => "ϑ",
+        "iota" => "ι", "kappa" => "κ", "lambda" => "λ", "mu" => "μ", "nu" => "ν", "xi" => "ξ",
+        "pi" => "π", "varpi" => "ϖ", "rho" => "ρ", "varrho" => "ϱ", "sigma" => "σ",
+        "varsigma" => "ς", "tau" => "τ", "upsilon" => "υ", "phi" => "ϕ", "varphi" => "φ",
+        "chi" => "χ", "psi" => "ψ", "omega" => "ω",
+        "Gamma" => "Γ", "Delta" => "Δ", "Theta" => "Θ", "Lambda" => "Λ", "Xi" => "Ξ", "Pi" => "Π",
+        "Sigma" => "Σ", "Upsilon" => "Υ", "Phi" => "Φ", "Psi" => "Ψ", "Omega" => "Ω",
+        // Big operators and calculus
+        "sum" => "∑", "prod" => "∏", "coprod" => "∐", "int" => "∫", "iint" => "∬", "iiint" => "∭",
+        "oint" => "∮", "partial" => "∂", "nabla" => "∇", "infty" => "∞",
+        // Binary operators and relations
+        "pm" => "±", "mp" => "∓", "times" => "×", "div" => "÷", "cdot" => "⋅", "ast" => "∗",
+        "circ" => "∘", "bullet" => "∙", "oplus" => "⊕", "otimes" => "⊗",
+        "leq" | "le" => "≤", "geq" | "ge" => "≥", "neq" | "ne" => "≠", "ll" => "≪", "gg" => "≫",
+        "approx" => "≈", "equiv" => "≡", "sim" => "∼", "simeq" => "≃", "cong" => "≅",
+        "propto" => "∝", "perp" => "⊥", "parallel" => "∥", "mid" => "∣",
+        // Arrows
+        "to" | "rightarrow" => "→", "leftarrow" | "gets" => "←", "leftrightarrow" => "↔",
+        "Rightarrow" => "⇒", "Leftarrow" => "⇐", "Leftrightarrow" => "⇔", "implies" => "⟹",
+        "iff" => "⟺", "mapsto" => "↦", "uparrow" => "↑", "downarrow" => "↓",
+        "longrightarrow" => "⟶", "longleftarrow" => "⟵",
+        // Sets and logic
+        "in" => "∈", "notin" => "∉", "ni" => "∋", "subset" => "⊂", "subseteq" => "⊆",
+        "supset" => "⊃", "supseteq" => "⊇", "cup" => "∪", "cap" => "∩", "setminus" => "∖",
+        "emptyset" | "varnothing" => "∅", "forall" => "∀", "exists" => "∃", "nexists" => "∄",
+        "neg" | "lnot" => "¬", "land" | "wedge" => "∧", "lor" | "vee" => "∨",
+        "bigcup" => "⋃", "bigcap" => "⋂", "top" => "⊤", "bot" => "⊥",
+        // Miscellaneous
+        "ldots" | "dots" => "…", "cdots" => "⋯", "vdots" => "⋮", "ddots" => "⋱",
+        "prime" => "′", "degree" => "°", "angle" => "∠", "hbar" => "ℏ", "ell" => "ℓ",
+        "langle" => "⟨", "rangle" => "⟩", "lfloor" => "⌊", "rfloor" => "⌋", "lceil" => "⌈",
+        "rceil" => "⌉", "lbrace" => "{", "rbrace" => "}", "vert" => "|", "Vert" => "‖",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols() {
+        assert_eq!(Some("α + β ≤ ∞".to_string()), render_math(r"\alpha + \beta \leq \infty"));
+        assert_eq!(Some("A ⊆ ℝ ∪ ∅".to_string()), render_math(r"A \subseteq \mathbb{R} \cup \emptyset"));
+        assert_eq!(Some("p ⇒ q".to_string()), render_math(r"p \Rightarrow q"));
+        assert_eq!(Some("∀ x ∈ ℕ".to_string()), render_math(r"\forall x \in \mathbb N"));
+    }
+
+    #[test]
+    fn test_scripts() {
+        assert_eq!(Some("x²".to_string()), render_math("x^2"));
+        assert_eq!(Some("xₙ₊₁ = eⁱᵖ".to_string()), render_math("x_{n+1} = e^{ip}"));
+        assert_eq!(Some("∑ᵢ₌₁ⁿ xᵢ".to_string()), render_math(r"\sum_{i=1}^{n} x_i"));
+        assert_eq!(Some("∫₀¹ f(x) dx".to_string()), render_math(r"\int_0^1 f(x) dx"));
+        assert_eq!(Some("90°".to_string()), render_math(r"90^\circ"));
+        // no subscript 'q' in Unicode
+        assert_eq!(None, render_math("x_q"));
+    }
+
+    #[test]
+    fn test_fractions_and_roots() {
+        assert_eq!(Some("½".to_string()), render_math(r"\frac12"));
+        assert_eq!(Some("(a+b)/2".to_string()), render_math(r"\frac{a+b}{2}"));
+        assert_eq!(Some("dy/dx".to_string()), render_math(r"\frac{dy}{dx}"));
+        assert_eq!(Some("√(x+1)".to_string()), render_math(r"\sqrt{x+1}"));
+        assert_eq!(Some("∛8".to_string()), render_math(r"\sqrt[3]{8}"));
+    }
+
+    #[test]
+    fn test_unsupported_is_none() {
+        assert_eq!(None, render_math(r"\begin{matrix} a & b \end{matrix}"));
+        assert_eq!(None, render_math(r"\unknowncommand x"));
+        assert_eq!(None, render_math("{unclosed"));
+        assert_eq!(None, render_math("x^"));
+    }
+
+    #[test]
+    fn test_to_unicode_converts_delimited_maths() {
+        assert_eq!(
+            "Energy: E = mc², and ∑ₖ k",
+            to_unicode(r"Energy: $E = mc^2$, and $$\sum_k k$$")
+        );
+        // unsupported expressions and unclosed delimiters stay as written
+        assert_eq!(r"$\weird{x}$ costs \$5 or $3", to_unicode(r"$\weird{x}$ costs \$5 or $3"));
+    }
+
+    #[test]
+    fn test_to_unicode_pairs_dollars_like_markdown() {
+        assert_eq!("costs $5 and $10", to_unicode("costs $5 and $10"));
+        assert_eq!("$ x $ but x²", to_unicode("$ x $ but $x^2$"));
+    }
+}
@@ -0,0 +1,141 @@
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+use super::card::Card;
+use super::preset::LeechAction;
+use super::scheduler::CardState;
+use super::tag::Tag;
+
+/// Tag of the cards that keep being forgotten
+pub const LEECH_TAG: &str = "leech";
+
+pub fn is_leech(card: &Card) -> bool {
+    card.tags.iter().any(|tag| tag == LEECH_TAG)
+}
+
+/// Whether `card`, which had `lapses_before` lapses before its last answer, has just lapsed
+/// for the `threshold`th time (or more) without being a leech yet
+pub fn becomes_leech(card: &Card, lapses_before: i32, threshold: u32) -> bool {
+    card.lapses > lapses_before && card.lapses >= threshold as i32 && !is_leech(card)
+}
+
+/// Tags `card` as a leech and, depending on `action`, suspends it
+pub async fn mark(db: &PgPool, card: &mut Card, action: LeechAction) -> Result<(), sqlx::Error> {
+    Tag::add_to_cards(db, LEECH_TAG, &[card.id]).await?;
+    card.tags.push(LEECH_TAG.to_string());
+    card.tags.sort();
+    if action == LeechAction::Suspend {
+        Card::set_suspended(db, &[card.id], true).await?;
+        card.suspended = true;
+    }
+    Ok(())
+}
+
+/// A failed review of a leech, from `views`
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub time: DateTime<Utc>,
+    pub typed_answer: Option<String>,
+    pub similarity: Option<f32>,
+    pub hint_used: bool,
+}
+
+/// A card tagged as a leech, with its review history
+#[derive(Debug, Clone)]
+pub struct Leech {
+    pub card: Card,
+    pub n_reviews: usize,
+    pub failures: Vec<Failure>, // newest first
+}
+
+impl Leech {
+    /// Every leech that isn't in the trash, most lapses first
+    pub async fn load_all(db: &PgPool) -> Result<Vec<Leech>, sqlx::Error> {
+        let cards: Vec<Card> = sqlx::query_as!(
+            Card,
+            r#"
+            SELECT id, front_text, back_text, deck_id, created, modified,
+                ARRAY(
+                    SELECT t.name FROM tags t
+                    JOIN card_tags ct ON ct.tag_id = t.id
+                    WHERE ct.card_id = cards.id
+                    ORDER BY t.name
+                ) AS "tags!",
+                due, interval_days, ease, lapses, suspended, note_id, ordinal, hint, notes, source,
+                state AS "state: CardState", step
+            FROM cards
+            WHERE deleted IS NULL
+            AND id IN (
+                SELECT ct.card_id FROM card_tags ct
+                JOIN tags t ON t.id = ct.tag_id
+                WHERE t.name = $1
+            )
+            ORDER BY lapses DESC, front_text
+            "#,
+            LEECH_TAG,
+        )
+        .fetch_all(db)
+        .await?;
+
+        let card_ids: Vec<Uuid> = cards.iter().map(|card| card.id).collect();
+        let views = sqlx::query!(
+            r#"
+            SELECT card_id AS "card_id!", time AS "time!", result AS "result!", typed_answer,
+                similarity, hint_used
+            FROM views
+            WHERE card_id = ANY($1) AND time IS NOT NULL AND result IS NOT NULL
+            ORDER BY time DESC
+            "#,
+            &card_ids,
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(cards
+            .into_iter()
+            .map(|card| {
+                let views: Vec<_> = views.iter().filter(|view| view.card_id == card.id).collect();
+                let failures = views
+                    .iter()
+                    .filter(|view| !view.result)
+                    .map(|view| Failure {
+                        time: view.time,
+                        typed_answer: view.typed_answer.clone(),
+                        similarity: view.similarity,
+                        hint_used: view.hint_used,
+                    })
+                    .collect();
+                Leech {
+                    n_reviews: views.len(),
+                    failures,
+                    card,
+                }
+            })
+            .collect())
+    }
+
+    /// No longer treats `card_id` as a leech, e.g. once it has been rewritten: it is untagged
+    /// and unsuspended
+    pub async fn clear(db: &PgPool, card_id: Uuid) -> Result<(), sqlx::Error> {
+        Tag::remove_from_cards(db, LEECH_TAG, &[card_id]).await?;
+        Card::set_suspended(db, &[card_id], false).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_becomes_leech_on_lapse_at_threshold() {
+        let card = Card { lapses: 8, ..Card::default() };
+        assert!(becomes_leech(&card, 7, 8));
+        // Only a new lapse makes a leech, and only once
+        assert!(!becomes_leech(&card, 8, 8));
+        assert!(!becomes_leech(&Card { lapses: 7, ..Card::default() }, 6, 8));
+        let tagged = Card { tags: vec![LEECH_TAG.to_string()], ..card.clone() };
+        assert!(!becomes_leech(&tagged, 7, 8));
+        // Lowering the threshold catches cards already past it on their next lapse
+        assert!(becomes_leech(&Card { lapses: 12, ..Card::default() }, 11, 8));
+    }
+}
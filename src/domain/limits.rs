@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rand::{seq::SliceRandom, Rng};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::activity::{day_of, start_of};
+use super::card::Card;
+use super::deck::Deck;
+
+/// Separates a parent deck's name from its child's, as in `Languages::Spanish`
+pub const DECK_SEPARATOR: &str = "::";
+
+/// Order new cards are introduced in
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NewOrder {
+    #[default]
+    Sequential, // oldest first
+    Random,
+    #[serde(rename = "tag")]
+    ByTag, // cards sharing tags together, oldest first within them
+}
+
+impl NewOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NewOrder::Sequential => "sequential",
+            NewOrder::Random => "random",
+            NewOrder::ByTag => "tag",
+        }
+    }
+
+    pub fn parse(order: &str) -> Option<Self> {
+        match order {
+            "sequential" => Some(NewOrder::Sequential),
+            "random" => Some(NewOrder::Random),
+            "tag" => Some(NewOrder::ByTag),
+            _ => None,
+        }
+    }
+}
+
+/// Where new cards go among the reviews of a session
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interleave {
+    #[default]
+    Mixed, // spread evenly through the reviews
+    NewFirst,
+    ReviewsFirst,
+}
+
+impl Interleave {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interleave::Mixed => "mixed",
+            Interleave::NewFirst => "new-first",
+            Interleave::ReviewsFirst => "reviews-first",
+        }
+    }
+
+    pub fn parse(interleave: &str) -> Option<Self> {
+        match interleave {
+            "mixed" => Some(Interleave::Mixed),
+            "new-first" => Some(Interleave::NewFirst),
+            "reviews-first" => Some(Interleave::ReviewsFirst),
+            _ => None,
+        }
+    }
+}
+
+/// How much of a deck is studied a day, and in what order; `None` limits are unlimited
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct DeckLimits {
+    pub new_per_day: Option<u32>,
+    pub reviews_per_day: Option<u32>,
+    pub new_order: NewOrder,
+    pub interleave: Interleave,
+}
+
+impl Default for DeckLimits {
+    fn default() -> Self {
+        Self {
+            new_per_day: Some(20),
+            reviews_per_day: Some(200),
+            new_order: NewOrder::default(),
+            interleave: Interleave::default(),
+        }
+    }
+}
+
+impl DeckLimits {
+    /// Parses `key=value` pairs such as `new=20 reviews=none order=random mix=new-first`,
+    /// as shown by `Display`; keys left out keep their current values
+    pub fn parse(&self, input: &str) -> Result<Self, String> {
+        let mut limits = *self;
+        for pair in input.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got `{}`", pair))?;
+            if !limits.set(key, value)? {
+                return Err(format!("Unknown option `{}`", key));
+            }
+        }
+        Ok(limits)
+    }
+
+    /// Sets option `key` as written by `Display`; `false` if there is no such option
+    pub fn set(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        fn limit(key: &str, value: &str) -> Result<Option<u32>, String> {
+            match value {
+                "none" => Ok(None),
+                value => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("Invalid value `{}` for `{}`", value, key)),
+            }
+        }
+        match key {
+            "new" => self.new_per_day = limit(key, value)?,
+            "reviews" => self.reviews_per_day = limit(key, value)?,
+            "order" => {
+                self.new_order = NewOrder::parse(value)
+                    .ok_or_else(|| format!("Unknown order `{}`: sequential, random or tag", value))?
+            }
+            "mix" => {
+                self.interleave = Interleave::parse(value)
+                    .ok_or_else(|| format!("Unknown mix `{}`: mixed, new-first or reviews-first", value))?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Puts `cards`, all new, in the order they should be introduced
+    pub fn sort_new(&self, cards: &mut [Card], rng: &mut impl Rng) {
+        match self.new_order {
+            NewOrder::Sequential => cards.sort_by_key(|card| card.created),
+            NewOrder::Random => cards.shuffle(rng),
+            NewOrder::ByTag => cards.sort_by(|a, b| {
+                // Untagged cards go last
+                (a.tags.is_empty(), &a.tags, a.created).cmp(&(b.tags.is_empty(), &b.tags, b.created))
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for DeckLimits {
+    /// e.g. "new=20 reviews=200 order=sequential mix=mixed"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limit = |limit: Option<u32>| limit.map_or("none".to_string(), |n| n.to_string());
+        write!(
+            f,
+            "new={} reviews={} order={} mix={}",
+            limit(self.new_per_day),
+            limit(self.reviews_per_day),
+            self.new_order.as_str(),
+            self.interleave.as_str()
+        )
+    }
+}
+
+/// Cards studied today in one deck
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StudiedToday {
+    pub new: u64,     // cards seen for the first time
+    pub reviews: u64, // reviews of the other cards
+}
+
+impl StudiedToday {
+    /// What each deck has studied since the study day began at `rollover_hour`
+    pub async fn load_all(db: &PgPool, rollover_hour: u32) -> Result<HashMap<Uuid, StudiedToday>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                cards.deck_id AS "deck_id!",
+                COUNT(DISTINCT views.card_id) FILTER (WHERE first_seen.time >= $1) AS "new!",
+                COUNT(*) FILTER (WHERE first_seen.time < $1) AS "reviews!"
+            FROM views
+            JOIN cards ON cards.id = views.card_id
+            JOIN (SELECT card_id, MIN(time) AS time FROM views GROUP BY card_id) first_seen
+                ON first_seen.card_id = views.card_id
+            WHERE views.time >= $1 AND cards.deck_id IS NOT NULL
+            GROUP BY cards.deck_id
+            "#,
+            start_of(day_of(Utc::now(), rollover_hour), rollover_hour),
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let studied = StudiedToday {
+                    new: row.new as u64,
+                    reviews: row.reviews as u64,
+                };
+                (row.deck_id, studied)
+            })
+            .collect())
+    }
+}
+
+/// Cards of one deck waiting to be studied: new ones and reviews due by now
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Waiting {
+    pub new: u64,
+    pub reviews: u64,
+}
+
+impl Waiting {
+    /// What each deck has waiting; trashed and suspended cards are left out
+    pub async fn load_all(db: &PgPool) -> Result<HashMap<Uuid, Waiting>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                deck_id AS "deck_id!",
+                COUNT(*) FILTER (WHERE due IS NULL) AS "new!",
+                COUNT(*) FILTER (WHERE due <= $1) AS "reviews!"
+            FROM cards
+            WHERE deck_id IS NOT NULL AND deleted IS NULL AND NOT suspended
+            GROUP BY deck_id
+            "#,
+            Utc::now(),
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let waiting = Waiting {
+                    new: row.new as u64,
+                    reviews: row.reviews as u64,
+                };
+                (row.deck_id, waiting)
+            })
+            .collect())
+    }
+}
+
+/// New cards and reviews a deck may still show today, `None` if unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Remaining {
+    pub new: Option<u64>,
+    pub reviews: Option<u64>,
+}
+
+/// What is left of each deck's daily limits. A parent deck's limits also count the
+/// cards studied in its children (`Parent::Child`), and cap them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DailyLimits {
+    /// Left of each deck's own limits, after what it and its children studied today
+    left: HashMap<Uuid, Remaining>,
+    /// Each deck and the decks above it
+    lineage: HashMap<Uuid, Vec<Uuid>>,
+}
+
+/// Whether `child` is `parent` or one of the decks below it
+pub fn is_within(child: &str, parent: &str) -> bool {
+    child == parent
+        || child
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.starts_with(DECK_SEPARATOR))
+}
+
+impl DailyLimits {
+    pub fn new(decks: &[Deck], studied: &HashMap<Uuid, StudiedToday>) -> Self {
+        let mut left = HashMap::new();
+        let mut lineage = HashMap::new();
+        for deck in decks {
+            let (new, reviews) = decks
+                .iter()
+                .filter(|other| is_within(&other.name, &deck.name))
+                .filter_map(|other| studied.get(&other.id))
+                .fold((0, 0), |(new, reviews), studied| (new + studied.new, reviews + studied.reviews));
+            let remaining = Remaining {
+                new: deck.limits.new_per_day.map(|limit| (limit as u64).saturating_sub(new)),
+                reviews: deck.limits.reviews_per_day.map(|limit| (limit as u64).saturating_sub(reviews)),
+            };
+            left.insert(deck.id, remaining);
+            let above: Vec<Uuid> = decks
+                .iter()
+                .filter(|other| is_within(&deck.name, &other.name))
+                .map(|other| other.id)
+                .collect();
+            lineage.insert(deck.id, above);
+        }
+        Self { left, lineage }
+    }
+
+    pub async fn load(db: &PgPool, decks: &[Deck], rollover_hour: u32) -> Result<Self, sqlx::Error> {
+        Ok(Self::new(decks, &StudiedToday::load_all(db, rollover_hour).await?))
+    }
+
+    /// What deck `deck_id` may still show today, within its own limits and those above it
+    pub fn remaining(&self, deck_id: Uuid) -> Remaining {
+        let mut remaining = Remaining::default();
+        for left in self.lineage.get(&deck_id).into_iter().flatten().filter_map(|id| self.left.get(id)) {
+            remaining.new = min_limit(remaining.new, left.new);
+            remaining.reviews = min_limit(remaining.reviews, left.reviews);
+        }
+        remaining
+    }
+
+    /// How much of what deck `deck_id` has `waiting` can still be studied today
+    pub fn available(&self, deck_id: Uuid, waiting: Waiting) -> Waiting {
+        let remaining = self.remaining(deck_id);
+        Waiting {
+            new: remaining.new.map_or(waiting.new, |left| left.min(waiting.new)),
+            reviews: remaining.reviews.map_or(waiting.reviews, |left| left.min(waiting.reviews)),
+        }
+    }
+
+    /// How much `deck` and the decks below it have `waiting` that can still be studied today,
+    /// each within its own limits and all of it within `deck`'s
+    pub fn available_within(&self, deck: &Deck, decks: &[Deck], waiting: &HashMap<Uuid, Waiting>) -> Waiting {
+        let total = decks
+            .iter()
+            .filter(|other| is_within(&other.name, &deck.name))
+            .map(|other| self.available(other.id, waiting.get(&other.id).copied().unwrap_or_default()))
+            .fold(Waiting::default(), |total, available| Waiting {
+                new: total.new + available.new,
+                reviews: total.reviews + available.reviews,
+            });
+        self.available(deck.id, total)
+    }
+
+    /// Counts a card of `deck_id` against the limits if they have room for it
+    fn take(&mut self, deck_id: Option<Uuid>, new: bool) -> bool {
+        let Some(deck_id) = deck_id else {
+            return true;
+        };
+        let remaining = self.remaining(deck_id);
+        if matches!(if new { remaining.new } else { remaining.reviews }, Some(0)) {
+            return false;
+        }
+        for id in self.lineage.get(&deck_id).into_iter().flatten() {
+            if let Some(left) = self.left.get_mut(id) {
+                let count = if new { &mut left.new } else { &mut left.reviews };
+                if let Some(count) = count {
+                    *count -= 1;
+                }
+            }
+        }
+        true
+    }
+
+    /// Today's queue from the due `cards`: new cards in their deck's order, as many of them
+    /// and of the reviews as the limits allow, new cards placed by `interleave`
+    pub fn build_queue(
+        mut self,
+        cards: Vec<Card>,
+        decks: &[Deck],
+        interleave: Interleave,
+        rng: &mut impl Rng,
+    ) -> Vec<Card> {
+        let (mut new, reviews): (Vec<Card>, Vec<Card>) = cards.into_iter().partition(|card| card.due.is_none());
+        // Each deck's new cards in its own order, decks in the order they first appear
+        let mut deck_order: Vec<Option<Uuid>> = Vec::new();
+        for card in &new {
+            if !deck_order.contains(&card.deck_id) {
+                deck_order.push(card.deck_id);
+            }
+        }
+        new.sort_by_key(|card| deck_order.iter().position(|id| *id == card.deck_id));
+        for deck_id in deck_order {
+            let limits = decks.iter().find(|deck| Some(deck.id) == deck_id).map(|deck| deck.limits);
+            let start = new.iter().position(|card| card.deck_id == deck_id).unwrap_or(0);
+            let end = start + new[start..].iter().take_while(|card| card.deck_id == deck_id).count();
+            limits.unwrap_or_default().sort_new(&mut new[start..end], rng);
+        }
+
+        let reviews: Vec<Card> = reviews.into_iter().filter(|card| self.take(card.deck_id, false)).collect();
+        let new: Vec<Card> = new.into_iter().filter(|card| self.take(card.deck_id, true)).collect();
+        interleave_cards(reviews, new, interleave)
+    }
+}
+
+/// The tighter of two limits, where `None` is unlimited
+fn min_limit(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn interleave_cards(reviews: Vec<Card>, new: Vec<Card>, interleave: Interleave) -> Vec<Card> {
+    match interleave {
+        Interleave::NewFirst => new.into_iter().chain(reviews).collect(),
+        Interleave::ReviewsFirst => reviews.into_iter().chain(new).collect(),
+        Interleave::Mixed => {
+            // Place each card at the middle of its share of the session
+            let position = |i: usize, n: usize| (2 * i + 1) as f64 / (2 * n) as f64;
+            let (n_reviews, n_new) = (reviews.len(), new.len());
+            let mut cards: Vec<(f64, Card)> = reviews
+                .into_iter()
+                .enumerate()
+                .map(|(i, card)| (position(i, n_reviews), card))
+                .chain(new.into_iter().enumerate().map(|(i, card)| (position(i, n_new), card)))
+                .collect();
+            cards.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            cards.into_iter().map(|(_, card)| card).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn deck(name: &str, new_per_day: Option<u32>) -> Deck {
+        Deck {
+            limits: DeckLimits {
+                new_per_day,
+                ..DeckLimits::default()
+            },
+            ..Deck::new(name)
+        }
+    }
+
+    fn new_card(deck: &Deck, age_days: i64) -> Card {
+        Card {
+            deck_id: Some(deck.id),
+            created: Some(Utc::now() - Duration::days(age_days)),
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let limits = DeckLimits::default().parse("new=5 reviews=none order=tag").unwrap();
+        assert_eq!(Some(5), limits.new_per_day);
+        assert_eq!(None, limits.reviews_per_day);
+        assert_eq!(NewOrder::ByTag, limits.new_order);
+        assert_eq!("new=5 reviews=none order=tag mix=mixed", limits.to_string());
+        assert_eq!(Ok(limits), DeckLimits::default().parse(&limits.to_string()));
+        assert!(DeckLimits::default().parse("order=alphabetical").is_err());
+    }
+
+    #[test]
+    fn test_parent_limit_caps_children() {
+        let parent = deck("Languages", Some(10));
+        let child = deck("Languages::Spanish", Some(8));
+        let other = deck("Languages2", None);
+        let decks = vec![parent.clone(), child.clone(), other.clone()];
+        let studied = HashMap::from([(child.id, StudiedToday { new: 4, reviews: 0 })]);
+        let limits = DailyLimits::new(&decks, &studied);
+        assert_eq!(Some(4), limits.remaining(child.id).new);
+        assert_eq!(Some(6), limits.remaining(parent.id).new);
+        assert_eq!(None, limits.remaining(other.id).new);
+
+        // The parent's 6 left are shared between the decks
+        let cards: Vec<Card> = (0..5).map(|i| new_card(&child, i)).chain((0..5).map(|i| new_card(&parent, i))).collect();
+        let queue = limits.build_queue(cards, &decks, Interleave::NewFirst, &mut rand::thread_rng());
+        assert_eq!(6, queue.len());
+        assert_eq!(4, queue.iter().filter(|card| card.deck_id == Some(child.id)).count());
+        // Oldest first
+        assert!(queue[0].created < queue[1].created);
+    }
+
+    #[test]
+    fn test_available_within_counts_children() {
+        let parent = deck("Languages", Some(10));
+        let child = deck("Languages::Spanish", Some(3));
+        let other = deck("Languages2", None);
+        let decks = vec![parent.clone(), child.clone(), other.clone()];
+        let limits = DailyLimits::new(&decks, &HashMap::new());
+        let waiting = HashMap::from([
+            (parent.id, Waiting { new: 4, reviews: 2 }),
+            (child.id, Waiting { new: 5, reviews: 1 }),
+            (other.id, Waiting { new: 7, reviews: 0 }),
+        ]);
+        // The child shows 3 of its 5 new cards, and the parent 4 + 3 of its 10
+        assert_eq!(Waiting { new: 7, reviews: 3 }, limits.available_within(&parent, &decks, &waiting));
+        assert_eq!(Waiting { new: 3, reviews: 1 }, limits.available_within(&child, &decks, &waiting));
+
+        // A tighter parent caps the sum
+        let parent = Deck {
+            limits: DeckLimits {
+                new_per_day: Some(5),
+                ..parent.limits
+            },
+            ..parent
+        };
+        let decks = vec![parent.clone(), child.clone()];
+        let limits = DailyLimits::new(&decks, &HashMap::new());
+        assert_eq!(5, limits.available_within(&parent, &decks, &waiting).new);
+    }
+
+    #[test]
+    fn test_mixed_interleave_spreads_new_cards() {
+        let deck = deck("Spanish", None);
+        let review = Card {
+            due: Some(Utc::now()),
+            ..new_card(&deck, 0)
+        };
+        let cards: Vec<Card> = vec![review; 4].into_iter().chain([new_card(&deck, 0), new_card(&deck, 0)]).collect();
+        let queue = DailyLimits::new(std::slice::from_ref(&deck), &HashMap::new()).build_queue(
+            cards,
+            std::slice::from_ref(&deck),
+            Interleave::Mixed,
+            &mut rand::thread_rng(),
+        );
+        let pattern: Vec<bool> = queue.iter().map(|card| card.due.is_none()).collect();
+        assert_eq!(vec![false, true, false, false, true, false], pattern);
+    }
+}
@@ -1,3 +1,29 @@
+pub mod activity;
+pub mod answer;
 pub mod card;
+pub mod choice;
+pub mod cloze;
+pub mod csv;
 pub mod deck;
 pub mod deckset;
+pub mod diff;
+pub mod exam;
+pub mod expr;
+pub mod history;
+pub mod latex;
+pub mod leech;
+pub mod limits;
+pub mod note;
+pub mod note_type;
+pub mod params;
+pub mod preset;
+pub mod revision;
+pub mod scheduler;
+pub mod search;
+pub mod simulation;
+pub mod stats;
+pub mod tag;
+pub mod tag_expr;
+pub mod template;
+pub mod trash;
+pub mod view;
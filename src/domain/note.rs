@@ -0,0 +1,582 @@
+use std::collections::HashSet;
+
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+use super::card::{Card, FORWARD, REVERSE};
+use super::cloze;
+use super::note_type::NoteType;
+use super::revision::{CardRevision, RevisionSource};
+use super::tag::Tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteKind {
+    /// A front -> back card, plus a back -> front card if `reverse` is set
+    Basic,
+    /// `front_text` holds `{{cN::...}}` deletions, one card per number; `back_text` is extra info
+    Cloze,
+    /// Named fields rendered by the templates of a `NoteType`, one card per template
+    Typed,
+}
+
+impl NoteKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteKind::Basic => "basic",
+            NoteKind::Cloze => "cloze",
+            NoteKind::Typed => "typed",
+        }
+    }
+
+    fn from_db(kind: &str) -> Self {
+        match kind {
+            "cloze" => NoteKind::Cloze,
+            "typed" => NoteKind::Typed,
+            _ => NoteKind::Basic,
+        }
+    }
+}
+
+// CREATE TABLE IF NOT EXISTS notes (
+//     id UUID NOT NULL,
+//     PRIMARY KEY(id),
+//     front_text TEXT,
+//     back_text TEXT,
+//     reverse BOOLEAN NOT NULL DEFAULT FALSE,
+//     created TIMESTAMPTZ,
+//     modified TIMESTAMPTZ,
+//     kind TEXT NOT NULL DEFAULT 'basic',
+//     note_type_id UUID REFERENCES note_types(id),
+//     field_names TEXT[] NOT NULL DEFAULT '{}',
+//     field_values TEXT[] NOT NULL DEFAULT '{}'
+// );
+/// The text shared by the cards generated from it (see `NoteKind`).
+/// Each card keeps its own copy of its text (and its own schedule); saving the note
+/// brings every sibling back in sync.
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub id: Uuid,
+    pub kind: NoteKind,
+    pub front_text: Option<String>,
+    pub back_text: Option<String>,
+    pub reverse: bool,
+    /// The type of a typed note, and its field values by name
+    pub note_type: Option<NoteType>,
+    pub fields: Vec<(String, String)>,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+impl Note {
+    /// Keeps only the first of `cards` from each note, so siblings aren't studied the same day
+    pub fn bury_siblings(cards: Vec<Card>) -> Vec<Card> {
+        let mut seen_notes: HashSet<Uuid> = HashSet::new();
+        cards
+            .into_iter()
+            .filter(|card| card.note_id.is_none_or(|note_id| seen_notes.insert(note_id)))
+            .collect()
+    }
+
+    /// The note text as seen from `card`, whose sides are swapped if it is a reverse card
+    pub fn from_card(id: Uuid, card: &Card, reverse: bool) -> Self {
+        let (front_text, back_text) = match card.ordinal {
+            REVERSE => (card.back_text.clone(), card.front_text.clone()),
+            _ => (card.front_text.clone(), card.back_text.clone()),
+        };
+        Note {
+            id,
+            kind: NoteKind::Basic,
+            front_text,
+            back_text,
+            reverse,
+            note_type: None,
+            fields: Vec::new(),
+            created: None,
+            modified: None,
+        }
+    }
+
+    /// A cloze note with `source` text containing deletions and `extra` shown on every back
+    pub fn cloze(id: Uuid, source: Option<String>, extra: Option<String>) -> Self {
+        Note {
+            id,
+            kind: NoteKind::Cloze,
+            front_text: source,
+            back_text: extra,
+            reverse: false,
+            note_type: None,
+            fields: Vec::new(),
+            created: None,
+            modified: None,
+        }
+    }
+
+    /// A note of `note_type` with `fields`, as (name, value)
+    pub fn typed(id: Uuid, note_type: NoteType, fields: Vec<(String, String)>) -> Self {
+        Note {
+            id,
+            kind: NoteKind::Typed,
+            front_text: None,
+            back_text: None,
+            reverse: false,
+            note_type: Some(note_type),
+            fields,
+            created: None,
+            modified: None,
+        }
+    }
+
+    /// The value of field `name` of a typed note
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The text of the sibling with `ordinal`
+    pub fn card_text(&self, ordinal: i32) -> (Option<String>, Option<String>) {
+        match self.kind {
+            NoteKind::Basic => match ordinal {
+                REVERSE => (self.back_text.clone(), self.front_text.clone()),
+                _ => (self.front_text.clone(), self.back_text.clone()),
+            },
+            NoteKind::Cloze => {
+                // The back keeps the markers so the answer can be highlighted, see `cloze::render_back`
+                let source = self.front_text.clone().unwrap_or_default();
+                let front = cloze::render_front(&source, ordinal.max(0) as u32);
+                let back = match self.back_text.as_deref().map(str::trim) {
+                    Some(extra) if !extra.is_empty() => format!("{}\n\n{}", source, extra),
+                    _ => source,
+                };
+                (Some(front), Some(back))
+            }
+            NoteKind::Typed => match self
+                .note_type
+                .as_ref()
+                .and_then(|note_type| note_type.render(ordinal, &self.fields))
+            {
+                Some((front, back)) => (Some(front), Some(back)),
+                None => (None, None),
+            },
+        }
+    }
+
+    /// Ordinals of the cards this note should have
+    fn wanted_ordinals(&self) -> Vec<i32> {
+        match self.kind {
+            NoteKind::Basic if self.reverse => vec![FORWARD, REVERSE],
+            NoteKind::Basic => vec![FORWARD],
+            NoteKind::Cloze => cloze::numbers(self.front_text.as_deref().unwrap_or_default())
+                .into_iter()
+                .map(|n| n as i32)
+                .collect(),
+            NoteKind::Typed => self
+                .note_type
+                .as_ref()
+                .map(|note_type| note_type.wanted_ordinals(&self.fields))
+                .unwrap_or_default(),
+        }
+    }
+
+    pub async fn load(db: &PgPool, id: Uuid) -> Result<Option<Note>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, kind, front_text, back_text, reverse, note_type_id,
+            field_names, field_values, created, modified
+            FROM notes
+            WHERE id = $1
+            "#,
+            id,
+        )
+        .fetch_optional(db)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let note_type = match row.note_type_id {
+            Some(note_type_id) => NoteType::load(db, note_type_id).await?,
+            None => None,
+        };
+        Ok(Some(Note {
+            id: row.id,
+            kind: NoteKind::from_db(&row.kind),
+            front_text: row.front_text,
+            back_text: row.back_text,
+            reverse: row.reverse,
+            note_type,
+            fields: row.field_names.into_iter().zip(row.field_values).collect(),
+            created: row.created,
+            modified: row.modified,
+        }))
+    }
+
+    /// Inserts or updates the note row only; its cards are left as they are
+    pub async fn save(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let (names, values): (Vec<String>, Vec<String>) = self.fields.iter().cloned().unzip();
+        sqlx::query!(
+            r#"
+            INSERT INTO notes (id, kind, front_text, back_text, reverse, note_type_id,
+            field_names, field_values, created, modified)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (id) DO UPDATE
+            SET kind = EXCLUDED.kind,
+            front_text = EXCLUDED.front_text,
+            back_text = EXCLUDED.back_text,
+            reverse = EXCLUDED.reverse,
+            note_type_id = EXCLUDED.note_type_id,
+            field_names = EXCLUDED.field_names,
+            field_values = EXCLUDED.field_values,
+            modified = EXCLUDED.modified
+            "#,
+            self.id,
+            self.kind.as_str(),
+            self.front_text,
+            self.back_text,
+            self.reverse,
+            self.note_type.as_ref().map(|note_type| note_type.id),
+            &names,
+            &values,
+            self.created.unwrap_or_else(Utc::now),
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the notes whose cards have all been purged
+    pub async fn delete_orphans(db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM notes
+            WHERE NOT EXISTS (SELECT 1 FROM cards WHERE cards.note_id = notes.id)
+            "#,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Ids of every card of the note, including trashed ones
+    pub async fn card_ids(db: &PgPool, id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!("SELECT id FROM cards WHERE note_id = $1", id)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Saves `card`, as edited, and its note, then brings its siblings in line: their text is
+    /// updated, missing cards are generated and cards the note no longer wants are trashed.
+    ///
+    /// If the front contains cloze deletions the note becomes a cloze note, with the card's
+    /// front as its source and back as extra info; otherwise it is a basic note.
+    /// `reverse` changes whether a basic note has a reverse card; `None` keeps the current setting.
+    /// The text of a typed note's card can't be mapped back to its fields, so only the card
+    /// is saved (see `save_typed`).
+    pub async fn save_with_card(
+        db: &PgPool,
+        card: &mut Card,
+        reverse: Option<bool>,
+        author: &str,
+    ) -> Result<(), sqlx::Error> {
+        let existing = match card.note_id {
+            Some(note_id) => Note::load(db, note_id).await?,
+            None => None,
+        };
+        if existing.as_ref().is_some_and(|note| note.kind == NoteKind::Typed) {
+            return card.save(db).await;
+        }
+        let note_id = card.note_id.unwrap_or_else(Uuid::new_v4);
+        let had_reverse = existing
+            .as_ref()
+            .is_some_and(|note| note.kind == NoteKind::Basic && note.reverse);
+        let was_cloze = existing.is_some_and(|note| note.kind == NoteKind::Cloze);
+
+        let source = card.front_text.clone().unwrap_or_default();
+        let note = if cloze::numbers(&source).is_empty() {
+            if was_cloze {
+                // The editor showed the cloze source, which is now this card's front
+                card.ordinal = FORWARD;
+            }
+            Note::from_card(note_id, card, reverse.unwrap_or(had_reverse))
+        } else {
+            Note::cloze(note_id, card.front_text.clone(), card.back_text.clone())
+        };
+        // Only turning the option on brings back a reverse card that was trashed
+        let restore_trashed = match note.kind {
+            NoteKind::Basic => note.reverse && !had_reverse,
+            NoteKind::Cloze | NoteKind::Typed => true,
+        };
+        note.save_for_card(db, card, restore_trashed, author).await
+    }
+
+    /// Saves `card`'s note as a note of `note_type` with `fields`, as (name, value), and
+    /// renders the card and its siblings from the type's templates
+    pub async fn save_typed(
+        db: &PgPool,
+        card: &mut Card,
+        note_type: NoteType,
+        fields: Vec<(String, String)>,
+        author: &str,
+    ) -> Result<(), sqlx::Error> {
+        let note_id = card.note_id.unwrap_or_else(Uuid::new_v4);
+        Note::typed(note_id, note_type, fields).save_for_card(db, card, true, author).await
+    }
+
+    /// Brings the note back to this snapshot, taken before or after an edit of `card`, and
+    /// renders `card` and its siblings from it again
+    pub async fn restore_for_card(&self, db: &PgPool, card: &mut Card, author: &str) -> Result<(), sqlx::Error> {
+        if let (NoteKind::Typed, Some(note_type)) = (self.kind, &self.note_type) {
+            return Note::save_typed(db, card, note_type.clone(), self.fields.clone(), author).await;
+        }
+        // As in `save_with_card`, only turning the option back on brings back a trashed reverse card
+        let had_reverse = Note::load(db, self.id)
+            .await?
+            .is_some_and(|note| note.kind == NoteKind::Basic && note.reverse);
+        let restore_trashed = self.kind != NoteKind::Basic || (self.reverse && !had_reverse);
+        self.save_for_card(db, card, restore_trashed, author).await
+    }
+
+    async fn save_for_card(
+        &self,
+        db: &PgPool,
+        card: &mut Card,
+        restore_trashed: bool,
+        author: &str,
+    ) -> Result<(), sqlx::Error> {
+        self.save(db).await?;
+
+        let siblings = self.siblings(db, card.id).await?;
+        let wanted = self.wanted_ordinals();
+        let mut keep_card = true;
+        if self.kind != NoteKind::Basic && !wanted.contains(&card.ordinal) {
+            // Take over the first wanted ordinal that no other card has, if any
+            let taken: HashSet<i32> = siblings.iter().map(|s| s.ordinal).collect();
+            match wanted.iter().find(|n| !taken.contains(n)) {
+                Some(n) => card.ordinal = *n,
+                None => keep_card = false,
+            }
+        }
+
+        card.note_id = Some(self.id);
+        (card.front_text, card.back_text) = self.card_text(card.ordinal);
+        if keep_card {
+            card.save(db).await?;
+        } else if card.created.is_some() {
+            Card::delete_many_from_db(db, &[card.id]).await?;
+        }
+
+        let covered = keep_card.then_some(card.ordinal);
+        self.sync_siblings(db, card, &siblings, covered, restore_trashed, author).await
+    }
+
+    /// Every other card of the note, including trashed ones
+    async fn siblings(&self, db: &PgPool, card_id: Uuid) -> Result<Vec<Sibling>, sqlx::Error> {
+        sqlx::query_as!(
+            Sibling,
+            r#"
+            SELECT id, ordinal, front_text, back_text, deleted FROM cards
+            WHERE note_id = $1 AND id != $2
+            "#,
+            self.id,
+            card_id,
+        )
+        .fetch_all(db)
+        .await
+    }
+
+    /// Rewrites the text of `siblings` from the note, keeping a revision by `author` of each one
+    /// that changes, and generates, restores or trashes cards to match the wanted ordinals
+    async fn sync_siblings(
+        &self,
+        db: &PgPool,
+        saved: &Card,
+        siblings: &[Sibling],
+        covered: Option<i32>,
+        restore_trashed: bool,
+        author: &str,
+    ) -> Result<(), sqlx::Error> {
+        for sibling in siblings {
+            let (front_text, back_text) = self.card_text(sibling.ordinal);
+            let previous = Card {
+                id: sibling.id,
+                front_text: sibling.front_text.clone(),
+                back_text: sibling.back_text.clone(),
+                ..Card::default()
+            };
+            let edited = Card {
+                front_text: front_text.clone(),
+                back_text: back_text.clone(),
+                ..previous.clone()
+            };
+            CardRevision::record(db, &previous, &edited, RevisionSource::Tui, author).await?;
+            sqlx::query!(
+                r#"
+                UPDATE cards
+                SET front_text = $2, back_text = $3, modified = $4
+                WHERE id = $1
+                "#,
+                sibling.id,
+                front_text,
+                back_text,
+                Utc::now(),
+            )
+            .execute(db)
+            .await?;
+        }
+
+        let wanted = self.wanted_ordinals();
+        for ordinal in &wanted {
+            // A deleted basic card isn't silently recreated; only a missing reverse card is
+            let creatable = self.kind != NoteKind::Basic || *ordinal == REVERSE;
+            if covered == Some(*ordinal) || !creatable {
+                continue;
+            }
+            if !siblings.iter().any(|s| s.ordinal == *ordinal) {
+                let (front_text, back_text) = self.card_text(*ordinal);
+                let card = Card {
+                    front_text,
+                    back_text,
+                    deck_id: saved.deck_id,
+                    tags: saved.tags.clone(),
+                    note_id: Some(self.id),
+                    ordinal: *ordinal,
+                    ..Card::default()
+                };
+                card.save(db).await?;
+                Tag::set_for_card(db, card.id, &card.tags).await?;
+            }
+        }
+
+        if restore_trashed {
+            let trashed_wanted: Vec<Uuid> = siblings
+                .iter()
+                .filter(|s| s.deleted.is_some() && wanted.contains(&s.ordinal))
+                .map(|s| s.id)
+                .collect();
+            if !trashed_wanted.is_empty() {
+                Card::restore_from_trash(db, &trashed_wanted).await?;
+            }
+        }
+
+        let unwanted: Vec<Uuid> = siblings
+            .iter()
+            .filter(|s| s.deleted.is_none() && !wanted.contains(&s.ordinal))
+            .map(|s| s.id)
+            .collect();
+        if !unwanted.is_empty() {
+            Card::delete_many_from_db(db, &unwanted).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Sibling {
+    id: Uuid,
+    ordinal: i32,
+    front_text: Option<String>,
+    back_text: Option<String>,
+    deleted: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::deck::Deck;
+    use crate::domain::limits::{DailyLimits, DeckLimits, Interleave};
+
+    #[test]
+    fn test_buried_siblings_leave_the_new_card_limit_to_other_notes() {
+        let deck = Deck {
+            limits: DeckLimits {
+                new_per_day: Some(2),
+                ..DeckLimits::default()
+            },
+            ..Deck::new("Spanish")
+        };
+        let cards: Vec<Card> = (0..3)
+            .flat_map(|_| {
+                let note_id = Some(Uuid::new_v4());
+                [FORWARD, REVERSE].map(|ordinal| Card {
+                    deck_id: Some(deck.id),
+                    note_id,
+                    ordinal,
+                    ..Card::default()
+                })
+            })
+            .collect();
+        let queue = DailyLimits::new(std::slice::from_ref(&deck), &Default::default()).build_queue(
+            Note::bury_siblings(cards),
+            std::slice::from_ref(&deck),
+            Interleave::NewFirst,
+            &mut rand::thread_rng(),
+        );
+        assert_eq!(2, queue.len());
+        assert_ne!(queue[0].note_id, queue[1].note_id);
+        assert!(queue.iter().all(|card| card.ordinal == FORWARD));
+    }
+
+    #[test]
+    fn test_reverse_card_swaps_sides() {
+        let card = Card {
+            front_text: Some("hello".to_string()),
+            back_text: Some("hola".to_string()),
+            ordinal: REVERSE,
+            ..Card::default()
+        };
+        let note = Note::from_card(Uuid::new_v4(), &card, true);
+        assert_eq!(Some("hola".to_string()), note.front_text);
+        assert_eq!((Some("hola".to_string()), Some("hello".to_string())), note.card_text(FORWARD));
+        assert_eq!((Some("hello".to_string()), Some("hola".to_string())), note.card_text(REVERSE));
+    }
+
+    #[test]
+    fn test_cloze_note_generates_card_per_number() {
+        let note = Note::cloze(
+            Uuid::new_v4(),
+            Some("{{c1::Paris}} is in {{c2::France}}".to_string()),
+            Some("capital".to_string()),
+        );
+        assert_eq!(vec![1, 2], note.wanted_ordinals());
+        assert_eq!(
+            (
+                Some("Paris is in [...]".to_string()),
+                Some("{{c1::Paris}} is in {{c2::France}}\n\ncapital".to_string())
+            ),
+            note.card_text(2)
+        );
+    }
+
+    #[test]
+    fn test_typed_note_renders_templates() {
+        use crate::domain::note_type::CardTemplate;
+
+        let note_type = NoteType {
+            id: Uuid::new_v4(),
+            name: "Capital".to_string(),
+            fields: vec!["Country".to_string(), "City".to_string()],
+            templates: vec![
+                CardTemplate {
+                    name: "Forward".to_string(),
+                    front: "Capital of {{Country}}?".to_string(),
+                    back: "{{City}}".to_string(),
+                },
+                CardTemplate {
+                    name: "Backward".to_string(),
+                    front: "{{City}} is the capital of?".to_string(),
+                    back: "{{Country}}".to_string(),
+                },
+            ],
+        };
+        let fields = vec![("Country".to_string(), "France".to_string()), ("City".to_string(), String::new())];
+        let note = Note::typed(Uuid::new_v4(), note_type, fields);
+        assert_eq!(vec![FORWARD], note.wanted_ordinals());
+        assert_eq!(Some("France"), note.field("Country"));
+        assert_eq!(
+            (Some("Capital of France?".to_string()), Some(String::new())),
+            note.card_text(FORWARD)
+        );
+        assert_eq!((None, None), note.card_text(2));
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::template::{self, FRONT_SIDE};
+
+/// One kind of card generated from notes of a type, e.g. "Recognition" asking
+/// word -> meaning and "Production" asking meaning -> word
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct CardTemplate {
+    pub name: String,
+    pub front: String,
+    pub back: String,
+}
+
+// CREATE TABLE IF NOT EXISTS note_types (
+//     id UUID NOT NULL,
+//     PRIMARY KEY(id),
+//     name TEXT NOT NULL UNIQUE,
+//     fields TEXT[] NOT NULL DEFAULT '{}'
+// );
+/// A user-defined kind of note: named fields and the templates rendering them into cards.
+/// Types are defined under `app.note_types` in the configuration and stored on startup.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct NoteType {
+    #[serde(skip, default = "Uuid::new_v4")]
+    pub id: Uuid,
+    pub name: String,
+    pub fields: Vec<String>,
+    pub templates: Vec<CardTemplate>,
+}
+
+impl NoteType {
+    /// `values` by field name, missing ones empty
+    pub fn field_map(&self, values: &[(String, String)]) -> HashMap<String, String> {
+        let mut fields: HashMap<String, String> = self
+            .fields
+            .iter()
+            .map(|name| (name.clone(), String::new()))
+            .collect();
+        for (name, value) in values {
+            if let Some(field) = fields.get_mut(name) {
+                field.clone_from(value);
+            }
+        }
+        fields
+    }
+
+    /// Front and back of the card generated by template `ordinal`; the back can include
+    /// the front as `{{FrontSide}}`
+    pub fn render(&self, ordinal: i32, values: &[(String, String)]) -> Option<(String, String)> {
+        let template = self.templates.get(usize::try_from(ordinal).ok()?)?;
+        let mut fields = self.field_map(values);
+        let front = template::render(&template.front, &fields);
+        fields.insert(FRONT_SIDE.to_string(), front.clone());
+        let back = template::render(&template.back, &fields);
+        Some((front, back))
+    }
+
+    /// Ordinals of the templates generating a card for `values`: those whose front shows a field
+    pub fn wanted_ordinals(&self, values: &[(String, String)]) -> Vec<i32> {
+        let fields = self.field_map(values);
+        self.templates
+            .iter()
+            .enumerate()
+            .filter(|(_, template)| template::shows_fields(&template.front, &fields))
+            .map(|(ordinal, _)| ordinal as i32)
+            .collect()
+    }
+
+    pub async fn load(db: &PgPool, id: Uuid) -> Result<Option<NoteType>, sqlx::Error> {
+        Ok(Self::load_all(db).await?.into_iter().find(|note_type| note_type.id == id))
+    }
+
+    pub async fn load_all(db: &PgPool) -> Result<Vec<NoteType>, sqlx::Error> {
+        let types = sqlx::query!(
+            r#"
+            SELECT id, name, fields FROM note_types
+            ORDER BY name
+            "#
+        )
+        .fetch_all(db)
+        .await?;
+        let templates = sqlx::query!(
+            r#"
+            SELECT note_type_id, ordinal, name, front, back FROM card_templates
+            ORDER BY note_type_id, ordinal
+            "#
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(types
+            .into_iter()
+            .map(|row| NoteType {
+                id: row.id,
+                name: row.name,
+                fields: row.fields,
+                templates: templates
+                    .iter()
+                    .filter(|template| template.note_type_id == row.id)
+                    .map(|template| CardTemplate {
+                        name: template.name.clone(),
+                        front: template.front.clone(),
+                        back: template.back.clone(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Stores the types defined in the configuration, matched to stored ones by name.
+    /// Existing notes pick up changed templates the next time they are saved.
+    pub async fn sync_from_config(db: &PgPool, note_types: &[NoteType]) -> Result<(), sqlx::Error> {
+        for note_type in note_types {
+            let mut transaction = db.begin().await?;
+            let id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO note_types (id, name, fields)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (name) DO UPDATE
+                SET fields = EXCLUDED.fields
+                RETURNING id
+                "#,
+                note_type.id,
+                note_type.name,
+                &note_type.fields,
+            )
+            .fetch_one(&mut *transaction)
+            .await?;
+            sqlx::query!("DELETE FROM card_templates WHERE note_type_id = $1", id)
+                .execute(&mut *transaction)
+                .await?;
+            for (ordinal, template) in note_type.templates.iter().enumerate() {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO card_templates (note_type_id, ordinal, name, front, back)
+                    VALUES ($1, $2, $3, $4, $5)
+                    "#,
+                    id,
+                    ordinal as i32,
+                    template.name,
+                    template.front,
+                    template.back,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+            transaction.commit().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocab() -> NoteType {
+        NoteType {
+            id: Uuid::new_v4(),
+            name: "Vocab".to_string(),
+            fields: ["Word", "Reading", "Meaning", "Example"].map(String::from).to_vec(),
+            templates: vec![
+                CardTemplate {
+                    name: "Recognition".to_string(),
+                    front: "{{Word}}".to_string(),
+                    back: "{{FrontSide}}{{#Reading}} ({{Reading}}){{/Reading}}\n{{Meaning}}".to_string(),
+                },
+                CardTemplate {
+                    name: "Production".to_string(),
+                    front: "{{Meaning}}".to_string(),
+                    back: "{{Word}}".to_string(),
+                },
+            ],
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_with_front_side() {
+        let values = values(&[("Word", "犬"), ("Reading", "いぬ"), ("Meaning", "dog")]);
+        assert_eq!(
+            Some(("犬".to_string(), "犬 (いぬ)\ndog".to_string())),
+            vocab().render(0, &values)
+        );
+        assert_eq!(Some(("dog".to_string(), "犬".to_string())), vocab().render(1, &values));
+        assert_eq!(None, vocab().render(2, &values));
+    }
+
+    #[test]
+    fn test_blank_fronts_generate_no_card() {
+        let note_type = vocab();
+        assert_eq!(vec![0, 1], note_type.wanted_ordinals(&values(&[("Word", "犬"), ("Meaning", "dog")])));
+        assert_eq!(vec![0], note_type.wanted_ordinals(&values(&[("Word", "犬"), ("Meaning", " ")])));
+    }
+}
@@ -0,0 +1,147 @@
+//! Parameterised cards: `{{name=expr}}` draws or computes a value, shows it and names it
+//! for later expressions; `{{expr}}` shows a computed value. For example
+//! `{{a=rand(2,12)}} × {{b=rand(2,12)}} = ?` on the front and `{{a*b}}` on the back.
+//!
+//! A card is parameterised if its text defines a variable. Its values are drawn afresh for
+//! each review from a seed, which is kept with the review so the values can be reproduced.
+//! Anything in braces that isn't a valid expression, like a cloze deletion, is left as it is.
+
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::card::Card;
+use super::expr::Expr;
+
+/// `{{name=expr}}` split into its name and expression, if `tag` is one
+fn definition(tag: &str) -> Option<(&str, &str)> {
+    let (name, expr) = tag.split_once('=')?;
+    let name = name.trim();
+    let is_name = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    is_name.then_some((name, expr))
+}
+
+/// Each `{{...}}` in `text`, as (start, end, inner text), in order
+fn tags(text: &str) -> Vec<(usize, usize, &str)> {
+    let mut tags = Vec::new();
+    let mut from = 0usize;
+    while let Some(start) = text[from..].find("{{").map(|i| from + i) {
+        let Some(len) = text[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        tags.push((start, end, &text[start + 2..end - 2]));
+        from = end;
+    }
+    tags
+}
+
+/// Whether `text` defines a variable
+pub fn is_parameterised(text: &str) -> bool {
+    tags(text)
+        .into_iter()
+        .any(|(_, _, tag)| definition(tag).is_some_and(|(_, expr)| Expr::parse(expr).is_ok()))
+}
+
+/// Numbers are shown without a fractional part if they are whole, else to at most 6 places
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let text = format!("{:.6}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Replaces the tags of `text` with their values, defining variables in `vars` as it goes
+fn expand(text: &str, vars: &mut HashMap<String, f64>, rng: &mut StdRng) -> String {
+    let mut out = String::new();
+    let mut last = 0usize;
+    for (start, end, tag) in tags(text) {
+        out.push_str(&text[last..start]);
+        last = end;
+        let (name, source) = match definition(tag) {
+            Some((name, source)) => (Some(name), source),
+            None => (None, tag),
+        };
+        match Expr::parse(source).and_then(|expr| expr.eval(vars, rng)) {
+            Ok(value) => {
+                if let Some(name) = name {
+                    vars.insert(name.to_string(), value);
+                }
+                out.push_str(&format_number(value));
+            }
+            Err(_) => out.push_str(&text[start..end]),
+        }
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// A copy of `card` with the values drawn from `seed` filled in, front then back;
+/// `None` if the card isn't parameterised
+pub fn generate(card: &Card, seed: i64) -> Option<Card> {
+    let front = card.front_text.as_deref().unwrap_or_default();
+    let back = card.back_text.as_deref().unwrap_or_default();
+    if !is_parameterised(front) && !is_parameterised(back) {
+        return None;
+    }
+    let mut rng = StdRng::seed_from_u64(seed as u64);
+    let mut vars = HashMap::new();
+    let front_text = card.front_text.as_ref().map(|front| expand(front, &mut vars, &mut rng));
+    let back_text = card.back_text.as_ref().map(|back| expand(back, &mut vars, &mut rng));
+    Some(Card {
+        front_text,
+        back_text,
+        ..card.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(front: &str, back: &str) -> Card {
+        Card {
+            front_text: Some(front.to_string()),
+            back_text: Some(back.to_string()),
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_is_reproducible() {
+        let card = card("{{a=rand(2,12)}} × {{b=rand(2,12)}} = ?", "{{a*b}}");
+        let generated = generate(&card, 42).unwrap();
+        let front = generated.front_text.clone().unwrap();
+        let numbers: Vec<i64> = front
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse().unwrap())
+            .collect();
+        assert_eq!(2, numbers.len());
+        assert_eq!(Some((numbers[0] * numbers[1]).to_string()), generated.back_text);
+        let again = generate(&card, 42).unwrap();
+        assert_eq!((generated.front_text, generated.back_text), (again.front_text, again.back_text));
+    }
+
+    #[test]
+    fn test_other_braces_are_kept() {
+        let card = card("{{t=round(c*9/5+32, 1)}}{{c=20}} {{c1::Paris}} {{Word}}", "{{c * 9/5 + 32}}°F");
+        let generated = generate(&card, 1).unwrap();
+        // `c` is only defined after the first tag, which is kept as it was
+        assert_eq!(
+            Some("{{t=round(c*9/5+32, 1)}}20 {{c1::Paris}} {{Word}}".to_string()),
+            generated.front_text
+        );
+        assert_eq!(Some("68°F".to_string()), generated.back_text);
+        assert!(generate(&self::card("{{c1::Paris}}", "{{1+1}}"), 1).is_none());
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!("42", format_number(42.0));
+        assert_eq!("-0.5", format_number(-0.5));
+        assert_eq!("0.333333", format_number(1.0 / 3.0));
+    }
+}
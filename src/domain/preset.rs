@@ -0,0 +1,381 @@
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+use super::limits::{DeckLimits, Interleave, NewOrder};
+use super::scheduler::{CardState, Schedule, SchedulerKind};
+
+/// Desired retention accepted, as a fraction of reviews answered correctly
+const RETENTION_RANGE: std::ops::RangeInclusive<f32> = 0.7..=0.99;
+
+/// How the cards of a deck are answered
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewMode {
+    #[default]
+    Reveal, // think of the answer, then reveal it
+    Type,   // type the answer
+    Choice, // pick it out of several
+}
+
+impl ReviewMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewMode::Reveal => "reveal",
+            ReviewMode::Type => "type",
+            ReviewMode::Choice => "choice",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "reveal" => Some(ReviewMode::Reveal),
+            "type" => Some(ReviewMode::Type),
+            "choice" => Some(ReviewMode::Choice),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to a card that becomes a leech, besides being tagged
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LeechAction {
+    #[default]
+    Suspend, // keep it out of reviews until it is rewritten
+    Tag,     // only tag it
+}
+
+impl LeechAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeechAction::Suspend => "suspend",
+            LeechAction::Tag => "tag",
+        }
+    }
+
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "suspend" => Some(LeechAction::Suspend),
+            "tag" => Some(LeechAction::Tag),
+            _ => None,
+        }
+    }
+}
+
+/// Values of a preset row, as stored
+struct RawPreset {
+    id: Uuid,
+    name: String,
+    scheduler: String,
+    learning_steps: Vec<i32>,
+    new_per_day: Option<i32>,
+    reviews_per_day: Option<i32>,
+    new_order: String,
+    interleave: String,
+    desired_retention: f32,
+    leech_threshold: i32,
+    leech_action: String,
+    review_mode: String,
+}
+
+// CREATE TABLE IF NOT EXISTS presets (
+//     id UUID NOT NULL,
+//     PRIMARY KEY(id),
+//     name TEXT NOT NULL UNIQUE,
+//     ... one column per option
+// );
+/// Named deck options shared by the decks linked to it; editing the preset changes them all.
+/// Presets under `app.presets` in the configuration are added on startup if missing.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Preset {
+    #[serde(skip)]
+    pub id: Uuid,
+    pub name: String,
+    pub scheduler: SchedulerKind,
+    pub learning_steps: Vec<u32>, // minutes between the first reviews of a new or forgotten card
+    #[serde(flatten)]
+    pub limits: DeckLimits,
+    pub desired_retention: f32,
+    pub leech_threshold: u32, // lapses after which a card is a leech
+    pub leech_action: LeechAction,
+    pub review_mode: ReviewMode,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: "default".to_string(),
+            scheduler: SchedulerKind::default(),
+            learning_steps: vec![1, 10],
+            limits: DeckLimits::default(),
+            desired_retention: 0.9,
+            leech_threshold: 8,
+            leech_action: LeechAction::default(),
+            review_mode: ReviewMode::default(),
+        }
+    }
+}
+
+impl From<RawPreset> for Preset {
+    fn from(raw: RawPreset) -> Self {
+        Preset {
+            id: raw.id,
+            name: raw.name,
+            scheduler: SchedulerKind::parse(&raw.scheduler).unwrap_or_default(),
+            learning_steps: raw.learning_steps.into_iter().map(|step| step.max(1) as u32).collect(),
+            limits: DeckLimits {
+                new_per_day: raw.new_per_day.map(|n| n.max(0) as u32),
+                reviews_per_day: raw.reviews_per_day.map(|n| n.max(0) as u32),
+                new_order: NewOrder::parse(&raw.new_order).unwrap_or_default(),
+                interleave: Interleave::parse(&raw.interleave).unwrap_or_default(),
+            },
+            desired_retention: raw.desired_retention,
+            leech_threshold: raw.leech_threshold.max(1) as u32,
+            leech_action: LeechAction::parse(&raw.leech_action).unwrap_or_default(),
+            review_mode: ReviewMode::parse(&raw.review_mode).unwrap_or_default(),
+        }
+    }
+}
+
+impl Preset {
+    pub fn new(name: &str) -> Self {
+        Preset {
+            name: name.to_string(),
+            ..Preset::default()
+        }
+    }
+
+    /// `schedule` after an answer under these options: through the learning steps and then, once
+    /// the card has graduated, towards the desired retention if that scheduler is chosen
+    pub fn next_schedule(&self, schedule: &Schedule, correct: bool, hint_used: bool, now: DateTime<Utc>) -> Schedule {
+        let next = schedule.next_with_steps(correct, hint_used, &self.learning_steps, now);
+        let graduated = correct && next.state == CardState::Review;
+        match graduated && self.scheduler == SchedulerKind::Retention {
+            true => next.towards_retention(self.desired_retention),
+            false => next,
+        }
+    }
+
+    /// Parses `key=value` pairs as shown by `Display`, e.g. `steps=1,10 retention=0.85 mode=type`;
+    /// keys left out keep their current values
+    pub fn parse(&self, input: &str) -> Result<Self, String> {
+        let mut preset = self.clone();
+        for pair in input.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got `{}`", pair))?;
+            let invalid = || format!("Invalid value `{}` for `{}`", value, key);
+            match key {
+                "scheduler" => {
+                    preset.scheduler = SchedulerKind::parse(value)
+                        .ok_or_else(|| format!("Unknown scheduler `{}`: sm2 or retention", value))?
+                }
+                "steps" => {
+                    preset.learning_steps = match value {
+                        "none" => Vec::new(),
+                        steps => steps
+                            .split(',')
+                            .map(|step| step.parse::<u32>().ok())
+                            .collect::<Option<Vec<u32>>>()
+                            .ok_or_else(invalid)?,
+                    }
+                }
+                "retention" => preset.desired_retention = value.parse().map_err(|_| invalid())?,
+                "leech" => preset.leech_threshold = value.parse().map_err(|_| invalid())?,
+                "leeches" => {
+                    preset.leech_action = LeechAction::parse(value)
+                        .ok_or_else(|| format!("Unknown leech action `{}`: suspend or tag", value))?
+                }
+                "mode" => {
+                    preset.review_mode = ReviewMode::parse(value)
+                        .ok_or_else(|| format!("Unknown mode `{}`: reveal, type or choice", value))?
+                }
+                key => {
+                    if !preset.limits.set(key, value)? {
+                        return Err(format!("Unknown option `{}`", key));
+                    }
+                }
+            }
+        }
+        preset.validate()?;
+        Ok(preset)
+    }
+
+    /// Checks the options that parse but make no sense, such as a zero-minute learning step;
+    /// presets from the configuration go through this too
+    pub fn validate(&self) -> Result<(), String> {
+        if !RETENTION_RANGE.contains(&self.desired_retention) {
+            return Err(format!(
+                "`retention` must be between 0.7 and 0.99, got `{}`",
+                self.desired_retention
+            ));
+        }
+        if self.learning_steps.contains(&0) {
+            return Err("Learning `steps` must be at least 1 minute".to_string());
+        }
+        if self.leech_threshold == 0 {
+            return Err("`leech` must be at least 1 lapse".to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn load_all(db: &PgPool) -> Result<Vec<Preset>, sqlx::Error> {
+        let presets = sqlx::query_as!(
+            RawPreset,
+            r#"
+            SELECT id, name, scheduler, learning_steps, new_per_day, reviews_per_day, new_order,
+                interleave, desired_retention, leech_threshold, leech_action, review_mode
+            FROM presets
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(presets.into_iter().map(Preset::from).collect())
+    }
+
+    /// Saves the preset, which every deck linked to it picks up
+    pub async fn save(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let steps: Vec<i32> = self.learning_steps.iter().map(|step| *step as i32).collect();
+        sqlx::query!(
+            r#"
+            INSERT INTO presets (id, name, scheduler, learning_steps, new_per_day, reviews_per_day,
+                new_order, interleave, desired_retention, leech_threshold, leech_action, review_mode)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                scheduler = EXCLUDED.scheduler,
+                learning_steps = EXCLUDED.learning_steps,
+                new_per_day = EXCLUDED.new_per_day,
+                reviews_per_day = EXCLUDED.reviews_per_day,
+                new_order = EXCLUDED.new_order,
+                interleave = EXCLUDED.interleave,
+                desired_retention = EXCLUDED.desired_retention,
+                leech_threshold = EXCLUDED.leech_threshold,
+                leech_action = EXCLUDED.leech_action,
+                review_mode = EXCLUDED.review_mode
+            "#,
+            self.id,
+            self.name,
+            self.scheduler.as_str(),
+            &steps,
+            self.limits.new_per_day.map(|n| n as i32),
+            self.limits.reviews_per_day.map(|n| n as i32),
+            self.limits.new_order.as_str(),
+            self.limits.interleave.as_str(),
+            self.desired_retention,
+            self.leech_threshold as i32,
+            self.leech_action.as_str(),
+            self.review_mode.as_str(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes preset `id`; its decks keep their own options again
+    pub async fn delete(db: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM presets WHERE id = $1", id).execute(db).await?;
+        Ok(())
+    }
+
+    /// Adds the configured presets that aren't stored yet; stored ones are left as edited
+    pub async fn seed_from_config(db: &PgPool, presets: &[Preset]) -> Result<(), sqlx::Error> {
+        let stored: Vec<String> = Preset::load_all(db).await?.into_iter().map(|preset| preset.name).collect();
+        for preset in presets.iter().filter(|preset| !stored.contains(&preset.name)) {
+            Preset {
+                id: Uuid::new_v4(),
+                ..preset.clone()
+            }
+            .save(db)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Preset {
+    /// e.g. "scheduler=sm2 steps=1,10 new=20 reviews=200 order=sequential mix=mixed retention=0.9 leech=8
+    /// leeches=suspend mode=reveal"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let steps = match self.learning_steps.is_empty() {
+            true => "none".to_string(),
+            false => self.learning_steps.iter().map(u32::to_string).collect::<Vec<String>>().join(","),
+        };
+        write!(
+            f,
+            "scheduler={} steps={} {} retention={} leech={} leeches={} mode={}",
+            self.scheduler.as_str(),
+            steps,
+            self.limits,
+            self.desired_retention,
+            self.leech_threshold,
+            self.leech_action.as_str(),
+            self.review_mode.as_str()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips_display() {
+        let preset = Preset::new("Exam cram")
+            .parse("scheduler=retention steps=1,5,30 new=50 retention=0.95 leeches=tag mode=choice")
+            .unwrap();
+        assert_eq!(SchedulerKind::Retention, preset.scheduler);
+        assert_eq!(vec![1, 5, 30], preset.learning_steps);
+        assert_eq!(Some(50), preset.limits.new_per_day);
+        assert_eq!(ReviewMode::Choice, preset.review_mode);
+        assert_eq!(LeechAction::Tag, preset.leech_action);
+        assert_eq!(
+            "scheduler=retention steps=1,5,30 new=50 reviews=200 order=sequential mix=mixed retention=0.95 leech=8 leeches=tag mode=choice",
+            preset.to_string()
+        );
+        let reparsed = Preset::new("Exam cram").parse(&preset.to_string()).unwrap();
+        assert_eq!(preset.to_string(), reparsed.to_string());
+        assert!(preset.parse("retention=0.5").is_err());
+        assert!(preset.parse("steps=1,x").is_err());
+        assert!(preset.parse("steps=1,,10").is_err());
+        assert!(preset.parse("steps=0,10").is_err());
+        assert!(preset.parse("leech=0").is_err());
+        assert!(preset.parse("colour=blue").is_err());
+        assert!(preset.parse("leeches=delete").is_err());
+        assert!(preset.parse("steps=none").unwrap().learning_steps.is_empty());
+    }
+
+    #[test]
+    fn test_config_preset() {
+        let yaml = "name: Languages\nlearning_steps: [1, 10, 60]\nnew_per_day: 15\nnew_order: tag\nreview_mode: type\n";
+        let preset: Preset = config::Config::builder()
+            .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap();
+        assert_eq!("Languages", preset.name);
+        assert_eq!(vec![1, 10, 60], preset.learning_steps);
+        assert_eq!(Some(15), preset.limits.new_per_day);
+        assert_eq!(Some(200), preset.limits.reviews_per_day);
+        assert_eq!(NewOrder::ByTag, preset.limits.new_order);
+        assert_eq!(ReviewMode::Type, preset.review_mode);
+        assert_eq!(Ok(()), preset.validate());
+
+        let invalid = [
+            Preset {
+                desired_retention: 1.5,
+                ..preset.clone()
+            },
+            Preset {
+                learning_steps: vec![0, 10],
+                ..preset.clone()
+            },
+        ];
+        for preset in invalid {
+            assert!(preset.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+use super::card::Card;
+
+/// What made an edit to a card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevisionSource {
+    Tui,
+}
+
+impl RevisionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevisionSource::Tui => "tui",
+        }
+    }
+}
+
+// CREATE TABLE IF NOT EXISTS card_revisions (
+//     id UUID NOT NULL,
+//     PRIMARY KEY(id),
+//     card_id UUID NOT NULL,
+//     front_text TEXT,
+//     back_text TEXT,
+//     source TEXT NOT NULL,
+//     author TEXT,
+//     created TIMESTAMPTZ NOT NULL
+// );
+/// The text a card had before an edit, and who made that edit, when, and how
+#[derive(Debug, Clone)]
+pub struct CardRevision {
+    pub id: Uuid,
+    pub card_id: Uuid,
+    pub front_text: Option<String>,
+    pub back_text: Option<String>,
+    pub source: String,
+    pub author: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl CardRevision {
+    /// Records the text of `previous` if `edited` changes it
+    pub async fn record(
+        db: &PgPool,
+        previous: &Card,
+        edited: &Card,
+        source: RevisionSource,
+        author: &str,
+    ) -> Result<(), sqlx::Error> {
+        if previous.front_text == edited.front_text && previous.back_text == edited.back_text {
+            return Ok(());
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO card_revisions (id, card_id, front_text, back_text, source, author, created)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            Uuid::new_v4(),
+            previous.id,
+            previous.front_text,
+            previous.back_text,
+            source.as_str(),
+            author,
+            Utc::now(),
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Revisions of a card, newest first
+    pub async fn load_for_card(db: &PgPool, card_id: Uuid) -> Result<Vec<CardRevision>, sqlx::Error> {
+        sqlx::query_as!(
+            CardRevision,
+            r#"
+            SELECT id, card_id, front_text, back_text, source, author, created
+            FROM card_revisions
+            WHERE card_id = $1
+            ORDER BY created DESC
+            "#,
+            card_id,
+        )
+        .fetch_all(db)
+        .await
+    }
+}
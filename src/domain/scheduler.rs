@@ -0,0 +1,343 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use super::card::Card;
+
+pub const DEFAULT_EASE: f32 = 2.5;
+pub const MIN_EASE: f32 = 1.3;
+const LAPSE_EASE_PENALTY: f32 = 0.2;
+const HINT_EASE_PENALTY: f32 = 0.15;
+const HINT_INTERVAL_FACTOR: f32 = 1.2;
+
+/// Retention SM-2's intervals are assumed to give
+const SM2_RETENTION: f32 = 0.9;
+
+/// How intervals are worked out
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerKind {
+    /// Plain SM-2
+    #[default]
+    Sm2,
+    /// SM-2 with intervals scaled towards a desired retention
+    Retention,
+}
+
+impl SchedulerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchedulerKind::Sm2 => "sm2",
+            SchedulerKind::Retention => "retention",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "sm2" => Some(SchedulerKind::Sm2),
+            "retention" => Some(SchedulerKind::Retention),
+            _ => None,
+        }
+    }
+}
+
+/// Where a card is in its life: new cards and forgotten ones go through short learning steps
+/// before they are reviewed at day intervals again
+#[derive(sqlx::Type, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum CardState {
+    #[default]
+    New,
+    Learning,
+    Review,
+    Relearning,
+}
+
+impl CardState {
+    /// Whether the card is between learning steps, due again within minutes
+    pub fn is_learning(&self) -> bool {
+        matches!(self, CardState::Learning | CardState::Relearning)
+    }
+}
+
+/// Scheduling state of a card, following a pass/fail variant of SM-2
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub due: Option<DateTime<Utc>>,
+    pub interval_days: i32,
+    pub ease: f32,
+    pub lapses: i32,
+    pub state: CardState,
+    pub step: i32, // learning step the card is at, while learning
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            due: None,
+            interval_days: 0,
+            ease: DEFAULT_EASE,
+            lapses: 0,
+            state: CardState::New,
+            step: 0,
+        }
+    }
+}
+
+impl From<&Card> for Schedule {
+    fn from(card: &Card) -> Self {
+        Self {
+            due: card.due,
+            interval_days: card.interval_days,
+            ease: card.ease,
+            lapses: card.lapses,
+            state: card.state,
+            step: card.step,
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the schedule after answering the card at `now`
+    pub fn next(&self, correct: bool, now: DateTime<Utc>) -> Schedule {
+        let (interval_days, ease, lapses) = if correct {
+            let interval = match self.interval_days {
+                0 => 1,
+                1 => 6,
+                n => (n as f32 * self.ease).round() as i32,
+            };
+            (interval, self.ease, self.lapses)
+        } else {
+            // Forgetting a card that had graduated counts as a lapse
+            let lapses = if self.interval_days > 0 { self.lapses + 1 } else { self.lapses };
+            (1, (self.ease - LAPSE_EASE_PENALTY).max(MIN_EASE), lapses)
+        };
+
+        Schedule {
+            due: Some(now + Duration::days(interval_days as i64)),
+            interval_days,
+            ease,
+            lapses,
+            state: CardState::Review,
+            step: 0,
+        }
+    }
+
+    /// Like `next`, but a correct answer that needed the hint counts as a hard one:
+    /// the interval grows by a small fixed factor instead of the ease, which drops a little
+    pub fn next_with_hint(&self, correct: bool, hint_used: bool, now: DateTime<Utc>) -> Schedule {
+        if !correct || !hint_used {
+            return self.next(correct, now);
+        }
+        let interval_days = match self.interval_days {
+            0 => 1,
+            n => ((n as f32 * HINT_INTERVAL_FACTOR).round() as i32).max(n + 1),
+        };
+        Schedule {
+            due: Some(now + Duration::days(interval_days as i64)),
+            interval_days,
+            ease: (self.ease - HINT_EASE_PENALTY).max(MIN_EASE),
+            lapses: self.lapses,
+            state: CardState::Review,
+            step: 0,
+        }
+    }
+
+    /// Like `next_with_hint`, but new and forgotten cards first go through `steps` (minutes):
+    /// a correct answer moves to the next step, a wrong one back to the first, and passing
+    /// the last step graduates the card to day intervals. Without steps this is `next_with_hint`.
+    pub fn next_with_steps(&self, correct: bool, hint_used: bool, steps: &[u32], now: DateTime<Utc>) -> Schedule {
+        let first_step = match steps.first() {
+            Some(minutes) => *minutes,
+            None => return self.next_with_hint(correct, hint_used, now),
+        };
+        match self.state {
+            CardState::Review if correct => self.next_with_hint(true, hint_used, now),
+            CardState::Review => Schedule {
+                due: Some(now + Duration::minutes(first_step as i64)),
+                state: CardState::Relearning,
+                step: 0,
+                // The lapse is counted, and the interval to come back with set, as usual
+                ..self.next(false, now)
+            },
+            CardState::New | CardState::Learning | CardState::Relearning => {
+                let step = if correct { self.step + 1 } else { 0 };
+                match steps.get(step as usize) {
+                    Some(minutes) => Schedule {
+                        due: Some(now + Duration::minutes(*minutes as i64)),
+                        state: match self.state {
+                            CardState::Relearning => CardState::Relearning,
+                            _ => CardState::Learning,
+                        },
+                        step,
+                        ..self.clone()
+                    },
+                    // Relearnt cards go back to the interval set when they lapsed
+                    None if self.state == CardState::Relearning => Schedule {
+                        due: Some(now + Duration::days(self.interval_days.max(1) as i64)),
+                        interval_days: self.interval_days.max(1),
+                        state: CardState::Review,
+                        step: 0,
+                        ..self.clone()
+                    },
+                    None => Schedule {
+                        interval_days: 0,
+                        ..self.clone()
+                    }
+                    .next_with_hint(true, hint_used, now),
+                }
+            }
+        }
+    }
+
+    /// Scales the interval from SM-2's assumed retention to `desired_retention`: a higher
+    /// target brings reviews forward, a lower one pushes them back
+    pub fn towards_retention(self, desired_retention: f32) -> Schedule {
+        let factor = desired_retention.ln() / SM2_RETENTION.ln();
+        let interval_days = ((self.interval_days as f32 * factor).round() as i32).max(1);
+        let shift = (interval_days - self.interval_days) as i64;
+        Schedule {
+            due: self.due.map(|due| due + Duration::days(shift)),
+            interval_days,
+            ..self
+        }
+    }
+
+    /// Shortens the interval so the card comes up again before `exam` (counting from the study
+    /// day `today`), at the latest the day before it; after the exam the schedule is left alone
+    pub fn capped_before(self, exam: NaiveDate, today: NaiveDate) -> Schedule {
+        let days_left = (exam - today).num_days();
+        if days_left <= 0 {
+            return self;
+        }
+        let max_interval = (days_left - 1).max(1) as i32;
+        if self.interval_days <= max_interval {
+            return self;
+        }
+        let shortened = (self.interval_days - max_interval) as i64;
+        Schedule {
+            due: self.due.map(|due| due - Duration::days(shortened)),
+            interval_days: max_interval,
+            ..self
+        }
+    }
+
+    /// New cards (never reviewed) are always due
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.due.is_none_or(|due| due <= now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correct_answers_grow_interval() {
+        let now = Utc::now();
+        let first = Schedule::default().next(true, now);
+        assert_eq!(1, first.interval_days);
+        let second = first.next(true, now);
+        assert_eq!(6, second.interval_days);
+        let third = second.next(true, now);
+        assert_eq!(15, third.interval_days);
+        assert_eq!(Some(now + Duration::days(15)), third.due);
+    }
+
+    #[test]
+    fn test_failure_after_graduating_is_a_lapse() {
+        let now = Utc::now();
+        let learnt = Schedule { interval_days: 6, ..Schedule::default() };
+        let failed = learnt.next(false, now);
+        assert_eq!(1, failed.interval_days);
+        assert_eq!(1, failed.lapses);
+        assert!((failed.ease - 2.3).abs() < f32::EPSILON);
+
+        let failed_new = Schedule::default().next(false, now);
+        assert_eq!(0, failed_new.lapses);
+    }
+
+    #[test]
+    fn test_hinted_answer_is_weaker() {
+        let now = Utc::now();
+        let learnt = Schedule { interval_days: 10, ..Schedule::default() };
+        let hinted = learnt.next_with_hint(true, true, now);
+        assert_eq!(12, hinted.interval_days);
+        assert!(hinted.ease < learnt.ease);
+        assert_eq!(learnt.next(true, now), learnt.next_with_hint(true, false, now));
+        assert_eq!(learnt.next(false, now), learnt.next_with_hint(false, true, now));
+    }
+
+    #[test]
+    fn test_interval_is_capped_before_exam() {
+        let now = Utc::now();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let exam = NaiveDate::from_ymd_opt(2026, 10, 29).unwrap();
+        let learnt = Schedule { interval_days: 6, ..Schedule::default() }.next(true, now);
+        assert_eq!(15, learnt.interval_days);
+        let capped = learnt.clone().capped_before(exam, today);
+        assert_eq!(9, capped.interval_days);
+        assert_eq!(Some(now + Duration::days(9)), capped.due);
+        assert_eq!(learnt.ease, capped.ease);
+        // Short intervals, and schedules after the exam, are left alone
+        let soon = Schedule::default().next(true, now);
+        assert_eq!(soon, soon.clone().capped_before(exam, today));
+        assert_eq!(learnt, learnt.clone().capped_before(today, exam));
+    }
+
+    #[test]
+    fn test_desired_retention_scales_interval() {
+        let now = Utc::now();
+        let learnt = Schedule { interval_days: 10, ..Schedule::default() }.next(true, now);
+        assert_eq!(learnt, learnt.clone().towards_retention(0.9));
+        let stricter = learnt.clone().towards_retention(0.95);
+        assert_eq!(12, stricter.interval_days);
+        assert_eq!(Some(now + Duration::days(12)), stricter.due);
+        assert_eq!(53, learnt.towards_retention(0.8).interval_days);
+    }
+
+    #[test]
+    fn test_learning_steps_before_graduating() {
+        let now = Utc::now();
+        let steps = [1, 10, 60];
+        let first = Schedule::default().next_with_steps(true, false, &steps, now);
+        assert_eq!(CardState::Learning, first.state);
+        assert_eq!(Some(now + Duration::minutes(10)), first.due);
+        let failed = first.next_with_steps(false, false, &steps, now);
+        assert_eq!((CardState::Learning, 0), (failed.state, failed.step));
+        assert_eq!(Some(now + Duration::minutes(1)), failed.due);
+        let last = failed
+            .next_with_steps(true, false, &steps, now)
+            .next_with_steps(true, false, &steps, now);
+        assert_eq!(Some(now + Duration::minutes(60)), last.due);
+        let graduated = last.next_with_steps(true, false, &steps, now);
+        assert_eq!((CardState::Review, 1), (graduated.state, graduated.interval_days));
+        assert_eq!(0, graduated.lapses);
+        // Without steps, cards graduate straight away
+        assert_eq!(
+            Schedule::default().next(true, now),
+            Schedule::default().next_with_steps(true, false, &[], now)
+        );
+    }
+
+    #[test]
+    fn test_lapse_goes_through_relearning() {
+        let now = Utc::now();
+        let learnt = Schedule { interval_days: 15, state: CardState::Review, ..Schedule::default() };
+        let lapsed = learnt.next_with_steps(false, false, &[10], now);
+        assert_eq!(CardState::Relearning, lapsed.state);
+        assert_eq!(1, lapsed.lapses);
+        assert_eq!(Some(now + Duration::minutes(10)), lapsed.due);
+        // Failing again while relearning is not another lapse
+        let again = lapsed.next_with_steps(false, false, &[10], now);
+        assert_eq!(1, again.lapses);
+        let relearnt = again.next_with_steps(true, false, &[10], now);
+        assert_eq!(CardState::Review, relearnt.state);
+        assert_eq!(Some(now + Duration::days(1)), relearnt.due);
+        assert!((relearnt.ease - 2.3).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_ease_has_floor() {
+        let schedule = Schedule { interval_days: 3, ease: MIN_EASE, ..Schedule::default() };
+        assert_eq!(MIN_EASE, schedule.next(false, Utc::now()).ease);
+    }
+}
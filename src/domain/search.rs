@@ -0,0 +1,323 @@
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use super::card::Card;
+use super::limits::DECK_SEPARATOR;
+
+/// Global card search, e.g. `deck:spanish tag:verb -is:suspended lapses:>3 front:"to be"`
+///
+/// Terms are AND-ed together; prefix a term with `-` to negate it.
+/// Supported terms:
+///     text                 front or back contains `text`
+///     front:"phrase"       front contains `phrase` (also `back:`)
+///     deck:name            card is in deck `name` or a deck below it (`name::child`)
+///     tag:name             card has tag `name`
+///     is:due|new|suspended scheduling state
+///     created:<7d          created less than 7 days ago (`d`, `w`, `m`, `y`)
+///     lapses:>3            lapse count comparison (`<`, `<=`, `=`, `>=`, `>`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub terms: Vec<SearchTerm>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchTerm {
+    Text(String),
+    Front(String),
+    Back(String),
+    Deck(String),
+    Tag(String),
+    Due,
+    New,
+    Suspended,
+    Created(Comparison, i32), // age in days
+    Lapses(Comparison, i32),
+    Not(Box<SearchTerm>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    /// Splits a leading comparison operator off `value`, defaulting to `=`
+    fn split(value: &str) -> (Self, &str) {
+        for (prefix, cmp) in [
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+            ("=", Comparison::Eq),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (cmp, rest);
+            }
+        }
+        (Comparison::Eq, value)
+    }
+
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Eq => "=",
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let terms = tokenise(input)
+            .iter()
+            .map(|token| parse_term(token))
+            .collect::<Result<Vec<SearchTerm>, String>>()?;
+        Ok(Self {
+            text: input.trim().to_string(),
+            terms,
+        })
+    }
+
+    /// Compiles the query to a `SELECT` returning rows for `Card`
+    pub fn build(&self) -> QueryBuilder<'static, Postgres> {
+        let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+            r#"
+            SELECT c.id, c.front_text, c.back_text, c.deck_id, c.created, c.modified,
+                ARRAY(
+                    SELECT t.name FROM tags t
+                    JOIN card_tags ct ON ct.tag_id = t.id
+                    WHERE ct.card_id = c.id
+                    ORDER BY t.name
+                ) AS tags,
+                c.due, c.interval_days, c.ease, c.lapses, c.suspended, c.note_id, c.ordinal,
+                c.hint, c.notes, c.source, c.state, c.step
+            FROM cards c
+            LEFT JOIN decks d ON d.id = c.deck_id
+            WHERE c.deleted IS NULL"#,
+        );
+        for term in &self.terms {
+            qb.push(" AND ");
+            push_term(&mut qb, term);
+        }
+        qb.push(" ORDER BY d.name, c.created");
+        qb
+    }
+
+    pub async fn run(&self, db: &PgPool) -> Result<Vec<Card>, sqlx::Error> {
+        self.build().build_query_as::<Card>().fetch_all(db).await
+    }
+}
+
+/// Splits on whitespace outside of double quotes, dropping the quotes
+fn tokenise(input: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for ch in input.chars() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_term(token: &str) -> Result<SearchTerm, String> {
+    if let Some(inner) = token.strip_prefix('-') {
+        if !inner.is_empty() {
+            return Ok(SearchTerm::Not(Box::new(parse_term(inner)?)));
+        }
+    }
+
+    let (key, value) = match token.split_once(':') {
+        Some((key, value)) => (key.to_lowercase(), value),
+        None => return Ok(SearchTerm::Text(token.to_string())),
+    };
+    if value.is_empty() {
+        return Err(format!("Missing value for `{}:`", key));
+    }
+
+    match key.as_str() {
+        "front" => Ok(SearchTerm::Front(value.to_string())),
+        "back" => Ok(SearchTerm::Back(value.to_string())),
+        "deck" => Ok(SearchTerm::Deck(value.to_string())),
+        "tag" => Ok(SearchTerm::Tag(value.to_lowercase())),
+        "is" => match value.to_lowercase().as_str() {
+            "due" => Ok(SearchTerm::Due),
+            "new" => Ok(SearchTerm::New),
+            "suspended" => Ok(SearchTerm::Suspended),
+            other => Err(format!("Unknown state `is:{}`", other)),
+        },
+        "created" => {
+            let (cmp, age) = Comparison::split(value);
+            Ok(SearchTerm::Created(cmp, parse_days(age)?))
+        }
+        "lapses" => {
+            let (cmp, count) = Comparison::split(value);
+            let count = count
+                .parse::<i32>()
+                .map_err(|_| format!("Invalid number `{}` for `lapses:`", count))?;
+            Ok(SearchTerm::Lapses(cmp, count))
+        }
+        // Anything else (e.g. a URL) is searched for as-is
+        _ => Ok(SearchTerm::Text(token.to_string())),
+    }
+}
+
+/// Parses an age such as `7d`, `2w`, `3m` or `1y` into days
+fn parse_days(value: &str) -> Result<i32, String> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'd')) => (&value[..i], 1),
+        Some((i, 'w')) => (&value[..i], 7),
+        Some((i, 'm')) => (&value[..i], 30),
+        Some((i, 'y')) => (&value[..i], 365),
+        _ => (value, 1),
+    };
+    number
+        .parse::<i32>()
+        .map(|n| n * multiplier)
+        .map_err(|_| format!("Invalid age `{}`", value))
+}
+
+/// Escapes `LIKE` wildcards and wraps the text for a substring match
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn push_term(qb: &mut QueryBuilder<'static, Postgres>, term: &SearchTerm) {
+    match term {
+        SearchTerm::Text(text) => {
+            qb.push("(c.front_text ILIKE ")
+                .push_bind(like_pattern(text))
+                .push(" OR c.back_text ILIKE ")
+                .push_bind(like_pattern(text))
+                .push(")");
+        }
+        SearchTerm::Front(text) => {
+            qb.push("c.front_text ILIKE ").push_bind(like_pattern(text));
+        }
+        SearchTerm::Back(text) => {
+            qb.push("c.back_text ILIKE ").push_bind(like_pattern(text));
+        }
+        SearchTerm::Deck(name) => {
+            qb.push("(lower(d.name) = lower(")
+                .push_bind(name.clone())
+                .push(") OR starts_with(lower(d.name), lower(")
+                .push_bind(format!("{}{}", name, DECK_SEPARATOR))
+                .push(")))");
+        }
+        SearchTerm::Tag(name) => {
+            qb.push(
+                "EXISTS (SELECT 1 FROM card_tags ct JOIN tags t ON t.id = ct.tag_id \
+                WHERE ct.card_id = c.id AND t.name = ",
+            )
+            .push_bind(name.clone())
+            .push(")");
+        }
+        SearchTerm::Due => {
+            qb.push("(c.due IS NOT NULL AND c.due <= now() AND NOT c.suspended)");
+        }
+        SearchTerm::New => {
+            qb.push("c.due IS NULL");
+        }
+        SearchTerm::Suspended => {
+            qb.push("c.suspended");
+        }
+        SearchTerm::Created(cmp, days) => {
+            qb.push("(now() - c.created) ")
+                .push(cmp.as_sql())
+                .push(" make_interval(days => ")
+                .push_bind(*days)
+                .push(")");
+        }
+        SearchTerm::Lapses(cmp, count) => {
+            qb.push("c.lapses ").push(cmp.as_sql()).push(" ").push_bind(*count);
+        }
+        SearchTerm::Not(inner) => {
+            // `IS NOT TRUE` so NULL text columns still count as "not matching"
+            qb.push("(");
+            push_term(qb, inner);
+            qb.push(") IS NOT TRUE");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_terms() {
+        let query = SearchQuery::parse(r#"cat deck:Spanish -tag:Verb front:"to be" is:due"#).unwrap();
+        assert_eq!(
+            vec![
+                SearchTerm::Text("cat".to_string()),
+                SearchTerm::Deck("Spanish".to_string()),
+                SearchTerm::Not(Box::new(SearchTerm::Tag("verb".to_string()))),
+                SearchTerm::Front("to be".to_string()),
+                SearchTerm::Due,
+            ],
+            query.terms
+        );
+    }
+
+    #[test]
+    fn test_parse_comparisons() {
+        let query = SearchQuery::parse("created:<7d lapses:>3 created:>=2w lapses:0").unwrap();
+        assert_eq!(
+            vec![
+                SearchTerm::Created(Comparison::Lt, 7),
+                SearchTerm::Lapses(Comparison::Gt, 3),
+                SearchTerm::Created(Comparison::Ge, 14),
+                SearchTerm::Lapses(Comparison::Eq, 0),
+            ],
+            query.terms
+        );
+    }
+
+    #[test]
+    fn test_invalid_terms_rejected() {
+        assert!(SearchQuery::parse("is:lost").is_err());
+        assert!(SearchQuery::parse("lapses:>many").is_err());
+        assert!(SearchQuery::parse("created:soon").is_err());
+        assert!(SearchQuery::parse("deck:").is_err());
+    }
+
+    #[test]
+    fn test_compiles_to_bound_sql() {
+        let query = SearchQuery::parse("50% -is:suspended lapses:>3").unwrap();
+        let qb = query.build();
+        let sql = qb.sql();
+        assert!(sql.contains("(c.front_text ILIKE $1 OR c.back_text ILIKE $2)"));
+        assert!(sql.contains("AND (c.suspended) IS NOT TRUE"));
+        assert!(sql.contains("AND c.lapses > $3"));
+        assert_eq!("%50\\%%", like_pattern("50%"));
+    }
+
+    #[test]
+    fn test_deck_includes_children() {
+        let query = SearchQuery::parse("deck:Languages").unwrap();
+        let qb = query.build();
+        assert!(qb
+            .sql()
+            .contains("(lower(d.name) = lower($1) OR starts_with(lower(d.name), lower($2)))"));
+    }
+}
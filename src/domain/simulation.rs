@@ -0,0 +1,380 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sqlx::PgPool;
+
+use super::activity::day_of;
+use super::deck::Deck;
+use super::deckset::DeckSet;
+use super::preset::Preset;
+use super::scheduler::{CardState, Schedule, DEFAULT_EASE};
+
+/// Longest simulation accepted, in days
+const MAX_DAYS: u32 = 3650;
+/// Most runs accepted
+const MAX_RUNS: u32 = 1000;
+/// Most reviews of one card on one simulated day; a card that keeps failing its learning
+/// steps waits for the next day after this many
+const MAX_DAILY_REVIEWS: u32 = 10;
+
+/// What to simulate, parsed from `key=value` pairs, e.g.
+/// `deck=Spanish cards=5000 new=20 retention=0.9 days=365`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimParams {
+    /// Name of a deck whose cards are included; without one, only `extra_cards` are
+    pub deck: Option<String>,
+    /// Hypothetical new cards added on top of the deck's
+    pub extra_cards: u32,
+    pub new_per_day: u32,
+    /// Chance of answering a due card correctly
+    pub retention: f64,
+    pub days: u32,
+    pub runs: u32,
+    /// Ease new cards start with
+    pub initial_ease: f32,
+    pub seed: u64,
+}
+
+impl Default for SimParams {
+    fn default() -> Self {
+        Self {
+            deck: None,
+            extra_cards: 0,
+            new_per_day: 20,
+            retention: 0.9,
+            days: 365,
+            runs: 20,
+            initial_ease: DEFAULT_EASE,
+            seed: rand::random(),
+        }
+    }
+}
+
+impl SimParams {
+    /// Parses whitespace-separated `key=value` pairs; keys left out keep their defaults
+    pub fn parse<'a>(pairs: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        fn number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("Invalid value `{}` for `{}`", value, key))
+        }
+        let mut params = SimParams::default();
+        for pair in pairs {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got `{}`", pair))?;
+            match key {
+                "deck" => params.deck = Some(value.to_string()),
+                "cards" => params.extra_cards = number(key, value)?,
+                "new" => params.new_per_day = number(key, value)?,
+                "retention" => params.retention = number(key, value)?,
+                "days" => params.days = number(key, value)?,
+                "runs" => params.runs = number(key, value)?,
+                "ease" => params.initial_ease = number(key, value)?,
+                "seed" => params.seed = number(key, value)?,
+                _ => return Err(format!("Unknown option `{}`", key)),
+            }
+        }
+        if !(0.0..=1.0).contains(&params.retention) {
+            return Err("`retention` must be between 0 and 1".to_string());
+        }
+        if !(1..=MAX_DAYS).contains(&params.days) || !(1..=MAX_RUNS).contains(&params.runs) {
+            return Err(format!("`days` must be 1-{} and `runs` 1-{}", MAX_DAYS, MAX_RUNS));
+        }
+        Ok(params)
+    }
+}
+
+/// A card at the start of the simulation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimCard {
+    pub schedule: Schedule,
+    /// Days from the start until it is due; `None` for a new card
+    pub due_in: Option<u32>,
+}
+
+impl SimCard {
+    /// The live, unsuspended cards of the deck called `deck`, as of `now`
+    pub async fn load_deck(db: &PgPool, deck: &str, now: DateTime<Utc>) -> Result<Vec<SimCard>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT cards.due, cards.interval_days, cards.ease, cards.lapses,
+                cards.state AS "state: CardState", cards.step
+            FROM cards
+            JOIN decks ON decks.id = cards.deck_id
+            WHERE decks.name = $1 AND decks.deleted IS NULL
+                AND cards.deleted IS NULL AND NOT cards.suspended
+            "#,
+            deck,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| SimCard {
+                schedule: Schedule {
+                    due: row.due,
+                    interval_days: row.interval_days,
+                    ease: row.ease,
+                    lapses: row.lapses,
+                    state: row.state,
+                    step: row.step,
+                },
+                due_in: row.due.map(|due| (due - now).num_days().max(0) as u32),
+            })
+            .collect())
+    }
+}
+
+/// Expected load on one simulated day
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayLoad {
+    /// Reviews, including the first review of new cards, averaged over the runs
+    pub mean_reviews: f64,
+    /// Reviews on the busiest 10% of runs
+    pub p90_reviews: u64,
+    pub mean_new: f64,
+}
+
+/// Simulates reviewing `cards`, plus `params.extra_cards` new ones, every day for `params.days`
+/// days from `start`, `params.runs` times. Answers are drawn at `params.retention` and
+/// scheduled as in a real review: with the deck's `options` (learning steps, which are
+/// repeated the same day, and scheduler), and intervals ending before `exam_date` if set,
+/// counted in study days starting at `rollover_hour`.
+pub fn simulate(
+    cards: &[SimCard],
+    params: &SimParams,
+    options: &Preset,
+    exam_date: Option<NaiveDate>,
+    start: DateTime<Utc>,
+    rollover_hour: u32,
+) -> Vec<DayLoad> {
+    let days = params.days as usize;
+    let mut reviews: Vec<Vec<u64>> = vec![Vec::with_capacity(params.runs as usize); days];
+    let mut new: Vec<u64> = vec![0; days];
+
+    for run in 0..params.runs {
+        let mut rng = StdRng::seed_from_u64(params.seed.wrapping_add(run as u64));
+        let mut schedules: Vec<Schedule> = cards.iter().map(|card| card.schedule.clone()).collect();
+        let mut due: Vec<Vec<usize>> = vec![Vec::new(); days];
+        let mut unseen: VecDeque<usize> = VecDeque::new();
+        for (i, card) in cards.iter().enumerate() {
+            match card.due_in {
+                Some(day) if (day as usize) < days => due[day as usize].push(i),
+                Some(_) => {}
+                None => unseen.push_back(i),
+            }
+        }
+        for _ in 0..params.extra_cards {
+            unseen.push_back(schedules.len());
+            schedules.push(Schedule {
+                ease: params.initial_ease,
+                ..Schedule::default()
+            });
+        }
+
+        for day in 0..days {
+            let now = start + Duration::days(day as i64);
+            let mut today: VecDeque<usize> = std::mem::take(&mut due[day]).into();
+            let introduced = unseen.len().min(params.new_per_day as usize);
+            today.extend(unseen.drain(..introduced));
+            new[day] += introduced as u64;
+            let mut reviewed: HashMap<usize, u32> = HashMap::new();
+            while let Some(i) = today.pop_front() {
+                let correct = rng.gen_bool(params.retention);
+                let mut schedule = options.next_schedule(&schedules[i], correct, false, now);
+                if let Some(exam) = exam_date {
+                    schedule = schedule.capped_before(exam, day_of(now, rollover_hour));
+                }
+                let next = match schedule.due {
+                    Some(due) => (due - start).num_days().max(0) as usize,
+                    None => day + 1,
+                };
+                schedules[i] = schedule;
+                let n_reviews = reviewed.entry(i).or_default();
+                *n_reviews += 1;
+                if next <= day && *n_reviews < MAX_DAILY_REVIEWS {
+                    today.push_back(i);
+                } else if next.max(day + 1) < days {
+                    due[next.max(day + 1)].push(i);
+                }
+            }
+            reviews[day].push(reviewed.values().map(|n| *n as u64).sum());
+        }
+    }
+
+    let runs = params.runs as f64;
+    reviews
+        .into_iter()
+        .zip(new)
+        .map(|(mut counts, new)| {
+            counts.sort_unstable();
+            let p90 = ((counts.len() as f64 * 0.9).ceil() as usize).clamp(1, counts.len()) - 1;
+            DayLoad {
+                mean_reviews: counts.iter().sum::<u64>() as f64 / runs,
+                p90_reviews: counts[p90],
+                mean_new: new as f64 / runs,
+            }
+        })
+        .collect()
+}
+
+/// Simulates `params`, starting now, with the cards and options of `params.deck` if one is named;
+/// study days start at `rollover_hour`
+pub async fn run(db: &PgPool, params: &SimParams, rollover_hour: u32) -> Result<Vec<DayLoad>, sqlx::Error> {
+    let now = Utc::now();
+    let deck = match &params.deck {
+        Some(name) => DeckSet::load_from_db(db).await?.decks.into_iter().find(|deck| deck.name == *name),
+        None => None,
+    };
+    let cards = match &deck {
+        Some(deck) => SimCard::load_deck(db, &deck.name, now).await?,
+        None => Vec::new(),
+    };
+    let options = deck.as_ref().map(Deck::options).unwrap_or_default();
+    let exam_date = deck.and_then(|deck| deck.exam_date);
+    Ok(simulate(&cards, params, &options, exam_date, now, rollover_hour))
+}
+
+/// The simulated load as CSV, one row per day
+pub fn to_csv(loads: &[DayLoad]) -> String {
+    let mut csv = String::from("day,mean_reviews,p90_reviews,mean_new\n");
+    for (day, load) in loads.iter().enumerate() {
+        csv.push_str(&format!(
+            "{},{:.2},{},{:.2}\n",
+            day + 1,
+            load.mean_reviews,
+            load.p90_reviews,
+            load.mean_new
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::scheduler::SchedulerKind;
+
+    /// Plain SM-2, as without learning steps
+    fn no_steps() -> Preset {
+        Preset {
+            learning_steps: Vec::new(),
+            ..Preset::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let params = SimParams::parse("deck=Spanish cards=5000 new=25 retention=0.85 seed=7".split_whitespace()).unwrap();
+        assert_eq!(Some("Spanish".to_string()), params.deck);
+        assert_eq!(5000, params.extra_cards);
+        assert_eq!(25, params.new_per_day);
+        assert_eq!(7, params.seed);
+        assert_eq!(365, params.days);
+        assert!(SimParams::parse(["retention=2"]).is_err());
+        assert!(SimParams::parse(["speed=fast"]).is_err());
+        assert!(SimParams::parse(["days"]).is_err());
+    }
+
+    #[test]
+    fn test_new_cards_are_spread_and_reviewed() {
+        let params = SimParams {
+            extra_cards: 50,
+            new_per_day: 20,
+            retention: 1.0,
+            days: 10,
+            runs: 3,
+            seed: 1,
+            ..SimParams::default()
+        };
+        let loads = simulate(&[], &params, &no_steps(), None, Utc::now(), 4);
+        let new: Vec<f64> = loads.iter().take(4).map(|load| load.mean_new).collect();
+        assert_eq!(vec![20.0, 20.0, 10.0, 0.0], new);
+        // Always correct: day 2 reviews day 1's cards again (interval 1), plus 20 new ones
+        assert_eq!(20.0, loads[0].mean_reviews);
+        assert_eq!(40.0, loads[1].mean_reviews);
+        // The first cards come back 6 days after their second review
+        assert_eq!(20, loads[7].p90_reviews);
+        assert_eq!(params.days as usize, loads.len());
+    }
+
+    #[test]
+    fn test_existing_cards_come_due() {
+        let card = SimCard {
+            schedule: Schedule {
+                interval_days: 6,
+                ..Schedule::default()
+            },
+            due_in: Some(2),
+        };
+        let params = SimParams {
+            days: 5,
+            runs: 1,
+            retention: 0.0,
+            ..SimParams::default()
+        };
+        let loads = simulate(&[card], &params, &no_steps(), None, Utc::now(), 4);
+        // Forgotten on day 3, so back the next day
+        let reviews: Vec<u64> = loads.iter().map(|load| load.p90_reviews).collect();
+        assert_eq!(vec![0, 0, 1, 1, 1], reviews);
+        assert!(to_csv(&loads).starts_with("day,mean_reviews,p90_reviews,mean_new\n1,0.00,0,0.00\n"));
+    }
+
+    #[test]
+    fn test_learning_steps_are_reviewed_the_same_day() {
+        let params = SimParams {
+            extra_cards: 1,
+            retention: 1.0,
+            days: 3,
+            runs: 1,
+            ..SimParams::default()
+        };
+        let options = Preset {
+            learning_steps: vec![1, 10],
+            ..Preset::default()
+        };
+        let loads = simulate(&[], &params, &options, None, Utc::now(), 4);
+        // The first review and the 10 minute step on day 1, then the first one-day interval
+        let reviews: Vec<u64> = loads.iter().map(|load| load.p90_reviews).collect();
+        assert_eq!(vec![2, 1, 0], reviews);
+
+        // Always forgotten: only repeated up to the daily cap
+        let params = SimParams { retention: 0.0, ..params };
+        let loads = simulate(&[], &params, &options, None, Utc::now(), 4);
+        assert_eq!(MAX_DAILY_REVIEWS as u64, loads[0].p90_reviews);
+    }
+
+    #[test]
+    fn test_retention_scheduler_and_exam_cap() {
+        let card = SimCard {
+            schedule: Schedule {
+                interval_days: 10,
+                state: CardState::Review,
+                ..Schedule::default()
+            },
+            due_in: Some(0),
+        };
+        let params = SimParams {
+            retention: 1.0,
+            days: 40,
+            runs: 1,
+            ..SimParams::default()
+        };
+        let due_days = |loads: &[DayLoad]| -> Vec<usize> {
+            loads.iter().enumerate().filter(|(_, load)| load.p90_reviews > 0).map(|(day, _)| day).collect()
+        };
+        let start = Utc::now();
+        let sm2 = due_days(&simulate(std::slice::from_ref(&card), &params, &no_steps(), None, start, 4));
+        let retention = Preset {
+            scheduler: SchedulerKind::Retention,
+            desired_retention: 0.97,
+            ..no_steps()
+        };
+        let sooner = due_days(&simulate(std::slice::from_ref(&card), &params, &retention, None, start, 4));
+        assert!(sooner[1] < sm2[1]);
+
+        let exam = day_of(start, 4) + Duration::days(5);
+        let capped = due_days(&simulate(&[card], &params, &no_steps(), Some(exam), start, 4));
+        assert_eq!(vec![0, 4], capped[..2].to_vec());
+    }
+}
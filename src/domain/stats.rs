@@ -0,0 +1,223 @@
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::activity::day_of;
+
+/// Days covered by the history and forecast charts
+pub const STATS_DAYS: usize = 30;
+/// Upper bounds (inclusive) of the interval distribution buckets, in days; the last is open
+const INTERVAL_BUCKETS: [(i32, &str); 6] = [(1, "1d"), (7, "≤1w"), (30, "≤1m"), (90, "≤3m"), (365, "≤1y"), (i32::MAX, ">1y")];
+/// Upper bounds (exclusive) of the ease distribution buckets; the last is open
+const EASE_BUCKETS: [(f32, &str); 5] = [(1.7, "<1.7"), (2.1, "<2.1"), (2.5, "<2.5"), (2.9, "<2.9"), (f32::MAX, "2.9+")];
+
+/// A logged review, as far as the statistics are concerned
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewEntry {
+    pub day: NaiveDate,
+    pub correct: bool,
+    pub duration_ms: i32,
+}
+
+/// A card's schedule, as far as the statistics are concerned
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardEntry {
+    pub due: Option<NaiveDate>, // `None` for new cards
+    pub interval_days: i32,
+    pub ease: f32,
+}
+
+/// Study statistics over the last `STATS_DAYS` days and the next ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// Reviews per day, oldest first, ending today
+    pub reviews: Vec<u64>,
+    /// Percentage of reviews answered correctly per day, for days with reviews,
+    /// as (days before today, percentage)
+    pub retention: Vec<(f64, f64)>,
+    /// Cards due per day, starting today (including overdue ones)
+    pub forecast: Vec<u64>,
+    /// Reviewed cards per interval bucket, as (label, cards)
+    pub intervals: Vec<(&'static str, u64)>,
+    /// Reviewed cards per ease bucket, as (label, cards)
+    pub eases: Vec<(&'static str, u64)>,
+    /// Minutes spent reviewing per day, oldest first, ending today
+    pub minutes: Vec<u64>,
+}
+
+impl Stats {
+    /// Statistics for the cards of `deck_id`, or of every deck; trashed and suspended cards
+    /// are left out of the forecast and distributions. Days start at `rollover_hour`.
+    pub async fn load(db: &PgPool, deck_id: Option<Uuid>, rollover_hour: u32) -> Result<Stats, sqlx::Error> {
+        let since = Utc::now() - chrono::Duration::days(STATS_DAYS as i64 + 1);
+        let views = sqlx::query!(
+            r#"
+            SELECT views.time, views.result, views.duration_ms
+            FROM views
+            LEFT JOIN cards ON cards.id = views.card_id
+            WHERE views.time >= $1 AND ($2::uuid IS NULL OR cards.deck_id = $2)
+            "#,
+            since,
+            deck_id,
+        )
+        .fetch_all(db)
+        .await?;
+        let cards = sqlx::query!(
+            r#"
+            SELECT due, interval_days, ease
+            FROM cards
+            WHERE deleted IS NULL AND NOT suspended AND ($1::uuid IS NULL OR deck_id = $1)
+            "#,
+            deck_id,
+        )
+        .fetch_all(db)
+        .await?;
+
+        let reviews: Vec<ReviewEntry> = views
+            .into_iter()
+            .filter_map(|row| {
+                Some(ReviewEntry {
+                    day: day_of(row.time?, rollover_hour),
+                    correct: row.result.unwrap_or(false),
+                    duration_ms: row.duration_ms.unwrap_or(0),
+                })
+            })
+            .collect();
+        let cards: Vec<CardEntry> = cards
+            .into_iter()
+            .map(|row| CardEntry {
+                due: row.due.map(|due| day_of(due, rollover_hour)),
+                interval_days: row.interval_days,
+                ease: row.ease,
+            })
+            .collect();
+        Ok(Stats::compute(&reviews, &cards, day_of(Utc::now(), rollover_hour)))
+    }
+
+    pub fn compute(reviews: &[ReviewEntry], cards: &[CardEntry], today: NaiveDate) -> Stats {
+        // Index of `day` in the history, if it is one of the last `STATS_DAYS` days
+        let history_index = |day: NaiveDate| {
+            let ago = (today - day).num_days();
+            (0..STATS_DAYS as i64).contains(&ago).then(|| STATS_DAYS - 1 - ago as usize)
+        };
+
+        let mut counts = vec![0u64; STATS_DAYS];
+        let mut correct = vec![0u64; STATS_DAYS];
+        let mut millis = vec![0u64; STATS_DAYS];
+        for review in reviews {
+            if let Some(i) = history_index(review.day) {
+                counts[i] += 1;
+                correct[i] += review.correct as u64;
+                millis[i] += review.duration_ms.max(0) as u64;
+            }
+        }
+        let retention = counts
+            .iter()
+            .zip(&correct)
+            .enumerate()
+            .filter(|(_, (count, _))| **count > 0)
+            .map(|(i, (count, correct))| {
+                let ago = (STATS_DAYS - 1 - i) as f64;
+                (ago, 100.0 * *correct as f64 / *count as f64)
+            })
+            .collect();
+
+        let mut forecast = vec![0u64; STATS_DAYS];
+        for due in cards.iter().filter_map(|card| card.due) {
+            let ahead = (due - today).num_days().max(0) as usize;
+            if let Some(count) = forecast.get_mut(ahead) {
+                *count += 1;
+            }
+        }
+
+        // New cards have no interval or ease to speak of yet
+        let reviewed: Vec<&CardEntry> = cards.iter().filter(|card| card.due.is_some()).collect();
+        let intervals = INTERVAL_BUCKETS
+            .iter()
+            .enumerate()
+            .map(|(i, (max, label))| {
+                let min = if i == 0 { i32::MIN } else { INTERVAL_BUCKETS[i - 1].0 };
+                let count = reviewed
+                    .iter()
+                    .filter(|card| card.interval_days > min && card.interval_days <= *max)
+                    .count();
+                (*label, count as u64)
+            })
+            .collect();
+        let eases = EASE_BUCKETS
+            .iter()
+            .enumerate()
+            .map(|(i, (max, label))| {
+                let min = if i == 0 { f32::MIN } else { EASE_BUCKETS[i - 1].0 };
+                let count = reviewed.iter().filter(|card| card.ease >= min && card.ease < *max).count();
+                (*label, count as u64)
+            })
+            .collect();
+
+        Stats {
+            reviews: counts,
+            retention,
+            forecast,
+            intervals,
+            eases,
+            minutes: millis.iter().map(|ms| ms.div_ceil(60_000)).collect(),
+        }
+    }
+
+    pub fn total_reviews(&self) -> u64 {
+        self.reviews.iter().sum()
+    }
+
+    pub fn total_minutes(&self) -> u64 {
+        self.minutes.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_history_and_retention() {
+        let review = |d, correct| ReviewEntry {
+            day: day(d),
+            correct,
+            duration_ms: 90_000,
+        };
+        let reviews = vec![review(19, true), review(19, false), review(18, true), review(1, true)];
+        let stats = Stats::compute(&reviews, &[], day(19));
+        assert_eq!(STATS_DAYS, stats.reviews.len());
+        assert_eq!(2, stats.reviews[STATS_DAYS - 1]);
+        assert_eq!(1, stats.reviews[STATS_DAYS - 2]);
+        assert_eq!(1, stats.reviews[STATS_DAYS - 19]);
+        // Seen from 19 November, all of them are more than 30 days ago
+        let later = NaiveDate::from_ymd_opt(2026, 11, 19).unwrap();
+        assert_eq!(0, Stats::compute(&reviews, &[], later).total_reviews());
+        assert_eq!(vec![(18.0, 100.0), (1.0, 100.0), (0.0, 50.0)], stats.retention);
+        assert_eq!(3, stats.minutes[STATS_DAYS - 1]);
+    }
+
+    #[test]
+    fn test_forecast_and_distributions() {
+        let card = |due: Option<u32>, interval_days, ease| CardEntry {
+            due: due.map(day),
+            interval_days,
+            ease,
+        };
+        let cards = vec![
+            card(Some(10), 1, 2.5),
+            card(Some(19), 5, 2.0),
+            card(Some(25), 40, 2.5),
+            card(None, 0, 2.5),
+        ];
+        let stats = Stats::compute(&[], &cards, day(19));
+        assert_eq!(2, stats.forecast[0]);
+        assert_eq!(1, stats.forecast[6]);
+        assert_eq!(vec![1, 1, 0, 1, 0, 0], stats.intervals.iter().map(|(_, n)| *n).collect::<Vec<u64>>());
+        assert_eq!(vec![0, 1, 0, 2, 0], stats.eases.iter().map(|(_, n)| *n).collect::<Vec<u64>>());
+    }
+}
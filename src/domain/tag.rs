@@ -0,0 +1,123 @@
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+// CREATE TABLE IF NOT EXISTS tags (
+//     id UUID NOT NULL,
+//     PRIMARY KEY(id),
+//     name TEXT UNIQUE NOT NULL,
+//     created TIMESTAMPTZ
+// );
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl Tag {
+    /// Splits user input such as `verb, irregular past` into tag names
+    pub fn parse_names(input: &str) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for name in input.split(|c: char| c == ',' || c.is_whitespace()) {
+            let name = name.trim().to_lowercase();
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub async fn load_all(db: &PgPool) -> Result<Vec<Tag>, sqlx::Error> {
+        sqlx::query_as!(
+            Tag,
+            r#"
+            SELECT id, name, created FROM tags
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(db)
+        .await
+    }
+
+    /// Returns the id of tag `name`, creating the tag if it doesn't exist yet
+    pub async fn get_or_create(db: &PgPool, name: &str) -> Result<Uuid, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO tags (id, name, created)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO UPDATE
+            SET name = EXCLUDED.name
+            RETURNING id
+            "#,
+            Uuid::new_v4(),
+            name,
+            Utc::now(),
+        )
+        .fetch_one(db)
+        .await
+    }
+
+    /// Adds tag `name` to every card in `card_ids`
+    pub async fn add_to_cards(db: &PgPool, name: &str, card_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        let tag_id = Tag::get_or_create(db, name).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO card_tags (card_id, tag_id)
+            SELECT card_id, $2 FROM UNNEST($1::UUID[]) AS card_id
+            ON CONFLICT DO NOTHING
+            "#,
+            card_ids,
+            tag_id,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes tag `name` from every card in `card_ids`
+    pub async fn remove_from_cards(db: &PgPool, name: &str, card_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM card_tags
+            WHERE card_id = ANY($1)
+            AND tag_id IN (SELECT id FROM tags WHERE name = $2)
+            "#,
+            card_ids,
+            name,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Replaces the tags of a single card with `names`
+    pub async fn set_for_card(db: &PgPool, card_id: Uuid, names: &[String]) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM card_tags
+            WHERE card_id = $1
+            "#,
+            card_id,
+        )
+        .execute(db)
+        .await?;
+
+        for name in names {
+            Tag::add_to_cards(db, name, &[card_id]).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_names_normalises_and_dedups() {
+        assert_eq!(
+            vec!["verb".to_string(), "irregular".to_string()],
+            Tag::parse_names(" Verb, irregular  verb,"),
+        );
+    }
+}
@@ -0,0 +1,181 @@
+/// Boolean expression over card tags, e.g. `verb and not irregular`
+///
+/// Grammar (keywords are case-insensitive, `and` binds tighter than `or`):
+///     expr := and ( "or" and )*
+///     and  := not ( "and" not )*
+///     not  := "not" not | atom
+///     atom := "(" expr ")" | TAG
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagExpr {
+    Tag(String), // lowercased
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+impl TagExpr {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenise(input);
+        if tokens.is_empty() {
+            return Err("Empty tag expression".to_string());
+        }
+        let mut pos = 0usize;
+        let expr = parse_or(&tokens, &mut pos)?;
+        if pos < tokens.len() {
+            return Err(format!("Unexpected `{}` in tag expression", tokens[pos]));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluates the expression against a card's tags (case-insensitive, lowercasing both
+    /// sides the way `Tag::parse_names` does)
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            TagExpr::Tag(name) => tags.iter().any(|t| t.to_lowercase() == *name),
+            TagExpr::Not(inner) => !inner.matches(tags),
+            TagExpr::And(lhs, rhs) => lhs.matches(tags) && rhs.matches(tags),
+            TagExpr::Or(lhs, rhs) => lhs.matches(tags) || rhs.matches(tags),
+        }
+    }
+}
+
+impl std::fmt::Display for TagExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagExpr::Tag(name) => write!(f, "{}", name),
+            TagExpr::Not(inner) => write!(f, "not {}", inner),
+            TagExpr::And(lhs, rhs) => write!(f, "({} and {})", lhs, rhs),
+            TagExpr::Or(lhs, rhs) => write!(f, "({} or {})", lhs, rhs),
+        }
+    }
+}
+
+/// Splits on whitespace, keeping parentheses as their own tokens
+fn tokenise(input: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for ch in input.chars() {
+        if ch.is_whitespace() || ch == '(' || ch == ')' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !ch.is_whitespace() {
+                tokens.push(ch.to_string());
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn is_keyword(token: &str, keyword: &str) -> bool {
+    token.eq_ignore_ascii_case(keyword)
+}
+
+fn parse_or(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    let mut lhs = parse_and(tokens, pos)?;
+    while *pos < tokens.len() && is_keyword(&tokens[*pos], "or") {
+        *pos += 1;
+        let rhs = parse_and(tokens, pos)?;
+        lhs = TagExpr::Or(Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+}
+
+fn parse_and(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    let mut lhs = parse_not(tokens, pos)?;
+    while *pos < tokens.len() && is_keyword(&tokens[*pos], "and") {
+        *pos += 1;
+        let rhs = parse_not(tokens, pos)?;
+        lhs = TagExpr::And(Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+}
+
+fn parse_not(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    match tokens.get(*pos) {
+        Some(token) if is_keyword(token, "not") => {
+            *pos += 1;
+            Ok(TagExpr::Not(Box::new(parse_not(tokens, pos)?)))
+        }
+        _ => parse_atom(tokens, pos),
+    }
+}
+
+fn parse_atom(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    let token = tokens
+        .get(*pos)
+        .ok_or("Tag expression ended unexpectedly".to_string())?;
+    *pos += 1;
+    match token.as_str() {
+        "(" => {
+            let expr = parse_or(tokens, pos)?;
+            match tokens.get(*pos) {
+                Some(t) if t == ")" => {
+                    *pos += 1;
+                    Ok(expr)
+                }
+                _ => Err("Missing `)` in tag expression".to_string()),
+            }
+        }
+        ")" => Err("Unexpected `)` in tag expression".to_string()),
+        t if is_keyword(t, "and") || is_keyword(t, "or") => {
+            Err(format!("Expected a tag but found `{}`", t))
+        }
+        t => Ok(TagExpr::Tag(t.to_lowercase())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_and_not_matches() {
+        let expr = TagExpr::parse("verb and not irregular").unwrap();
+        assert!(expr.matches(&tags(&["verb"])));
+        assert!(!expr.matches(&tags(&["verb", "irregular"])));
+        assert!(!expr.matches(&tags(&["noun"])));
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let expr = TagExpr::parse("noun or verb and past").unwrap();
+        assert!(expr.matches(&tags(&["noun"])));
+        assert!(!expr.matches(&tags(&["verb"])));
+        assert!(expr.matches(&tags(&["verb", "past"])));
+    }
+
+    #[test]
+    fn test_parentheses_and_case() {
+        let expr = TagExpr::parse("(Noun OR verb) AND NOT (past)").unwrap();
+        assert!(expr.matches(&tags(&["noun"])));
+        assert!(!expr.matches(&tags(&["VERB", "past"])));
+        // Non-ASCII tags are lowercased like stored tag names
+        assert!(TagExpr::parse("Ökonomie").unwrap().matches(&tags(&["ökonomie"])));
+        assert!(TagExpr::parse("été").unwrap().matches(&tags(&["ÉTÉ"])));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let expr = TagExpr::parse("a or b and not c").unwrap();
+        assert_eq!("(a or (b and not c))", expr.to_string());
+        assert_eq!(expr, TagExpr::parse(&expr.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_expressions_rejected() {
+        assert!(TagExpr::parse("").is_err());
+        assert!(TagExpr::parse("verb and").is_err());
+        assert!(TagExpr::parse("(verb").is_err());
+        assert!(TagExpr::parse("verb noun").is_err());
+    }
+}
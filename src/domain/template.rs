@@ -0,0 +1,165 @@
+//! Card templates: text with `{{Field}}` placeholders, filled in from a note's fields.
+//!
+//! `{{#Field}}...{{/Field}}` is kept only if the field has text and `{{^Field}}...{{/Field}}`
+//! only if it doesn't. A placeholder naming no field is left as it is, so typos stay visible.
+
+use std::collections::HashMap;
+
+/// Name under which a back template can include the rendered front
+pub const FRONT_SIDE: &str = "FrontSide";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Field(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/// Renders `template` with the values in `fields`
+pub fn render(template: &str, fields: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    render_nodes(&parse(template), fields, &mut out);
+    out
+}
+
+/// Whether `template` shows any of `fields`: a card whose front template shows nothing
+/// but its fixed text would be blank, so isn't generated. Whitespace doesn't count as text.
+pub fn shows_fields(template: &str, fields: &HashMap<String, String>) -> bool {
+    let set: HashMap<String, String> = fields
+        .iter()
+        .map(|(name, value)| (name.clone(), value.trim().to_string()))
+        .collect();
+    let blank: HashMap<String, String> = fields.keys().map(|name| (name.clone(), String::new())).collect();
+    let nodes = parse(template);
+    let (mut with, mut without) = (String::new(), String::new());
+    render_nodes(&nodes, &set, &mut with);
+    render_nodes(&nodes, &blank, &mut without);
+    with != without
+}
+
+fn is_set(fields: &HashMap<String, String>, name: &str) -> bool {
+    fields.get(name).is_some_and(|value| !value.trim().is_empty())
+}
+
+fn render_nodes(nodes: &[Node], fields: &HashMap<String, String>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Field(name) => match fields.get(name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    out.push_str("{{");
+                    out.push_str(name);
+                    out.push_str("}}");
+                }
+            },
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                if is_set(fields, name) != *inverted {
+                    render_nodes(children, fields, out);
+                }
+            }
+        }
+    }
+}
+
+/// Parses `template` into nodes; a section that is never closed, or a stray closing tag,
+/// is kept as text
+fn parse(template: &str) -> Vec<Node> {
+    // Each open section: its name, whether it is inverted, the raw opening tag and
+    // the nodes before it
+    let mut stack: Vec<(String, bool, String, Vec<Node>)> = Vec::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        push_text(&mut nodes, &rest[..start]);
+        let raw = &rest[start..start + 2 + len + 2];
+        let tag = rest[start + 2..start + 2 + len].trim();
+        rest = &rest[start + 2 + len + 2..];
+
+        if let Some(name) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+            let inverted = tag.starts_with('^');
+            stack.push((name.trim().to_string(), inverted, raw.to_string(), std::mem::take(&mut nodes)));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            match stack.last() {
+                Some((open, ..)) if open == name.trim() => {
+                    let (name, inverted, _, before) = stack.pop().expect("checked above");
+                    let children = std::mem::replace(&mut nodes, before);
+                    nodes.push(Node::Section {
+                        name,
+                        inverted,
+                        children,
+                    });
+                }
+                _ => push_text(&mut nodes, raw),
+            }
+        } else {
+            nodes.push(Node::Field(tag.to_string()));
+        }
+    }
+    push_text(&mut nodes, rest);
+
+    // Unclosed sections: their opening tag becomes text
+    while let Some((_, _, raw, mut before)) = stack.pop() {
+        push_text(&mut before, &raw);
+        before.append(&mut nodes);
+        nodes = before;
+    }
+    nodes
+}
+
+fn push_text(nodes: &mut Vec<Node>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match nodes.last_mut() {
+        Some(Node::Text(last)) => last.push_str(text),
+        _ => nodes.push(Node::Text(text.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_fields_and_sections() {
+        let vocab = fields(&[("Word", "犬"), ("Reading", "いぬ"), ("Example", "")]);
+        let template = "{{Word}}{{#Reading}} ({{ Reading }}){{/Reading}}{{#Example}}\n{{Example}}{{/Example}}{{^Example}} -{{/Example}}";
+        assert_eq!("犬 (いぬ) -", render(template, &vocab));
+        assert_eq!("{{Missing}}", render("{{Missing}}", &vocab));
+    }
+
+    #[test]
+    fn test_malformed_tags_kept_as_text() {
+        let vocab = fields(&[("Word", "dog")]);
+        assert_eq!("{{#Word}}dog", render("{{#Word}}{{Word}}", &vocab));
+        assert_eq!("dog{{/Word}}", render("{{Word}}{{/Word}}", &vocab));
+        assert_eq!("dog {{Word", render("{{Word}} {{Word", &vocab));
+    }
+
+    #[test]
+    fn test_shows_fields() {
+        let vocab = fields(&[("Word", "dog"), ("Meaning", "")]);
+        assert!(shows_fields("Translate: {{Word}}", &vocab));
+        assert!(!shows_fields("Translate: {{Meaning}}", &vocab));
+        assert!(!shows_fields("{{#Meaning}}{{Meaning}}{{/Meaning}}", &vocab));
+    }
+}
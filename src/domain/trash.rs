@@ -0,0 +1,155 @@
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+use super::{card::Card, deck::{Deck, RenamedDeck}, note::Note};
+
+/// A trashed deck (with the cards trashed alongside it) or a card trashed on its own
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrashItem {
+    Deck {
+        id: Uuid,
+        name: String,
+        deleted: DateTime<Utc>,
+        n_cards: i64,
+    },
+    Card {
+        id: Uuid,
+        front_text: String,
+        deck_name: String,
+        deleted: DateTime<Utc>,
+    },
+}
+
+impl TrashItem {
+    /// Everything in the trash, most recently deleted first
+    pub async fn load_all(db: &PgPool) -> Result<Vec<TrashItem>, sqlx::Error> {
+        let decks = sqlx::query!(
+            r#"
+            SELECT d.id, d.name, d.deleted AS "deleted!",
+                (SELECT COUNT(*) FROM cards c WHERE c.deck_id = d.id AND c.deleted = d.deleted) AS "n_cards!"
+            FROM decks d
+            WHERE d.deleted IS NOT NULL
+            "#,
+        )
+        .fetch_all(db)
+        .await?;
+
+        // Cards trashed together with their deck are listed under the deck
+        let cards = sqlx::query!(
+            r#"
+            SELECT c.id, c.front_text, d.name AS "deck_name?", c.deleted AS "deleted!"
+            FROM cards c
+            LEFT JOIN decks d ON d.id = c.deck_id
+            WHERE c.deleted IS NOT NULL
+            AND d.deleted IS DISTINCT FROM c.deleted
+            "#,
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut items: Vec<TrashItem> = decks
+            .into_iter()
+            .map(|row| TrashItem::Deck {
+                id: row.id,
+                name: row.name,
+                deleted: row.deleted,
+                n_cards: row.n_cards,
+            })
+            .chain(cards.into_iter().map(|row| TrashItem::Card {
+                id: row.id,
+                front_text: row.front_text.unwrap_or_default(),
+                deck_name: row.deck_name.unwrap_or_default(),
+                deleted: row.deleted,
+            }))
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.deleted()));
+        Ok(items)
+    }
+
+    pub fn deleted(&self) -> DateTime<Utc> {
+        match self {
+            TrashItem::Deck { deleted, .. } | TrashItem::Card { deleted, .. } => *deleted,
+        }
+    }
+
+    /// One-line description for the trash list
+    pub fn label(&self) -> String {
+        match self {
+            TrashItem::Deck { name, n_cards, deleted, .. } => format!(
+                "[deck] {} ({} cards) - deleted {}",
+                name,
+                n_cards,
+                deleted.format("%Y-%m-%d %H:%M")
+            ),
+            TrashItem::Card { front_text, deck_name, deleted, .. } => format!(
+                "[card] {} ({}) - deleted {}",
+                front_text.replace('\n', " "),
+                deck_name,
+                deleted.format("%Y-%m-%d %H:%M")
+            ),
+        }
+    }
+
+    /// Takes the item out of the trash, returning its deck if it had to be renamed
+    pub async fn restore(&self, db: &PgPool) -> Result<Vec<RenamedDeck>, sqlx::Error> {
+        match self {
+            TrashItem::Deck { id, .. } => Deck::restore_from_trash(db, *id).await,
+            TrashItem::Card { id, .. } => Card::restore_from_trash(db, &[*id]).await,
+        }
+    }
+
+    /// Deletes the item for good; its reviews stay in the log for activity and statistics
+    pub async fn purge(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        match self {
+            TrashItem::Deck { id, .. } => Deck::purge(db, *id).await,
+            TrashItem::Card { id, .. } => Card::purge_many(db, &[*id]).await,
+        }
+    }
+
+    /// Purges everything that has been in the trash for more than `retention_days`
+    pub async fn purge_expired(db: &PgPool, retention_days: i32) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM cards
+            WHERE deleted < now() - make_interval(days => $1)
+            OR deck_id IN (
+                SELECT id FROM decks
+                WHERE deleted < now() - make_interval(days => $1)
+            )
+            "#,
+            retention_days,
+        )
+        .execute(db)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM decks
+            WHERE deleted < now() - make_interval(days => $1)
+            "#,
+            retention_days,
+        )
+        .execute(db)
+        .await?;
+        Note::delete_orphans(db).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        let deleted = DateTime::parse_from_rfc3339("2024-03-01T10:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let card = TrashItem::Card {
+            id: Uuid::new_v4(),
+            front_text: "hola\nhello".to_string(),
+            deck_name: "spanish".to_string(),
+            deleted,
+        };
+        assert_eq!("[card] hola hello (spanish) - deleted 2024-03-01 10:30", card.label());
+    }
+}
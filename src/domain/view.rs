@@ -0,0 +1,79 @@
+use sqlx::{types::chrono::{DateTime, Utc}, PgPool};
+use uuid::Uuid;
+
+// CREATE TABLE IF NOT EXISTS views (
+//     id UUID NOT NULL,
+//     PRIMARY KEY(id),
+//     card_id UUID,
+//     FOREIGN KEY (card_id) REFERENCES cards(id),
+//     result BOOLEAN,
+//     time TIMESTAMPTZ,
+//     typed_answer TEXT,
+//     similarity REAL,
+//     hint_used BOOLEAN NOT NULL DEFAULT FALSE,
+//     seed BIGINT,
+//     duration_ms INTEGER
+// );
+/// A single review of a card
+#[derive(Debug, Clone)]
+pub struct View {
+    pub id: Uuid,
+    pub card_id: Option<Uuid>,
+    pub result: Option<bool>,
+    pub time: Option<DateTime<Utc>>,
+    pub typed_answer: Option<String>, // only in decks that require typing the answer
+    pub similarity: Option<f32>,
+    pub hint_used: bool,
+    pub seed: Option<i64>, // values of a parameterised card were drawn from this
+    pub duration_ms: Option<i32>, // time taken to answer
+}
+
+impl View {
+    pub fn new(card_id: Uuid, result: bool) -> Self {
+        View {
+            id: Uuid::new_v4(),
+            card_id: Some(card_id),
+            result: Some(result),
+            time: Some(Utc::now()),
+            typed_answer: None,
+            similarity: None,
+            hint_used: false,
+            seed: None,
+            duration_ms: None,
+        }
+    }
+
+    pub async fn delete_from_db(db: &PgPool, view_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM views
+            WHERE id = $1
+            "#,
+            view_id,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn save(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO views (id, card_id, result, time, typed_answer, similarity, hint_used, seed, duration_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            self.id,
+            self.card_id,
+            self.result,
+            self.time,
+            self.typed_answer,
+            self.similarity,
+            self.hint_used,
+            self.seed,
+            self.duration_ms,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 use flashy::{
     configuration::{self, Settings},
+    domain::{
+        csv,
+        simulation::{self, SimParams},
+    },
     startup,
     tui::{
         app::App,
@@ -22,9 +26,45 @@ async fn main() -> io::Result<()> {
     // DATABASE
     let pg_pool: PgPool = startup::acquire_pg_pool(&config.database);
 
+    // CLI: `flashy simulate deck=<name> cards=<n> new=<n> retention=<0-1> days=<n>` prints CSV
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("simulate") {
+        let params = SimParams::parse(args[1..].iter().map(String::as_str))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let loads = simulation::run(&pg_pool, &params, config.app.rollover_hour)
+            .await
+            .map_err(io::Error::other)?;
+        print!("{}", simulation::to_csv(&loads));
+        return Ok(());
+    }
+
+    // CLI: `flashy export deck=<name>` prints the deck as CSV;
+    // `flashy import deck=<name> file=<path>` adds the notes in a CSV file to the deck
+    if let Some(command @ ("export" | "import")) = args.first().map(String::as_str) {
+        let arg = |key: &str| {
+            args[1..]
+                .iter()
+                .find_map(|pair| pair.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Missing `{}=`", key)))
+        };
+        let deck = arg("deck")?;
+        if command == "export" {
+            let rows = csv::export_deck(&pg_pool, deck).await.map_err(io::Error::other)?;
+            print!("{}", csv::to_csv(&rows));
+        } else {
+            let text = std::fs::read_to_string(arg("file")?)?;
+            let rows = csv::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let added = csv::import_deck(&pg_pool, deck, &rows, &config.app.author)
+                .await
+                .map_err(io::Error::other)?;
+            println!("Imported {} note(s) into '{}'", added, deck);
+        }
+        return Ok(());
+    }
+
     // INITIALISE APP & TERMINAL
     let term = init().expect("Failed to intialise terminal");
-    let app = App::new(pg_pool);
+    let app = App::new(pg_pool, config.app);
 
     // RUN
     let _result = app.run(term).await;
@@ -1,20 +1,49 @@
 use super::event_handler::{self, Event};
+use super::markdown::CodeView;
 use super::panes::alertpopup::{AlertPopup, AlertPriority};
 use super::panes::confirm::{ConfirmAction, ConfirmPopup};
+use super::panes::heatmap::Heatmap;
+use super::panes::prompt::{InputPrompt, PromptAction};
+use super::screens::card_browser::{CardBrowser, CardColumn};
 use super::screens::create_card::CreateCard;
 use super::screens::create_deck::CreateDeck;
+use super::screens::leeches::LeechList;
+use super::screens::presets::PresetList;
+use super::screens::review_deck::ReviewDeck;
+use super::screens::revisions::RevisionHistory;
+use super::screens::search_results::SearchResults;
+use super::screens::simulation::SimulationScreen;
+use super::screens::stats::StatsScreen;
+use super::screens::trash::TrashBin;
 use super::{
     utils,
     utils::{Searcher, Tui},
 };
+use crate::configuration::AppSettings;
+use crate::domain::activity::{day_of, Activity};
 use crate::domain::card::Card;
-use crate::domain::deck::Deck;
+use crate::domain::deck::{Deck, RenamedDeck};
 use crate::domain::deckset::DeckSet;
+use crate::domain::exam::{self, ExamPlan};
+use crate::domain::history::{Command, History};
+use crate::domain::leech::{self, Leech};
+use crate::domain::limits::{self, DailyLimits, Waiting};
+use crate::domain::note::{Note, NoteKind};
+use crate::domain::note_type::NoteType;
+use crate::domain::preset::{Preset, ReviewMode};
+use crate::domain::revision::{CardRevision, RevisionSource};
+use crate::domain::search::SearchQuery;
+use crate::domain::simulation::{self, SimParams};
+use crate::domain::tag::Tag;
+use crate::domain::tag_expr::TagExpr;
+use crate::domain::trash::TrashItem;
 use crate::tui::panes::statusbar::StatusBar;
 use color_eyre::eyre;
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyModifiers};
 use crossterm::event::KeyCode::Char;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use uuid::Uuid;
 
 // UI
 use ratatui::{
@@ -30,7 +59,7 @@ use ratatui::{
 };
 
 // BACKEND
-use sqlx::PgPool;
+use sqlx::{types::chrono::{NaiveDate, Utc}, PgPool};
 
 #[derive(Debug, Default)]
 pub enum CurrentScreen {
@@ -39,7 +68,14 @@ pub enum CurrentScreen {
     CreateCard,
     CreateDeck,
     CONFIRM(ConfirmPopup),
+    PROMPT,
     REVIEW,
+    REVISIONS,
+    TRASH,
+    STATS,
+    SIMULATION,
+    PRESETS,
+    LEECHES,
     #[default]
     WELCOME,
 }
@@ -73,6 +109,14 @@ pub struct App<'a> {
     create_deck: Option<CreateDeck>,
     statusbar: Option<StatusBar>,
     alert: Option<AlertPopup<'a>>, // always appears in top-right (floating)
+    prompt: Option<InputPrompt>,
+    review: Option<ReviewDeck>,
+    trash: Option<TrashBin>,
+    stats: Option<StatsScreen>,
+    simulation: Option<SimulationScreen>,
+    presets: Option<PresetList>,
+    leeches: Option<LeechList>, // kept while editing one of them, to come back to
+    revisions: Option<RevisionHistory>,
 
     mode: Mode,
     should_quit: bool,
@@ -83,7 +127,18 @@ pub struct App<'a> {
     deck: Option<Deck>,
     deckset: Option<DeckSet>,
     db_pool: PgPool, // TODO: should be optional?
+    settings: AppSettings,
+    note_types: Vec<NoteType>, // offered by the card editor
+    activity: Activity, // reviews per day, for the heatmap, streaks and daily goal
+    exam_plans: HashMap<Uuid, ExamPlan>, // decks with an exam date, by deck
+    daily_limits: DailyLimits,
+    waiting: HashMap<Uuid, Waiting>, // cards waiting in each deck, before its limits
     current_list: Vec<String>,
+    card_filter: Option<TagExpr>, // tag expression applied to the CARDS list and reviews
+    search_results: Option<SearchResults>, // when set, the CARDS list shows these instead of `deck`
+    selected_cards: HashSet<Uuid>,
+    browser: CardBrowser, // sorting, columns and detail pane of the CARDS table
+    history: History, // undo/redo for this session
     pointer: ListState,
     n_items: usize, // number of items, e.g. list items, currently displayed
 }
@@ -102,6 +157,7 @@ impl<'a> Widget for &mut App<'a> {
 
                 let (main_area, statusbar_area) = (layout[0], layout[1]);
                 statusbar.mode = self.mode.clone();
+                statusbar.progress = Some(self.activity.progress(self.settings.daily_goal));
                 statusbar.render(statusbar_area, buf);
                 main_area
             }
@@ -113,21 +169,10 @@ impl<'a> Widget for &mut App<'a> {
 
         match &self.current_screen {
             CurrentScreen::WELCOME => {
-                let splash: String = r#"
-        _________  __        _________  __________  __     __  __    __
-       / ______ / / /       / _____  / / ________/ / /    / / / /   / /
-     / /_____   / /       / /    / / / /_______  / /____/ / / /___/ /
-   / ______/  / /       / /    / / /______   / / _____  / /_____  /
- / /        / /_____  / /    / / _______/  / / /    / / ______/ /
-/_/        /_______/ /_/    /_/ /_________/ /_/    /_/ /_______/
-                "#
-                .to_string();
-                // BUG: this is for some reason not aligned
-                let lines: Vec<Line> = splash.split('\n').map(Line::from).collect();
-
                 let instructions =
                     Title::from(Line::from(vec!["[ Press any key to get started ]".into()]));
                 let block = Block::default()
+                    .title(Title::from("FLASHY".bold()).alignment(Alignment::Center))
                     .title(
                         instructions
                             .alignment(Alignment::Center)
@@ -135,24 +180,53 @@ impl<'a> Widget for &mut App<'a> {
                     )
                     .borders(Borders::ALL)
                     .border_set(border::THICK)
-                    .padding(Padding::new(0, 0, 4, 0));
+                    .padding(Padding::new(2, 2, 2, 0));
+                let inner = block.inner(main_area);
+                block.render(main_area, buf);
 
-                Paragraph::new(lines)
-                    .style(
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD),
-                    )
-                    .block(block)
-                    .centered()
-                    .render(main_area, buf);
+                let progress = self.activity.progress(self.settings.daily_goal);
+                let goal_style = match progress.is_met() {
+                    true => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                    false => Style::default().fg(Color::Yellow),
+                };
+                let summary = vec![
+                    Line::styled(progress.to_string(), goal_style),
+                    Line::styled(
+                        format!(
+                            "Longest streak: {} days | {} reviews on {} days",
+                            self.activity.longest_streak(),
+                            self.activity.days.values().map(|day| day.reviews).sum::<u64>(),
+                            self.activity.days.len()
+                        ),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ];
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(vec![Constraint::Length(3), Constraint::Length(8), Constraint::Min(0)])
+                    .split(inner);
+                Paragraph::new(summary).centered().render(layout[0], buf);
+                // Centre the heatmap horizontally: labels plus two columns a week
+                let width = layout[1].width.min(4 + 2 * 53);
+                let heatmap_area = Rect {
+                    x: layout[1].x + (layout[1].width - width) / 2,
+                    width,
+                    ..layout[1]
+                };
+                Heatmap { activity: &self.activity }.render(heatmap_area, buf);
             }
             CurrentScreen::CARDS => {
-                let title = Title::from(
-                    format!("[ CARDS IN {} ]", self.deck.as_ref().unwrap().name.clone()).bold(),
-                );
-                let instructions =
-                    Title::from(Line::from(vec!["[ [n] to create new card ]".into()]));
+                let list_name = match &self.search_results {
+                    Some(results) => format!("SEARCH: {}", results.query.text),
+                    None => format!("CARDS IN {}", self.deck.as_ref().unwrap().name.clone()),
+                };
+                let title = match &self.card_filter {
+                    Some(filter) => Title::from(format!("[ {} | TAGS: {} ]", list_name, filter).bold()),
+                    None => Title::from(format!("[ {} ]", list_name).bold()),
+                };
+                let instructions = Title::from(Line::from(vec![
+                    "[ [n] new, [space] select, [d] delete, [m] move, [t] tags, [f] filter, [/] find, [s] search, [r] review, [c] multiple choice, [o/O] sort, [1-9] columns, [v] detail, [h] history, [u/^r] undo/redo ]".into(),
+                ]));
 
                 let block = Block::default()
                     .title(title.alignment(Alignment::Center))
@@ -164,54 +238,98 @@ impl<'a> Widget for &mut App<'a> {
                     .borders(Borders::ALL)
                     .border_set(border::THICK);
 
-                // help
-                let cards: Vec<String> = match &self.deck {
-                    Some(d) => {
-                        // do I have a current deck?
-                        match &d.cards {
-                            // does the deck have cards?
-                            Some(c) => c
-                                .iter()
-                                .map(|card| {
-                                    if let Some(text) = card.front_text.clone() {
-                                        text
-                                    } else {
-                                        tracing::warn!("invalid card, no text");
-                                        "".to_string()
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    }
-                    None => Vec::new(),
-                };
+                let cards: Vec<Card> = self.displayed_cards();
+                let deck_names = self.deck_names();
+                self.current_list = cards
+                    .iter()
+                    .map(|card| card.front_text.clone().unwrap_or_default())
+                    .collect();
 
-                self.current_list = cards.clone();
-                self.n_items = self.current_list.len();
-                let list_text = utils::add_nums_to_text(cards);
-                if list_text.is_empty() {
+                let main_area = match &self.mode {
+                    Mode::SEARCH(searcher) => utils::render_search_bar(searcher, main_area, buf),
+                    _ => main_area,
+                };
+                // (index into `cards`, highlighted chars) for each row
+                let rows: Vec<(usize, Vec<usize>)> = match &self.mode {
+                    Mode::SEARCH(searcher) => searcher
+                        .matches()
+                        .iter()
+                        .map(|m| (m.index, m.positions.clone()))
+                        .collect(),
+                    _ => (0..cards.len()).map(|i| (i, Vec::new())).collect(),
+                };
+                self.n_items = rows.len();
+                if rows.is_empty() {
                     self.alert = Some(AlertPopup::new(
                         std::time::Duration::new(5, 0),
                         "Warn: No cards in deck.".to_string(),
                         AlertPriority::Yellow,
                     ));
                 }
-                let list = utils::styled_list(list_text, block);
 
-                ratatui::widgets::StatefulWidget::render(list, main_area, buf, &mut self.pointer);
+                let (table_area, detail_area) = if self.browser.show_detail {
+                    let layout = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(vec![Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .split(main_area);
+                    (layout[0], Some(layout[1]))
+                } else {
+                    (main_area, None)
+                };
+
+                self.browser.state.select(self.pointer.selected());
+                self.browser.render_table(
+                    &cards,
+                    &rows,
+                    &deck_names,
+                    &self.selected_cards,
+                    block,
+                    table_area,
+                    buf,
+                );
+
+                if let Some(detail_area) = detail_area {
+                    if let Some(card) = self
+                        .pointer
+                        .selected()
+                        .and_then(|i| rows.get(i))
+                        .and_then(|(index, _)| cards.get(*index))
+                    {
+                        let code_view = CodeView {
+                            line_numbers: self.settings.code_line_numbers,
+                            ..CodeView::default()
+                        };
+                        CardBrowser::render_detail(card, &deck_names, code_view, detail_area, buf);
+                    }
+                }
             }
             CurrentScreen::DECKS => {
-                let text = match &self.mode {
-                    Mode::SEARCH(searcher) => {
-                        /*
-                        TODO:‼️
-                        There is no way to select results!
-                         */
-                        searcher.get_text()
-                    }
+                let list_text: Vec<Line> = match &self.mode {
+                    Mode::SEARCH(searcher) => utils::searcher_lines(searcher),
                     _ => match &self.deckset {
-                        Some(d) => d.decks.iter().map(|deck| deck.name.clone()).collect(),
+                        Some(d) => utils::add_nums_to_text(
+                            d.decks
+                                .iter()
+                                .map(|deck| {
+                                    let mut name = deck.name.clone();
+                                    if deck.reverse_by_default {
+                                        name.push_str(" ⇄");
+                                    }
+                                    if deck.type_answer {
+                                        name.push_str(" ✎");
+                                    }
+                                    let available = self.daily_limits.available_within(deck, &d.decks, &self.waiting);
+                                    name.push_str(&format!("  [{} new, {} due]", available.new, available.reviews));
+                                    if let Some(plan) = self.exam_plans.get(&deck.id) {
+                                        name.push_str(&plan.summary(self.settings.exam_load_warning));
+                                    }
+                                    name
+                                })
+                                .collect(),
+                        )
+                        .into_iter()
+                        .map(Line::from)
+                        .collect(),
                         None => {
                             self.alert = Some(AlertPopup::new(
                                 std::time::Duration::new(5, 0),
@@ -225,24 +343,12 @@ impl<'a> Widget for &mut App<'a> {
 
                 let main_area = match &self.mode {
                     Mode::NORMAL | Mode::INSERT => main_area,
-                    Mode::SEARCH(searcher) => {
-                        let layout = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints(vec![Constraint::Percentage(100), Constraint::Min(3)])
-                            .split(main_area);
-                        let (main_area, search_bar_area) = (layout[0], layout[1]);
-
-                        // Render search bar
-                        Paragraph::new(searcher.get_search_string())
-                            .block(Block::default().borders(Borders::ALL))
-                            .render(search_bar_area, buf);
-                        main_area
-                    }
+                    Mode::SEARCH(searcher) => utils::render_search_bar(searcher, main_area, buf),
                 };
 
                 let title = Title::from("DECKS".to_string());
                 let instructions = Title::from(Line::from(vec![
-                    "[ [n] to create deck, [/] to search ]".into(),
+                    "[ [n] to create deck, [/] to filter, [s] to search all cards, [r] reverse cards, [a] type answers, [t] trash, [g] stats, [w] simulate workload, [x] exam date, [o] daily limits, [p] presets, [l] leeches, [u/^r] undo/redo ]".into(),
                 ]));
 
                 let block = Block::default()
@@ -255,7 +361,6 @@ impl<'a> Widget for &mut App<'a> {
                     .borders(Borders::ALL)
                     .border_set(border::THICK);
 
-                let list_text = utils::add_nums_to_text(text.to_vec());
                 self.n_items = list_text.len();
 
                 let list = utils::styled_list(list_text, block);
@@ -302,10 +407,53 @@ impl<'a> Widget for &mut App<'a> {
                     self.create_screen = Some(CreateCard::default());
                 }
             }
-            CurrentScreen::REVIEW => todo!(),
+            CurrentScreen::REVIEW => {
+                if let Some(review) = &self.review {
+                    review.render(main_area, buf);
+                }
+            }
+            CurrentScreen::REVISIONS => {
+                if let Some(revisions) = &self.revisions {
+                    self.n_items = revisions.revisions.len();
+                    revisions.render(&mut self.pointer, main_area, buf);
+                }
+            }
+            CurrentScreen::TRASH => {
+                if let Some(trash) = &self.trash {
+                    self.n_items = trash.items.len();
+                    trash.render(self.settings.trash_retention_days, &mut self.pointer, main_area, buf);
+                }
+            }
+            CurrentScreen::STATS => {
+                if let Some(stats) = &self.stats {
+                    stats.render(main_area, buf);
+                }
+            }
+            CurrentScreen::SIMULATION => {
+                if let Some(simulation) = &self.simulation {
+                    simulation.render(main_area, buf);
+                }
+            }
+            CurrentScreen::PRESETS => {
+                if let Some(presets) = &self.presets {
+                    self.n_items = presets.presets.len();
+                    presets.render(&mut self.pointer, main_area, buf);
+                }
+            }
+            CurrentScreen::LEECHES => {
+                if let Some(leeches) = &self.leeches {
+                    self.n_items = leeches.leeches.len();
+                    leeches.render(&mut self.pointer, main_area, buf);
+                }
+            }
             CurrentScreen::CONFIRM(popup) => {
                 popup.render(main_area, buf);
             }
+            CurrentScreen::PROMPT => {
+                if let Some(prompt) = &self.prompt {
+                    prompt.render(main_area, buf);
+                }
+            }
         }
 
         // Renders top-right 'alert' popup, and sets to None when times out
@@ -319,19 +467,38 @@ impl<'a> Widget for &mut App<'a> {
 }
 
 impl<'a> App<'a> {
-    pub fn new(db_pool: PgPool) -> Self {
+    pub fn new(db_pool: PgPool, settings: AppSettings) -> Self {
         Self {
             current_screen: CurrentScreen::default(),
             create_screen: None,
             create_deck: None,
             statusbar: None,
             alert: None,
+            prompt: None,
+            review: None,
+            trash: None,
+            stats: None,
+            simulation: None,
+            presets: None,
+            leeches: None,
+            revisions: None,
             mode: Mode::default(),
             should_quit: false,
             deck: None,
             deckset: None,
             current_list: Vec::new(),
+            card_filter: None,
+            search_results: None,
+            selected_cards: HashSet::new(),
+            browser: CardBrowser::default(),
+            history: History::default(),
             db_pool,
+            settings,
+            note_types: Vec::new(),
+            activity: Activity::default(),
+            exam_plans: HashMap::new(),
+            daily_limits: DailyLimits::default(),
+            waiting: HashMap::new(),
             pointer: ListState::default(),
             n_items: 0usize,
             cursor: None,
@@ -344,6 +511,7 @@ impl<'a> App<'a> {
             Ok(deckset) => self.deckset = Some(deckset),
             Err(e) => return Err(e),
         }
+        self.refresh_deck_counts().await;
         self.alert = Some(AlertPopup::new(
             std::time::Duration::new(5, 0),
             "Decks loaded successfully".to_string(),
@@ -352,8 +520,1214 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Cards in the current deck (or search results) that pass the active tag filter, in display order
+    fn displayed_cards(&self) -> Vec<Card> {
+        let mut cards: Vec<Card> = match (&self.search_results, &self.deck) {
+            (Some(results), _) => results
+                .cards
+                .iter()
+                .filter(|card| self.card_filter.as_ref().is_none_or(|expr| expr.matches(&card.tags)))
+                .cloned()
+                .collect(),
+            (None, Some(deck)) => deck.filtered_cards(self.card_filter.as_ref()),
+            (None, None) => Vec::new(),
+        };
+        self.browser.sort(&mut cards, &self.deck_names());
+        cards
+    }
+
+    /// Ids of the selected cards, or of the card under the cursor if none are selected
+    fn target_card_ids(&self) -> Vec<Uuid> {
+        if self.selected_cards.is_empty() {
+            self.displayed_cards()
+                .get(self.pointer.selected().unwrap_or(0usize))
+                .map(|card| vec![card.id])
+                .unwrap_or_default()
+        } else {
+            self.selected_cards.iter().cloned().collect()
+        }
+    }
+
+    /// Deck names by id, for showing which deck a card belongs to
+    fn deck_names(&self) -> HashMap<Uuid, String> {
+        match &self.deckset {
+            Some(deckset) => deckset
+                .decks
+                .iter()
+                .map(|deck| (deck.id, deck.name.clone()))
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    fn select_next(&mut self) {
+        if self.n_items != 0 {
+            let selected = match self.pointer.selected() {
+                Some(val) => (val + 1).min(self.n_items - 1),
+                None => 0usize,
+            };
+            self.pointer.select(Some(selected));
+        }
+    }
+
+    fn select_previous(&mut self) {
+        if let Some(val) = self.pointer.selected() {
+            self.pointer.select(Some(val.saturating_sub(1)));
+        }
+    }
+
+    /// Moves the list pointer to the first search result, if any
+    fn reset_pointer_to_results(&mut self) {
+        if let Mode::SEARCH(searcher) = &self.mode {
+            let first = if searcher.matches().is_empty() { None } else { Some(0usize) };
+            self.pointer.select(first);
+        }
+    }
+
+    /// Loads the cards of the deck at `index` in the `DeckSet` and shows them
+    async fn open_deck(&mut self, index: usize) {
+        // Check we have a deckset
+        if let Some(deckset) = &self.deckset {
+            // Check we have a valid "pointer" to selected deck
+            if let Some(curr_deck) = deckset.decks.get(index) {
+                let mut deck = curr_deck.clone();
+                match deck.load_cards(&self.db_pool).await {
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!("failed to load cards {}", e);
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            "Error: Failed to load cards in deck.".to_string(),
+                            AlertPriority::Red,
+                        ));
+                    }
+                };
+                self.deck = Some(deck);
+                // Set ListState to default
+                self.pointer = ListState::default();
+                self.current_screen = CurrentScreen::CARDS;
+            };
+        }
+    }
+
+    /// Reloads the current deck's cards and any open search results
+    async fn reload_cards(&mut self) {
+        if let Some(deck) = &mut self.deck {
+            match deck.load_cards(&self.db_pool).await {
+                Ok(_) => tracing::info!("Deck reloaded"),
+                Err(e) => tracing::error!("Failed to reload deck! {}", e),
+            }
+        }
+        if let Some(results) = &mut self.search_results {
+            if let Err(e) = results.reload(&self.db_pool).await {
+                tracing::error!("Failed to reload search results! {}", e);
+            }
+        }
+    }
+
+    /// Undoes (or redoes) the most recent command and refreshes whatever it touched
+    async fn undo_or_redo(&mut self, redo: bool) {
+        let verb = if redo { "Redid" } else { "Undid" };
+        let result = if redo {
+            self.history.redo(&self.db_pool, &self.settings.author).await.map(|command| command.map(|command| (command, Vec::new())))
+        } else {
+            self.history.undo(&self.db_pool, &self.settings.author).await
+        };
+        let (command, renamed) = match result {
+            Ok(Some((command, renamed))) => (command.clone(), renamed),
+            Ok(None) => {
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    format!("Nothing to {}", if redo { "redo" } else { "undo" }),
+                    AlertPriority::Yellow,
+                ));
+                return;
+            }
+            Err(e) => {
+                tracing::error!("failed to {} command {}", verb, e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    format!("Error: Failed to {}!", if redo { "redo" } else { "undo" }),
+                    AlertPriority::Red,
+                ));
+                return;
+            }
+        };
+
+        if let (Some(review), Command::GradeReview { view, before, after }) = (&mut self.review, &command) {
+            let correct = view.result == Some(true);
+            if redo {
+                review.redo_answer(after, correct);
+            } else {
+                review.undo_answer(before, correct);
+            }
+        }
+        if let Command::GradeReview { .. } = &command {
+            self.refresh_activity().await;
+            self.refresh_deck_counts().await;
+        }
+        if let Command::DeleteDeck(deck) = &command {
+            if let Err(e) = self.fetch_decks().await {
+                tracing::error!("failed to reload decks {}", e);
+            }
+            // Don't leave a deleted deck open
+            if redo && self.deck.as_ref().is_some_and(|open| open.id == deck.id) {
+                self.deck = None;
+                self.current_screen = CurrentScreen::DECKS;
+            }
+        }
+        // Restoring a card also restores its deck if that was trashed
+        if let Command::DeleteCards(_) = &command {
+            if let Err(e) = self.fetch_decks().await {
+                tracing::error!("failed to reload decks {}", e);
+            }
+        }
+        self.selected_cards.clear();
+        self.reload_cards().await;
+        // Lists on screen may show what the command touched
+        match self.current_screen {
+            CurrentScreen::TRASH => self.reload_trash().await,
+            CurrentScreen::LEECHES => self.reload_leeches().await,
+            CurrentScreen::REVISIONS => self.reload_revisions().await,
+            CurrentScreen::STATS => {
+                if let Some(stats) = &mut self.stats {
+                    if let Err(e) = stats.reload(&self.db_pool).await {
+                        tracing::error!("failed to reload stats {}", e);
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.alert = Some(match RenamedDeck::describe(&renamed) {
+            Some(text) => AlertPopup::new(
+                std::time::Duration::new(5, 0),
+                format!("{}: {}. {}", verb, command.description(), text),
+                AlertPriority::Yellow,
+            ),
+            None => AlertPopup::new(
+                std::time::Duration::new(5, 0),
+                format!("{}: {}", verb, command.description()),
+                AlertPriority::Green,
+            ),
+        });
+    }
+
+    /// Reloads the reviews per day, e.g. after a grade
+    async fn refresh_activity(&mut self) {
+        match Activity::load(&self.db_pool, self.settings.rollover_hour).await {
+            Ok(activity) => self.activity = activity,
+            Err(e) => tracing::error!("failed to load review activity {}", e),
+        }
+    }
+
+    /// Reloads what the decks have left to study today: their daily limits, waiting cards
+    /// and exam plans
+    async fn refresh_deck_counts(&mut self) {
+        let decks = self.deckset.as_ref().map(|deckset| deckset.decks.as_slice()).unwrap_or_default();
+        match DailyLimits::load(&self.db_pool, decks, self.settings.rollover_hour).await {
+            Ok(limits) => self.daily_limits = limits,
+            Err(e) => tracing::error!("failed to load daily limits {}", e),
+        }
+        match Waiting::load_all(&self.db_pool).await {
+            Ok(waiting) => self.waiting = waiting,
+            Err(e) => tracing::error!("failed to load waiting cards {}", e),
+        }
+        match ExamPlan::load_all(&self.db_pool, self.settings.rollover_hour).await {
+            Ok(plans) => self.exam_plans = plans,
+            Err(e) => tracing::error!("failed to load exam plans {}", e),
+        }
+    }
+
+    /// Opens the prompt for the exam date of the deck under the cursor
+    fn open_exam_date_prompt(&mut self) {
+        let Some(deck) = self
+            .deckset
+            .as_ref()
+            .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+        else {
+            return;
+        };
+        let current = deck.exam_date.map(|date| date.to_string()).unwrap_or_default();
+        self.prompt = Some(InputPrompt::new(
+            &format!("Exam date for '{}'", deck.name),
+            "YYYY-MM-DD; leave empty to turn exam mode off",
+            &current,
+            PromptAction::ExamDate {
+                deck_id: deck.id,
+                name: deck.name.clone(),
+            },
+        ));
+        self.current_screen = CurrentScreen::PROMPT;
+    }
+
+    /// Opens the prompt for the daily limits of the deck under the cursor
+    fn open_limits_prompt(&mut self) {
+        let Some(deck) = self
+            .deckset
+            .as_ref()
+            .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+        else {
+            return;
+        };
+        if let Some(preset) = &deck.preset {
+            self.alert = Some(AlertPopup::new(
+                std::time::Duration::new(5, 0),
+                format!("'{}' uses preset '{}'; edit its limits under [p] presets", deck.name, preset.name),
+                AlertPriority::Yellow,
+            ));
+            return;
+        }
+        self.prompt = Some(InputPrompt::new(
+            &format!("Daily limits for '{}'", deck.name),
+            "new=<n|none> reviews=<n|none> order=sequential|random|tag mix=mixed|new-first|reviews-first; \
+             decks named `Parent::Child` are also held to Parent's limits",
+            &deck.limits.to_string(),
+            PromptAction::DeckLimits {
+                deck_id: deck.id,
+                name: deck.name.clone(),
+            },
+        ));
+        self.current_screen = CurrentScreen::PROMPT;
+    }
+
+    /// Loads the presets and shows them; [l] links the deck under the cursor
+    async fn open_presets(&mut self) {
+        let deck_id = self
+            .deckset
+            .as_ref()
+            .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+            .map(|deck| deck.id);
+        match self.load_presets(deck_id).await {
+            Ok(presets) => {
+                self.presets = Some(presets);
+                self.pointer = ListState::default();
+                self.current_screen = CurrentScreen::PRESETS;
+            }
+            Err(e) => {
+                tracing::error!("failed to load presets {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to load presets!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+    }
+
+    /// The presets, with how many decks use each, for linking deck `deck_id`
+    async fn load_presets(&self, deck_id: Option<Uuid>) -> Result<PresetList, sqlx::Error> {
+        let decks = self.deckset.as_ref().map(|deckset| deckset.decks.as_slice()).unwrap_or_default();
+        let deck = deck_id
+            .and_then(|id| decks.iter().find(|deck| deck.id == id))
+            .map(|deck| (deck.id, deck.name.clone(), deck.preset.as_ref().map(|preset| preset.id)));
+        let mut n_decks: HashMap<Uuid, usize> = HashMap::new();
+        for preset in decks.iter().filter_map(|deck| deck.preset.as_ref()) {
+            *n_decks.entry(preset.id).or_default() += 1;
+        }
+        PresetList::load(&self.db_pool, deck, n_decks).await
+    }
+
+    /// Reloads the decks, which take their presets' options, and the preset list
+    async fn reload_presets(&mut self) {
+        if let Err(e) = self.fetch_decks().await {
+            tracing::error!("failed to reload decks {}", e);
+        }
+        let deck_id = self.presets.as_ref().and_then(|presets| presets.deck.as_ref().map(|(id, ..)| *id));
+        match self.load_presets(deck_id).await {
+            Ok(presets) => self.presets = Some(presets),
+            Err(e) => tracing::error!("failed to reload presets {}", e),
+        }
+    }
+
+    /// The preset under the cursor
+    fn selected_preset(&self) -> Option<Preset> {
+        self.presets
+            .as_ref()
+            .and_then(|presets| presets.presets.get(self.pointer.selected().unwrap_or(0usize)))
+            .cloned()
+    }
+
+    /// Links the deck the presets were opened from to `preset_id`, or unlinks it
+    async fn link_preset(&mut self, preset_id: Option<Uuid>) {
+        let Some((deck_id, name, _)) = self.presets.as_ref().and_then(|presets| presets.deck.clone()) else {
+            return;
+        };
+        if let Err(e) = Deck::set_preset(&self.db_pool, deck_id, preset_id).await {
+            tracing::error!("failed to update deck {}", e);
+            self.alert = Some(AlertPopup::new(
+                std::time::Duration::new(5, 0),
+                "Error: Failed to update deck!".to_string(),
+                AlertPriority::Red,
+            ));
+            return;
+        }
+        self.reload_presets().await;
+        let text = match preset_id.and_then(|id| self.presets.as_ref()?.presets.iter().find(|preset| preset.id == id)) {
+            Some(preset) => format!("'{}' now uses preset '{}'", name, preset.name),
+            None => format!("'{}' uses its own options again", name),
+        };
+        self.alert = Some(AlertPopup::new(std::time::Duration::new(5, 0), text, AlertPriority::Green));
+    }
+
+    /// Cards of the decks below the open deck (`Parent::Child`) that pass the tag filter
+    async fn subdeck_cards(&self) -> Result<Vec<Card>, sqlx::Error> {
+        let (Some(open), Some(deckset)) = (&self.deck, &self.deckset) else {
+            return Ok(Vec::new());
+        };
+        let mut cards = Vec::new();
+        for deck in &deckset.decks {
+            if deck.id == open.id || !limits::is_within(&deck.name, &open.name) {
+                continue;
+            }
+            let mut deck = deck.clone();
+            deck.load_cards(&self.db_pool).await?;
+            cards.extend(deck.filtered_cards(self.card_filter.as_ref()));
+        }
+        Ok(cards)
+    }
+
+    /// Starts reviewing the due cards on display, along with those of the open deck's
+    /// children; a multiple-choice review draws its distractors from all the cards of their decks
+    async fn start_review(&mut self, multiple_choice: bool) {
+        let now = Utc::now();
+        let mut cards = self.displayed_cards();
+        if self.search_results.is_none() {
+            match self.subdeck_cards().await {
+                Ok(subdeck_cards) => cards.extend(subdeck_cards),
+                Err(e) => {
+                    tracing::error!("failed to load cards of subdecks {}", e);
+                    self.alert = Some(AlertPopup::new(
+                        std::time::Duration::new(5, 0),
+                        "Error: Failed to load cards!".to_string(),
+                        AlertPriority::Red,
+                    ));
+                    return;
+                }
+            }
+        }
+        let cards: Vec<Card> = cards
+            .into_iter()
+            .filter(|card| !card.suspended && card.schedule().is_due(now))
+            .collect();
+        // Buried siblings mustn't count against the limits below
+        let cards = Note::bury_siblings(cards);
+        // Exam decks introduce just enough new cards a day to see them all before the exam,
+        // and every deck stays within its daily limits
+        self.refresh_deck_counts().await;
+        let cards = exam::spread_new_cards(cards, &self.exam_plans);
+        let decks: Vec<Deck> = self
+            .deckset
+            .as_ref()
+            .map(|deckset| deckset.decks.clone())
+            .unwrap_or_default();
+        let interleave = self
+            .deck
+            .as_ref()
+            .and_then(|open| decks.iter().find(|deck| deck.id == open.id))
+            .map(|deck| deck.limits.interleave)
+            .unwrap_or_default();
+        let cards = self
+            .daily_limits
+            .clone()
+            .build_queue(cards, &decks, interleave, &mut rand::thread_rng());
+        // Decks answered by picking from choices start that way
+        let multiple_choice = multiple_choice
+            || self
+                .deck
+                .as_ref()
+                .and_then(|open| decks.iter().find(|deck| deck.id == open.id))
+                .is_some_and(|deck| deck.options().review_mode == ReviewMode::Choice);
+        if cards.is_empty() {
+            self.alert = Some(AlertPopup::new(
+                std::time::Duration::new(5, 0),
+                "Warning: No cards due for review, or today's limits are reached".to_string(),
+                AlertPriority::Yellow,
+            ));
+            return;
+        }
+
+        let review = if multiple_choice {
+            let mut pool: Vec<Card> = Vec::new();
+            for mut deck in decks {
+                if !cards.iter().any(|card| card.deck_id == Some(deck.id)) {
+                    continue;
+                }
+                if let Err(e) = deck.load_cards(&self.db_pool).await {
+                    tracing::error!("failed to load cards for deck {}", e);
+                    self.alert = Some(AlertPopup::new(
+                        std::time::Duration::new(5, 0),
+                        "Error: Failed to load cards!".to_string(),
+                        AlertPriority::Red,
+                    ));
+                    return;
+                }
+                pool.extend(deck.cards.unwrap_or_default());
+            }
+            ReviewDeck::new(cards, HashSet::new(), self.settings.answer).with_choices(&pool)
+        } else {
+            let type_answer_decks = decks.iter().filter(|deck| deck.type_answer).map(|deck| deck.id).collect();
+            ReviewDeck::new(cards, type_answer_decks, self.settings.answer)
+        };
+        let exam_dates = self
+            .exam_plans
+            .iter()
+            .map(|(deck_id, plan)| (*deck_id, plan.exam_date))
+            .collect();
+        let deck_options = self
+            .deckset
+            .as_ref()
+            .map(|deckset| deckset.decks.iter().map(|deck| (deck.id, deck.options())).collect())
+            .unwrap_or_default();
+        let mut review = review
+            .with_exam_dates(exam_dates, self.settings.rollover_hour)
+            .with_deck_options(deck_options);
+        review.code_view.line_numbers = self.settings.code_line_numbers;
+        self.review = Some(review);
+        self.current_screen = CurrentScreen::REVIEW;
+    }
+
+    /// Loads the trash and shows it
+    async fn open_trash(&mut self) {
+        match TrashBin::load(&self.db_pool).await {
+            Ok(trash) => {
+                self.trash = Some(trash);
+                self.pointer = ListState::default();
+                self.current_screen = CurrentScreen::TRASH;
+            }
+            Err(e) => {
+                tracing::error!("failed to load trash {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to load trash!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+    }
+
+    /// Loads the statistics of all decks and shows them; [d] switches to the deck under the cursor
+    async fn open_stats(&mut self) {
+        let deck = self
+            .deckset
+            .as_ref()
+            .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+            .map(|deck| (deck.id, deck.name.clone()));
+        match StatsScreen::load(&self.db_pool, deck, self.settings.rollover_hour).await {
+            Ok(stats) => {
+                self.stats = Some(stats);
+                self.current_screen = CurrentScreen::STATS;
+            }
+            Err(e) => {
+                tracing::error!("failed to load stats {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to load stats!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+    }
+
+    /// Opens the workload simulation prompt for the deck under the cursor
+    fn open_simulation_prompt(&mut self) {
+        let deck = self
+            .deckset
+            .as_ref()
+            .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+            .map(|deck| deck.name.clone());
+        let title = match &deck {
+            Some(deck) => format!("Simulate workload of '{}'", deck),
+            None => "Simulate workload".to_string(),
+        };
+        self.prompt = Some(InputPrompt::new(
+            &title,
+            "options: cards=<extra new cards> new=<per day> retention=<0-1> days= runs= ease= seed=",
+            "new=20 retention=0.9 days=365",
+            PromptAction::Simulate { deck },
+        ));
+        self.current_screen = CurrentScreen::PROMPT;
+    }
+
+    /// Loads the leeches and shows them
+    async fn open_leeches(&mut self) {
+        match LeechList::load(&self.db_pool).await {
+            Ok(leeches) if leeches.leeches.is_empty() => {
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "No leeches".to_string(),
+                    AlertPriority::Yellow,
+                ));
+            }
+            Ok(leeches) => {
+                self.leeches = Some(leeches);
+                self.pointer = ListState::default();
+                self.pointer.select(Some(0usize));
+                self.current_screen = CurrentScreen::LEECHES;
+            }
+            Err(e) => {
+                tracing::error!("failed to load leeches {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to load leeches!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+    }
+
+    /// Reloads the open leech list, and the deck counts suspending cards changes
+    async fn reload_leeches(&mut self) {
+        if self.leeches.is_none() {
+            return;
+        }
+        match LeechList::load(&self.db_pool).await {
+            Ok(leeches) => {
+                let last = leeches.leeches.len().saturating_sub(1);
+                self.pointer.select(Some(self.pointer.selected().unwrap_or(0usize).min(last)));
+                self.leeches = Some(leeches);
+            }
+            Err(e) => tracing::error!("failed to reload leeches {}", e),
+        }
+        self.refresh_deck_counts().await;
+    }
+
+    /// The card of the leech under the cursor
+    fn selected_leech(&self) -> Option<Card> {
+        self.leeches
+            .as_ref()
+            .and_then(|leeches| leeches.leeches.get(self.pointer.selected().unwrap_or(0usize)))
+            .map(|leech| leech.card.clone())
+    }
+
+    /// Suspends the selected leech, or brings it back into reviews
+    async fn toggle_leech_suspended(&mut self) {
+        let Some(card) = self.selected_leech() else {
+            return;
+        };
+        match Card::set_suspended(&self.db_pool, &[card.id], !card.suspended).await {
+            Ok(_) => {
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    if card.suspended { "Unsuspended card" } else { "Suspended card" }.to_string(),
+                    AlertPriority::Green,
+                ));
+            }
+            Err(e) => {
+                tracing::error!("failed to suspend card {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to suspend card!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+        self.reload_leeches().await;
+    }
+
+    /// Untags and unsuspends the selected leech, e.g. once it has been rewritten
+    async fn clear_leech(&mut self) {
+        let Some(card) = self.selected_leech() else {
+            return;
+        };
+        match Leech::clear(&self.db_pool, card.id).await {
+            Ok(_) => {
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Card is no longer a leech".to_string(),
+                    AlertPriority::Green,
+                ));
+            }
+            Err(e) => {
+                tracing::error!("failed to clear leech {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to update card!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+        self.reload_leeches().await;
+    }
+
+    /// The trash item under the cursor
+    fn selected_trash_item(&self) -> Option<TrashItem> {
+        self.trash
+            .as_ref()
+            .and_then(|trash| trash.items.get(self.pointer.selected().unwrap_or(0usize)))
+            .cloned()
+    }
+
+    /// Reloads the trash and decks after an item was restored or purged
+    async fn reload_trash(&mut self) {
+        if let Some(trash) = &mut self.trash {
+            if let Err(e) = trash.reload(&self.db_pool).await {
+                tracing::error!("failed to reload trash {}", e);
+            }
+        }
+        if let Err(e) = DeckSet::load_from_db(&self.db_pool)
+            .await
+            .map(|deckset| self.deckset = Some(deckset))
+        {
+            tracing::error!("failed to reload decks {}", e);
+        }
+        self.pointer = ListState::default();
+    }
+
+    /// Opens the global search prompt
+    fn open_search(&mut self, from_deck: bool) {
+        let current = self
+            .search_results
+            .as_ref()
+            .map(|results| results.query.text.clone())
+            .unwrap_or_default();
+        self.prompt = Some(InputPrompt::new(
+            "Search all cards",
+            "e.g. `deck:spanish tag:verb is:due lapses:>3 created:<7d front:\"to be\"`",
+            &current,
+            PromptAction::Search { from_deck },
+        ));
+        self.current_screen = CurrentScreen::PROMPT;
+    }
+
+    /// Opens the card editor; the reverse option follows the card's note, or the deck for new cards
+    async fn open_editor(&mut self, card: &Card) {
+        let note = match card.note_id {
+            Some(note_id) => Note::load(&self.db_pool, note_id).await.unwrap_or_else(|e| {
+                tracing::error!("failed to load note {}", e);
+                None
+            }),
+            None => None,
+        };
+        // Cloze cards are edited through their note's source text
+        let mut shown = card.clone();
+        if let Some(note) = note.as_ref().filter(|note| note.kind == NoteKind::Cloze) {
+            shown.front_text = note.front_text.clone();
+            shown.back_text = note.back_text.clone();
+        }
+        let mut create_screen = CreateCard::from(&shown);
+        create_screen.code_view.line_numbers = self.settings.code_line_numbers;
+        create_screen.note_types = self.note_types.clone();
+        if let Some(note) = note.as_ref().filter(|note| note.kind == NoteKind::Typed) {
+            create_screen.set_note_type(note.note_type.clone(), &note.fields);
+        }
+        create_screen.reverse = match note {
+            Some(note) => note.reverse,
+            None => self
+                .deckset
+                .as_ref()
+                .and_then(|deckset| card.deck_id.and_then(|id| deckset.get_deck_by_id(id)))
+                .is_some_and(|deck| deck.reverse_by_default),
+        };
+        self.create_screen = Some(create_screen);
+        self.current_screen = CurrentScreen::CreateCard;
+    }
+
+    /// Records the creation or edit of a card, snapshotting it and its note as saved;
+    /// `before_note` is the card's note as it was before the edit
+    async fn record_card_saved(&mut self, before: Card, before_note: Option<Note>, card_id: Uuid) {
+        match Card::load_many(&self.db_pool, &[card_id]).await {
+            Ok(mut cards) => {
+                if let Some(after) = cards.pop() {
+                    let command = match before.created {
+                        Some(_) => {
+                            self.record_revision(&before, &after).await;
+                            let after_note = match self.snapshot_note(&after).await {
+                                Ok((note, _)) => note,
+                                Err(e) => {
+                                    tracing::error!("failed to snapshot edited note {}", e);
+                                    return;
+                                }
+                            };
+                            Command::EditCard { before, after, before_note, after_note }
+                        }
+                        None => match self.snapshot_note(&after).await {
+                            Ok((note, siblings)) => Command::CreateCard { card: after, note, siblings },
+                            Err(e) => {
+                                tracing::error!("failed to snapshot new note {}", e);
+                                return;
+                            }
+                        },
+                    };
+                    self.history.push(command);
+                }
+            }
+            Err(e) => tracing::error!("failed to snapshot saved card {}", e),
+        }
+    }
+
+    /// The note of `card`, if any, and the other cards generated from it
+    async fn snapshot_note(&self, card: &Card) -> Result<(Option<Note>, Vec<Card>), sqlx::Error> {
+        let Some(note_id) = card.note_id else {
+            return Ok((None, Vec::new()));
+        };
+        let note = Note::load(&self.db_pool, note_id).await?;
+        let sibling_ids: Vec<Uuid> = Note::card_ids(&self.db_pool, note_id)
+            .await?
+            .into_iter()
+            .filter(|id| *id != card.id)
+            .collect();
+        let siblings = Card::load_many(&self.db_pool, &sibling_ids).await?;
+        Ok((note, siblings))
+    }
+
+    /// Keeps the text `previous` had before it was changed to `edited` in the TUI
+    async fn record_revision(&self, previous: &Card, edited: &Card) {
+        if let Err(e) = CardRevision::record(
+            &self.db_pool,
+            previous,
+            edited,
+            RevisionSource::Tui,
+            &self.settings.author,
+        )
+        .await
+        {
+            tracing::error!("failed to record card revision {}", e);
+        }
+    }
+
+    /// Shows the revision history of `card`
+    async fn open_revisions(&mut self, card: Card) {
+        match RevisionHistory::load(&self.db_pool, card).await {
+            Ok(revisions) if revisions.revisions.is_empty() => {
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Card has not been edited".to_string(),
+                    AlertPriority::Yellow,
+                ));
+            }
+            Ok(revisions) => {
+                self.revisions = Some(revisions);
+                self.pointer = ListState::default();
+                self.pointer.select(Some(0usize));
+                self.current_screen = CurrentScreen::REVISIONS;
+            }
+            Err(e) => {
+                tracing::error!("failed to load revisions {}", e);
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    "Error: Failed to load card history!".to_string(),
+                    AlertPriority::Red,
+                ));
+            }
+        }
+    }
+
+    /// Reloads the open revision history, going back to the card list if its card is gone
+    async fn reload_revisions(&mut self) {
+        let Some(card_id) = self.revisions.as_ref().map(|revisions| revisions.card.id) else {
+            return;
+        };
+        let card = match Card::load_many(&self.db_pool, &[card_id]).await {
+            Ok(mut cards) => cards.pop(),
+            Err(e) => {
+                tracing::error!("failed to reload card {}", e);
+                return;
+            }
+        };
+        match card {
+            Some(card) => match RevisionHistory::load(&self.db_pool, card).await {
+                Ok(revisions) => {
+                    let last = revisions.revisions.len().saturating_sub(1);
+                    self.pointer.select(Some(self.pointer.selected().unwrap_or(0usize).min(last)));
+                    self.revisions = Some(revisions);
+                }
+                Err(e) => tracing::error!("failed to reload revisions {}", e),
+            },
+            None => {
+                self.revisions = None;
+                self.pointer = ListState::default();
+                self.current_screen = CurrentScreen::CARDS;
+            }
+        }
+    }
+
+    /// Sets the open card's text back to the selected revision
+    async fn revert_to_revision(&mut self) {
+        let (current, reverted) = match &self.revisions {
+            Some(revisions) => match revisions.reverted_card(self.pointer.selected().unwrap_or(0usize)) {
+                Some(reverted) => (revisions.card.clone(), reverted),
+                None => return,
+            },
+            None => return,
+        };
+        let current_note = match current.note_id {
+            Some(note_id) => Note::load(&self.db_pool, note_id).await.unwrap_or_else(|e| {
+                tracing::error!("failed to load note {}", e);
+                None
+            }),
+            None => None,
+        };
+        if let Err(e) = Note::save_with_card(&self.db_pool, &mut reverted.clone(), None, &self.settings.author).await {
+            tracing::error!("failed to revert card {}", e);
+            self.alert = Some(AlertPopup::new(
+                std::time::Duration::new(5, 0),
+                "Error: Failed to revert card!".to_string(),
+                AlertPriority::Red,
+            ));
+            return;
+        }
+        self.record_card_saved(current, current_note, reverted.id).await;
+        self.reload_cards().await;
+        self.alert = Some(AlertPopup::new(
+            std::time::Duration::new(5, 0),
+            "Reverted card; [u] to undo".to_string(),
+            AlertPriority::Green,
+        ));
+        if let Some(card) = Card::load_many(&self.db_pool, &[reverted.id]).await.ok().and_then(|mut c| c.pop()) {
+            self.open_revisions(card).await;
+        }
+    }
+
+    /// Moves cards to another deck, recording the move so it can be undone
+    async fn move_cards(&mut self, card_ids: &[Uuid], deck_id: Uuid, deck_name: &str) -> Result<(), sqlx::Error> {
+        let before = Card::load_many(&self.db_pool, card_ids).await?;
+        Card::move_to_deck(&self.db_pool, card_ids, deck_id).await?;
+        self.history.push(Command::MoveCards {
+            before,
+            deck_id,
+            deck_name: deck_name.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Moves cards to the trash, recording the deletion so it can be undone
+    async fn delete_cards(&mut self, card_ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        Card::delete_many_from_db(&self.db_pool, card_ids).await?;
+        self.history.push(Command::DeleteCards(card_ids.to_vec()));
+        Ok(())
+    }
+
+    /// Moves a deck and its cards to the trash, recording the deletion so it can be undone
+    async fn delete_deck(&mut self, deck_id: Uuid) -> Result<(), sqlx::Error> {
+        if let Some(deckset) = &mut self.deckset {
+            if let Some(deck) = deckset.get_deck_by_id(deck_id) {
+                deckset.delete_deck_with_cards(&self.db_pool, deck_id).await?;
+                self.history.push(Command::DeleteDeck(deck));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the text entered into an `InputPrompt`
+    async fn submit_prompt(&mut self, prompt: InputPrompt) {
+        match prompt.action {
+            PromptAction::FilterCards => {
+                if prompt.text.trim().is_empty() {
+                    self.card_filter = None;
+                    self.pointer = ListState::default();
+                } else {
+                    match TagExpr::parse(&prompt.text) {
+                        Ok(expr) => {
+                            self.card_filter = Some(expr);
+                            self.pointer = ListState::default();
+                        }
+                        Err(e) => {
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                format!("Error: {}", e),
+                                AlertPriority::Red,
+                            ));
+                        }
+                    }
+                }
+            }
+            PromptAction::EditTags(card_ids) => {
+                // `tag` or `+tag` adds, `-tag` removes
+                let mut result: Result<(), sqlx::Error> = Ok(());
+                for name in Tag::parse_names(&prompt.text) {
+                    result = match name.strip_prefix('-') {
+                        Some(name) => Tag::remove_from_cards(&self.db_pool, name, &card_ids).await,
+                        None => {
+                            let name = name.trim_start_matches('+');
+                            if name.is_empty() {
+                                continue;
+                            }
+                            Tag::add_to_cards(&self.db_pool, name, &card_ids).await
+                        }
+                    };
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                match result {
+                    Ok(_) => {
+                        self.selected_cards.clear();
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("Updated tags on {} card(s)", card_ids.len()),
+                            AlertPriority::Green,
+                        ));
+                    }
+                    Err(e) => {
+                        tracing::error!("failed to update tags {}", e);
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            "Error: Failed to update tags!".to_string(),
+                            AlertPriority::Red,
+                        ));
+                    }
+                }
+                self.reload_cards().await;
+            }
+            PromptAction::MoveCards(card_ids) => {
+                let deck_id = self.deckset.as_ref().and_then(|deckset| {
+                    deckset
+                        .decks
+                        .iter()
+                        .find(|deck| deck.name.eq_ignore_ascii_case(prompt.text.trim()))
+                        .map(|deck| deck.id)
+                });
+                match deck_id {
+                    Some(deck_id) => match self.move_cards(&card_ids, deck_id, prompt.text.trim()).await {
+                        Ok(_) => {
+                            self.selected_cards.clear();
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                format!("Moved {} card(s) to '{}'", card_ids.len(), prompt.text.trim()),
+                                AlertPriority::Green,
+                            ));
+                        }
+                        Err(e) => {
+                            tracing::error!("failed to move cards {}", e);
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                "Error: Failed to move cards!".to_string(),
+                                AlertPriority::Red,
+                            ));
+                        }
+                    },
+                    None => {
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("Error: No deck named '{}'", prompt.text.trim()),
+                            AlertPriority::Red,
+                        ));
+                    }
+                }
+                self.reload_cards().await;
+            }
+            PromptAction::Search { from_deck } => {
+                let result = match SearchQuery::parse(&prompt.text) {
+                    Ok(query) => SearchResults::run(query, &self.db_pool, from_deck)
+                        .await
+                        .map_err(|e| {
+                            tracing::error!("search failed {}", e);
+                            "Search failed!".to_string()
+                        }),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(results) => {
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("{} card(s) found", results.cards.len()),
+                            AlertPriority::Green,
+                        ));
+                        self.search_results = Some(results);
+                        self.card_filter = None;
+                        self.selected_cards.clear();
+                        self.pointer = ListState::default();
+                        self.current_screen = CurrentScreen::CARDS;
+                    }
+                    Err(e) => {
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("Error: {}", e),
+                            AlertPriority::Red,
+                        ));
+                        if !from_deck {
+                            self.current_screen = CurrentScreen::DECKS;
+                        }
+                    }
+                }
+            }
+            PromptAction::Simulate { deck } => {
+                let result = match SimParams::parse(prompt.text.split_whitespace()) {
+                    Ok(mut params) => {
+                        params.deck = params.deck.or(deck);
+                        simulation::run(&self.db_pool, &params, self.settings.rollover_hour)
+                            .await
+                            .map(|loads| SimulationScreen { params, loads })
+                            .map_err(|e| {
+                                tracing::error!("simulation failed {}", e);
+                                "Simulation failed!".to_string()
+                            })
+                    }
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(simulation) => {
+                        self.simulation = Some(simulation);
+                        self.current_screen = CurrentScreen::SIMULATION;
+                    }
+                    Err(e) => {
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("Error: {}", e),
+                            AlertPriority::Red,
+                        ));
+                        self.current_screen = CurrentScreen::DECKS;
+                    }
+                }
+            }
+            PromptAction::ExamDate { deck_id, name } => {
+                self.current_screen = CurrentScreen::DECKS;
+                let text = prompt.text.trim();
+                let exam_date = match text {
+                    "" => None,
+                    text => match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+                        Ok(date) if date > day_of(Utc::now(), self.settings.rollover_hour) => Some(date),
+                        Ok(_) => {
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                "Error: The exam date must be after today".to_string(),
+                                AlertPriority::Red,
+                            ));
+                            return;
+                        }
+                        Err(_) => {
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                format!("Error: `{}` is not a date (YYYY-MM-DD)", text),
+                                AlertPriority::Red,
+                            ));
+                            return;
+                        }
+                    },
+                };
+                if let Err(e) = Deck::set_exam_date(&self.db_pool, deck_id, exam_date).await {
+                    tracing::error!("failed to update deck {}", e);
+                    self.alert = Some(AlertPopup::new(
+                        std::time::Duration::new(5, 0),
+                        "Error: Failed to update deck!".to_string(),
+                        AlertPriority::Red,
+                    ));
+                    return;
+                }
+                if let Err(e) = self.fetch_decks().await {
+                    tracing::error!("failed to reload decks {}", e);
+                }
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    match exam_date {
+                        Some(date) => format!("'{}' is now studied towards an exam on {}", name, date),
+                        None => format!("Exam mode turned off for '{}'", name),
+                    },
+                    AlertPriority::Green,
+                ));
+            }
+            PromptAction::DeckLimits { deck_id, name } => {
+                self.current_screen = CurrentScreen::DECKS;
+                let current = self
+                    .deckset
+                    .as_ref()
+                    .and_then(|deckset| deckset.get_deck_by_id(deck_id))
+                    .map(|deck| deck.limits)
+                    .unwrap_or_default();
+                let limits = match current.parse(&prompt.text) {
+                    Ok(limits) => limits,
+                    Err(e) => {
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("Error: {}", e),
+                            AlertPriority::Red,
+                        ));
+                        return;
+                    }
+                };
+                if let Err(e) = Deck::set_limits(&self.db_pool, deck_id, &limits).await {
+                    tracing::error!("failed to update deck {}", e);
+                    self.alert = Some(AlertPopup::new(
+                        std::time::Duration::new(5, 0),
+                        "Error: Failed to update deck!".to_string(),
+                        AlertPriority::Red,
+                    ));
+                    return;
+                }
+                if let Err(e) = self.fetch_decks().await {
+                    tracing::error!("failed to reload decks {}", e);
+                }
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    format!("Daily limits of '{}': {}", name, limits),
+                    AlertPriority::Green,
+                ));
+            }
+            PromptAction::NewPreset | PromptAction::EditPreset(_) => {
+                self.current_screen = CurrentScreen::PRESETS;
+                let result = match prompt.action {
+                    PromptAction::EditPreset(id) => self
+                        .presets
+                        .as_ref()
+                        .and_then(|presets| presets.presets.iter().find(|preset| preset.id == id))
+                        .ok_or_else(|| "Preset not found".to_string())
+                        .and_then(|preset| preset.parse(&prompt.text)),
+                    _ => match prompt.text.trim() {
+                        "" => Err("A preset needs a name".to_string()),
+                        name => Ok(Preset::new(name)),
+                    },
+                };
+                let preset = match result {
+                    Ok(preset) => preset,
+                    Err(e) => {
+                        self.alert = Some(AlertPopup::new(
+                            std::time::Duration::new(5, 0),
+                            format!("Error: {}", e),
+                            AlertPriority::Red,
+                        ));
+                        return;
+                    }
+                };
+                if let Err(e) = preset.save(&self.db_pool).await {
+                    // e.g. the name is taken
+                    tracing::error!("failed to save preset {}", e);
+                    self.alert = Some(AlertPopup::new(
+                        std::time::Duration::new(5, 0),
+                        "Error: Failed to save preset! Is the name taken?".to_string(),
+                        AlertPriority::Red,
+                    ));
+                    return;
+                }
+                self.reload_presets().await;
+                self.alert = Some(AlertPopup::new(
+                    std::time::Duration::new(5, 0),
+                    format!("Saved preset '{}'", preset.name),
+                    AlertPriority::Green,
+                ));
+            }
+        }
+    }
+
     async fn update(&mut self, event: Event) -> eyre::Result<()> {
+        // Cards in their learning steps come back into an open review once their step is over
+        if let (Event::Tick, CurrentScreen::REVIEW) = (&event, &self.current_screen) {
+            if let Some(review) = &mut self.review {
+                review.requeue_due(Utc::now());
+            }
+        }
         if let Event::Key(key) = event {
+            // Undo/redo work from any screen that isn't taking text input or asking for confirmation
+            let undo_screen = !matches!(
+                self.current_screen,
+                CurrentScreen::CreateCard | CurrentScreen::CreateDeck | CurrentScreen::CONFIRM(_) | CurrentScreen::PROMPT
+            );
+            // An answer being typed in a review is text input too
+            let typing = matches!(self.current_screen, CurrentScreen::REVIEW)
+                && self.review.as_ref().is_some_and(|review| review.is_typing());
+            if undo_screen && !typing && matches!(self.mode, Mode::NORMAL) {
+                match key.code {
+                    Char('u') => {
+                        self.undo_or_redo(false).await;
+                        return Ok(());
+                    }
+                    Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.undo_or_redo(true).await;
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+
             match &self.current_screen {
                 // Main screen lists cards
                 CurrentScreen::DECKS => match &mut self.mode {
@@ -392,7 +1766,7 @@ impl<'a> App<'a> {
                                 {
                                     tracing::info!("Deleting deck: {}", curr_deck.name);
                                     let popup = ConfirmPopup {
-                                            text: format!("Are you sure you want to delete deck '{}'?\n All of its cards will be also be deleted!", curr_deck.name),
+                                            text: format!("Are you sure you want to delete deck '{}'?\n All of its cards will also be moved to the trash.", curr_deck.name),
                                             action: ConfirmAction::DeleteDeck(curr_deck.id),
                                         };
                                     self.current_screen = CurrentScreen::CONFIRM(popup);
@@ -413,34 +1787,93 @@ impl<'a> App<'a> {
                             }
                         },
                         KeyCode::Enter => {
-                            // TODO: rewrite to use stored Uuid for deck retrieval, rather than assumign that n decks *displayed* is same as n decks (this is to achieve compatibility with selecting items when searching)
-                            match &self.deckset {
-                                // Check we have a deckset
-                                Some(deckset) => {
-                                    // Check we have a valid "pointer" to selected deck
-                                    if let Some(curr_deck) =
-                                        deckset.decks.get(self.pointer.selected().unwrap_or(0usize))
-                                    {
-                                        let mut deck = curr_deck.clone();
-                                        match deck.load_cards(&self.db_pool).await {
-                                            Ok(_) => {}
-                                            Err(e) => {
-                                                tracing::error!("failed to load cards {}", e);
-                                                self.alert = Some(AlertPopup::new(
-                                                    std::time::Duration::new(5, 0),
-                                                    "Error: Failed to load cards in deck."
-                                                        .to_string(),
-                                                    AlertPriority::Red,
-                                                ));
-                                            }
-                                        };
-                                        self.deck = Some(deck);
-                                        // Set ListState to default
-                                        self.pointer = ListState::default();
-                                        self.current_screen = CurrentScreen::CARDS;
-                                    };
+                            self.open_deck(self.pointer.selected().unwrap_or(0usize)).await;
+                        }
+                        Char('s') => self.open_search(false),
+                        Char('t') => self.open_trash().await,
+                        Char('g') => self.open_stats().await,
+                        Char('w') => self.open_simulation_prompt(),
+                        Char('x') => self.open_exam_date_prompt(),
+                        Char('o') => self.open_limits_prompt(),
+                        Char('p') => self.open_presets().await,
+                        Char('l') => self.open_leeches().await,
+                        Char('r') => {
+                            let deck = self
+                                .deckset
+                                .as_ref()
+                                .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+                                .cloned();
+                            if let Some(deck) = deck {
+                                let reverse = !deck.reverse_by_default;
+                                match Deck::set_reverse_by_default(&self.db_pool, deck.id, reverse).await {
+                                    Ok(_) => {
+                                        if let Err(e) = self.fetch_decks().await {
+                                            tracing::error!("failed to reload decks {}", e);
+                                        }
+                                        self.alert = Some(AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            format!(
+                                                "New cards in '{}' {} get a reverse card",
+                                                deck.name,
+                                                if reverse { "will" } else { "won't" }
+                                            ),
+                                            AlertPriority::Green,
+                                        ));
+                                    }
+                                    Err(e) => {
+                                        tracing::error!("failed to update deck {}", e);
+                                        self.alert = Some(AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            "Error: Failed to update deck!".to_string(),
+                                            AlertPriority::Red,
+                                        ));
+                                    }
+                                }
+                            }
+                        }
+                        Char('a') => {
+                            let deck = self
+                                .deckset
+                                .as_ref()
+                                .and_then(|deckset| deckset.decks.get(self.pointer.selected().unwrap_or(0usize)))
+                                .cloned();
+                            if let Some((deck, preset)) =
+                                deck.as_ref().and_then(|deck| Some((deck, deck.preset.as_ref()?)))
+                            {
+                                self.alert = Some(AlertPopup::new(
+                                    std::time::Duration::new(5, 0),
+                                    format!(
+                                        "'{}' uses preset '{}'; set its review mode under [p] presets",
+                                        deck.name, preset.name
+                                    ),
+                                    AlertPriority::Yellow,
+                                ));
+                            } else if let Some(deck) = deck {
+                                let type_answer = !deck.type_answer;
+                                match Deck::set_type_answer(&self.db_pool, deck.id, type_answer).await {
+                                    Ok(_) => {
+                                        if let Err(e) = self.fetch_decks().await {
+                                            tracing::error!("failed to reload decks {}", e);
+                                        }
+                                        self.alert = Some(AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            format!(
+                                                "Reviews of '{}' {} require typing the answer",
+                                                deck.name,
+                                                if type_answer { "will" } else { "won't" }
+                                            ),
+                                            AlertPriority::Green,
+                                        ));
+                                    }
+                                    Err(e) => {
+                                        tracing::error!("failed to update deck {}", e);
+                                        self.alert = Some(AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            "Error: Failed to update deck!".to_string(),
+                                            AlertPriority::Red,
+                                        ));
+                                    }
                                 }
-                                None => {}
                             }
                         }
                         KeyCode::Char('/') => {
@@ -458,66 +1891,164 @@ impl<'a> App<'a> {
                         _ => {}
                     },
                     Mode::SEARCH(ref mut searcher) => match &key.code {
-                        KeyCode::Esc => self.mode = Mode::NORMAL,
+                        KeyCode::Esc => {
+                            self.mode = Mode::NORMAL;
+                            self.pointer = ListState::default();
+                        }
+                        KeyCode::Enter => {
+                            let index = searcher.original_index(self.pointer.selected().unwrap_or(0usize));
+                            self.mode = Mode::NORMAL;
+                            if let Some(index) = index {
+                                self.open_deck(index).await;
+                            }
+                        }
+                        KeyCode::Down => self.select_next(),
+                        KeyCode::Up => self.select_previous(),
                         KeyCode::Char(ch) => {
                             searcher.push_and_search(*ch);
-                            searcher.build_index();
-                            searcher.search();
+                            self.reset_pointer_to_results();
                         }
                         KeyCode::Backspace => {
                             searcher.pop_and_search();
-                            searcher.build_index();
-                            searcher.search();
+                            self.reset_pointer_to_results();
                         }
                         _ => {}
                     },
                 },
 
                 // DISPLAY CARDS
-                CurrentScreen::CARDS => match &key.code {
-                    Char('q') => self.should_quit = true,
-                    Char('j') => {
-                        // how to set back to None?
-                        if self.n_items != 0 {
-                            let selected = match self.pointer.selected() {
-                                Some(val) => {
-                                    if val < self.n_items - 1 {
-                                        val + 1
-                                    } else {
-                                        val
+                CurrentScreen::CARDS => match &mut self.mode {
+                    Mode::SEARCH(ref mut searcher) => match &key.code {
+                        KeyCode::Esc => {
+                            self.mode = Mode::NORMAL;
+                            self.pointer = ListState::default();
+                        }
+                        KeyCode::Enter => {
+                            let index = searcher.original_index(self.pointer.selected().unwrap_or(0usize));
+                            self.mode = Mode::NORMAL;
+                            self.pointer = ListState::default();
+                            if let Some(card) = index.and_then(|i| self.displayed_cards().get(i).cloned()) {
+                                self.open_editor(&card).await;
+                            }
+                        }
+                        KeyCode::Down => self.select_next(),
+                        KeyCode::Up => self.select_previous(),
+                        KeyCode::Char(ch) => {
+                            searcher.push_and_search(*ch);
+                            self.reset_pointer_to_results();
+                        }
+                        KeyCode::Backspace => {
+                            searcher.pop_and_search();
+                            self.reset_pointer_to_results();
+                        }
+                        _ => {}
+                    },
+                    _ => match &key.code {
+                        Char('q') => self.should_quit = true,
+                        #[allow(clippy::collapsible_match)]
+                        Char('j') => {
+                            // how to set back to None?
+                            if self.n_items != 0 {
+                                let selected = match self.pointer.selected() {
+                                    Some(val) => {
+                                        if val < self.n_items - 1 {
+                                            val + 1
+                                        } else {
+                                            val
+                                        }
                                     }
-                                }
-                                None => 0usize,
+                                    None => 0usize,
+                                };
+                                self.pointer.select(Some(selected));
+                            }
+                        }
+                        Char('k') => {
+                            if let Some(val) = self.pointer.selected() {
+                                self.pointer.select(Some(val.saturating_sub(1)));
+                            }
+                        }
+                        Char('b') => {
+                            self.card_filter = None;
+                            self.selected_cards.clear();
+                            self.pointer = ListState::default();
+                            self.current_screen = match self.search_results.take() {
+                                Some(results) if results.from_deck => CurrentScreen::CARDS,
+                                _ => CurrentScreen::DECKS,
                             };
-                            self.pointer.select(Some(selected));
                         }
-                    }
-                    Char('k') => {
-                        if let Some(val) = self.pointer.selected() {
-                            self.pointer.select(Some(val.saturating_sub(1)));
+                        Char('s') => {
+                            let from_deck = self
+                                .search_results
+                                .as_ref()
+                                .is_none_or(|results| results.from_deck);
+                            self.open_search(from_deck);
                         }
-                    }
-                    Char('b') => {
-                        self.current_screen = CurrentScreen::DECKS;
-                    }
-                    Char('n') => {
-                        // Create new card
-                        self.create_screen = Some(CreateCard::from(&Card::new_with_deck(
-                            self.deck.as_ref().unwrap().id,
-                        )));
-                        self.current_screen = CurrentScreen::CreateCard;
-                    }
-                    Char('d') => {
-                        if let Some(deck) = &self.deck {
-                            if let Some(card) = deck
-                                .cards
-                                .clone()
-                                .unwrap_or(Vec::<Card>::new())
+                        Char(' ') => {
+                            if let Some(card) = self
+                                .displayed_cards()
                                 .get(self.pointer.selected().unwrap_or(0usize))
                             {
+                                if !self.selected_cards.remove(&card.id) {
+                                    self.selected_cards.insert(card.id);
+                                }
+                            }
+                        }
+                        Char('t') => {
+                            // Edit tags of the selected cards, or of the card under the cursor
+                            let card_ids: Vec<Uuid> = self.target_card_ids();
+                            if card_ids.is_empty() {
+                                self.alert = Some(AlertPopup::new(
+                                    std::time::Duration::new(5, 0),
+                                    "Warning: No card selected".to_string(),
+                                    AlertPriority::Yellow,
+                                ));
+                            } else {
+                                self.prompt = Some(InputPrompt::new(
+                                    &format!("Edit tags on {} card(s)", card_ids.len()),
+                                    "`tag` or `+tag` to add, `-tag` to remove",
+                                    "",
+                                    PromptAction::EditTags(card_ids),
+                                ));
+                                self.current_screen = CurrentScreen::PROMPT;
+                            }
+                        }
+                        Char('f') => {
+                            let current = self
+                                .card_filter
+                                .as_ref()
+                                .map(|filter| filter.to_string())
+                                .unwrap_or_default();
+                            self.prompt = Some(InputPrompt::new(
+                                "Filter by tags",
+                                "e.g. `verb and not irregular`; leave empty to clear",
+                                &current,
+                                PromptAction::FilterCards,
+                            ));
+                            self.current_screen = CurrentScreen::PROMPT;
+                        }
+                        Char('r') => self.start_review(false).await,
+                        Char('c') => self.start_review(true).await,
+                        Char('n') if self.search_results.is_some() => {
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                "Warning: Open a deck to create cards".to_string(),
+                                AlertPriority::Yellow,
+                            ));
+                        }
+                        Char('n') => {
+                            // Create new card
+                            let card = Card::new_with_deck(self.deck.as_ref().unwrap().id);
+                            self.open_editor(&card).await;
+                        }
+                        Char('d') => {
+                            let card_ids: Vec<Uuid> = self.target_card_ids();
+                            if !card_ids.is_empty() {
                                 self.current_screen = CurrentScreen::CONFIRM(ConfirmPopup {
-                                    text: "Are you sure you want to delete this card?".to_string(),
-                                    action: ConfirmAction::DeleteCard(card.id),
+                                    text: format!(
+                                        "Are you sure you want to delete {} card(s)?",
+                                        card_ids.len()
+                                    ),
+                                    action: ConfirmAction::DeleteCards(card_ids),
                                 });
                                 self.pointer = ListState::default();
                             } else {
@@ -528,19 +2059,14 @@ impl<'a> App<'a> {
                                 ));
                             }
                         }
-                    }
-                    KeyCode::Enter => {
-                        if let Some(deck) = &self.deck {
-                            // TODO: review this .clone()
-                            if let Some(card) = deck
-                                .cards
-                                .clone()
-                                .unwrap_or(Vec::<Card>::new())
+                        KeyCode::Enter => {
+                            if let Some(card) = self
+                                .displayed_cards()
                                 .get(self.pointer.selected().unwrap_or(0usize))
+                                .cloned()
                             {
-                                self.current_screen = CurrentScreen::CreateCard;
                                 self.pointer = ListState::default();
-                                self.create_screen = Some(CreateCard::from(card));
+                                self.open_editor(&card).await;
                             } else {
                                 self.alert = Some(AlertPopup::new(
                                     std::time::Duration::new(5, 0),
@@ -549,8 +2075,50 @@ impl<'a> App<'a> {
                                 ));
                             }
                         }
-                    }
-                    _ => {}
+                        Char('m') => {
+                            let card_ids: Vec<Uuid> = self.target_card_ids();
+                            if card_ids.is_empty() {
+                                self.alert = Some(AlertPopup::new(
+                                    std::time::Duration::new(5, 0),
+                                    "Warning: No card selected".to_string(),
+                                    AlertPriority::Yellow,
+                                ));
+                            } else {
+                                self.prompt = Some(InputPrompt::new(
+                                    &format!("Move {} card(s) to deck", card_ids.len()),
+                                    "Name of an existing deck",
+                                    "",
+                                    PromptAction::MoveCards(card_ids),
+                                ));
+                                self.current_screen = CurrentScreen::PROMPT;
+                            }
+                        }
+                        Char('h') => {
+                            let card = self
+                                .displayed_cards()
+                                .get(self.pointer.selected().unwrap_or(0usize))
+                                .cloned();
+                            if let Some(card) = card {
+                                self.open_revisions(card).await;
+                            }
+                        }
+                        Char('o') => self.browser.cycle_sort(),
+                        Char('O') => self.browser.reverse_sort(),
+                        Char('v') => self.browser.toggle_detail(),
+                        Char(ch) if ch.is_ascii_digit() => {
+                            self.browser.toggle_column(ch.to_digit(10).unwrap_or(0) as usize);
+                        }
+                        Char('/') => {
+                            let text: Vec<String> = self
+                                .displayed_cards()
+                                .iter()
+                                .map(|card| CardColumn::Front.cell_text(card, &HashMap::new()))
+                                .collect();
+                            self.mode = Mode::SEARCH(Searcher::new(text.iter().map(|t| t.as_str()).collect()));
+                            self.pointer = ListState::default();
+                        }
+                        _ => {}
+                    },
                 },
                 // CREATE NEW DECK
                 CurrentScreen::CreateDeck => {
@@ -607,6 +2175,8 @@ impl<'a> App<'a> {
                                         tf.right();
                                     }
                                 }
+                                Char('h') | KeyCode::Left if create_card.preview => create_card.scroll_code(-4),
+                                Char('l') | KeyCode::Right if create_card.preview => create_card.scroll_code(4),
                                 Char('h') | KeyCode::Left => {
                                     if let Some(tf) = create_card.current_text_field() {
                                         tf.left();
@@ -620,37 +2190,60 @@ impl<'a> App<'a> {
                                 KeyCode::Tab => {
                                     create_card.toggle_field();
                                 }
+                                Char('r') => create_card.toggle_reverse(),
+                                Char('p') => create_card.toggle_preview(),
+                                Char('t') => create_card.cycle_note_type(),
+                                KeyCode::Enter if !create_card.generates_cards() => {
+                                    self.alert = Some(AlertPopup::new(
+                                        std::time::Duration::new(5, 0),
+                                        "Fill in a field shown on the front of a card".to_string(),
+                                        AlertPriority::Yellow,
+                                    ));
+                                }
                                 KeyCode::Enter => {
-                                    if let Some(deck) = &mut self.deck {
-                                        match create_card.try_save(&self.db_pool).await {
-                                            Ok(_) => {
-                                                self.current_screen = CurrentScreen::CARDS;
-                                                self.create_screen = None;
-                                                self.alert = Some(AlertPopup::new(
-                                                    std::time::Duration::new(5, 0),
-                                                    "Card saved".to_string(),
-                                                    AlertPriority::Green,
-                                                ));
-                                                match deck.load_cards(&self.db_pool).await {
-                                                    Ok(_) => tracing::info!("Deck reloaded"),
-                                                    Err(e) => tracing::error!(
-                                                        "Failed to reload deck! {}",
-                                                        e
-                                                    ),
-                                                };
-                                            }
-                                            Err(e) => {
-                                                self.alert = Some(AlertPopup::new(
-                                                    std::time::Duration::new(5, 0),
-                                                    "Error: Failed to save card!".to_string(),
-                                                    AlertPriority::Red,
-                                                ));
-                                                tracing::error!("failed to save card {}", e);
-                                            }
-                                        }
+                                    // Cards that haven't been saved yet have no creation time
+                                    let before = create_card.card.clone();
+                                    let before_note = match before.note_id {
+                                        Some(note_id) => Note::load(&self.db_pool, note_id).await.unwrap_or_else(|e| {
+                                            tracing::error!("failed to load note {}", e);
+                                            None
+                                        }),
+                                        None => None,
                                     };
+                                    match create_card.try_save(&self.db_pool, &self.settings.author).await {
+                                        Ok(_) => {
+                                            let card_id = create_card.card.id;
+                                            self.record_card_saved(before, before_note, card_id).await;
+                                            // Leeches are edited from their list, and go back to it
+                                            self.current_screen = match self.leeches {
+                                                Some(_) => CurrentScreen::LEECHES,
+                                                None => CurrentScreen::CARDS,
+                                            };
+                                            self.create_screen = None;
+                                            self.alert = Some(AlertPopup::new(
+                                                std::time::Duration::new(5, 0),
+                                                "Card saved".to_string(),
+                                                AlertPriority::Green,
+                                            ));
+                                            self.reload_cards().await;
+                                            self.reload_leeches().await;
+                                        }
+                                        Err(e) => {
+                                            self.alert = Some(AlertPopup::new(
+                                                std::time::Duration::new(5, 0),
+                                                "Error: Failed to save card!".to_string(),
+                                                AlertPriority::Red,
+                                            ));
+                                            tracing::error!("failed to save card {}", e);
+                                        }
+                                    }
+                                }
+                                KeyCode::Esc => {
+                                    self.current_screen = match self.leeches {
+                                        Some(_) => CurrentScreen::LEECHES,
+                                        None => CurrentScreen::CARDS,
+                                    }
                                 }
-                                KeyCode::Esc => self.current_screen = CurrentScreen::CARDS,
                                 _ => {}
                             },
                             Mode::INSERT => {
@@ -674,45 +2267,343 @@ impl<'a> App<'a> {
                     self.current_screen = CurrentScreen::DECKS;
                 }
                 CurrentScreen::REVIEW => {
-                    todo!()
+                    if let Some(review) = &mut self.review {
+                        if review.is_finished() {
+                            self.review = None;
+                            self.current_screen = CurrentScreen::CARDS;
+                        } else if review.is_typing() {
+                            match &key.code {
+                                Char(c) => review.push_char(*c),
+                                KeyCode::Backspace => review.pop_char(),
+                                KeyCode::Enter => review.submit_typed(),
+                                KeyCode::Esc => {
+                                    self.review = None;
+                                    self.current_screen = CurrentScreen::CARDS;
+                                }
+                                _ => {}
+                            }
+                        } else {
+                            let grade = match &key.code {
+                                Char('y') | Char('n') if review.revealed => Some(key.code == Char('y')),
+                                KeyCode::Enter if review.revealed => review.suggested_grade(),
+                                _ => None,
+                            };
+                            if let Some(correct) = grade {
+                                match review.answer(&self.db_pool, correct).await {
+                                    Ok(Some(command)) => {
+                                        if let Command::GradeReview { before, after, .. } = &command {
+                                            if leech::is_leech(after) && !leech::is_leech(before) {
+                                                self.alert = Some(AlertPopup::new(
+                                                    std::time::Duration::new(5, 0),
+                                                    format!(
+                                                        "Card failed {} times and is now a leech{}; see [l] leeches",
+                                                        after.lapses,
+                                                        if after.suspended { " (suspended)" } else { "" }
+                                                    ),
+                                                    AlertPriority::Yellow,
+                                                ));
+                                            }
+                                        }
+                                        self.history.push(command);
+                                        self.refresh_activity().await;
+                                        self.refresh_deck_counts().await;
+                                    }
+                                    Ok(None) => {}
+                                    Err(e) => {
+                                        tracing::error!("failed to log review {}", e);
+                                        self.alert = Some(AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            "Error: Failed to save review!".to_string(),
+                                            AlertPriority::Red,
+                                        ));
+                                    }
+                                }
+                                return Ok(());
+                            }
+                            match &key.code {
+                                Char(c) if review.is_choosing() && c.is_ascii_digit() => {
+                                    if let Some(index) = c.to_digit(10).and_then(|n| n.checked_sub(1)) {
+                                        review.pick(index as usize);
+                                    }
+                                }
+                                Char(' ') if review.is_choosing() => {}
+                                Char('?') => {
+                                    review.reveal_hint();
+                                }
+                                Char(' ') => review.reveal(),
+                                Char('h') | KeyCode::Left => review.scroll_code(-4),
+                                Char('l') | KeyCode::Right => review.scroll_code(4),
+                                Char('b') | KeyCode::Esc => {
+                                    self.review = None;
+                                    self.current_screen = CurrentScreen::CARDS;
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                CurrentScreen::PROMPT => {
+                    if let Some(prompt) = &mut self.prompt {
+                        match &key.code {
+                            KeyCode::Enter => {
+                                self.current_screen = CurrentScreen::CARDS;
+                                if let Some(prompt) = self.prompt.take() {
+                                    self.submit_prompt(prompt).await;
+                                }
+                            }
+                            KeyCode::Esc => {
+                                self.current_screen = match prompt.action {
+                                    PromptAction::Search { from_deck: false }
+                                    | PromptAction::Simulate { .. }
+                                    | PromptAction::ExamDate { .. }
+                                    | PromptAction::DeckLimits { .. } => CurrentScreen::DECKS,
+                                    PromptAction::NewPreset | PromptAction::EditPreset(_) => CurrentScreen::PRESETS,
+                                    _ => CurrentScreen::CARDS,
+                                };
+                                self.prompt = None;
+                            }
+                            KeyCode::Backspace => prompt.pop_char(),
+                            Char(ch) => prompt.push_char(*ch),
+                            _ => {}
+                        }
+                    }
                 }
+                CurrentScreen::REVISIONS => match &key.code {
+                    Char('q') => self.should_quit = true,
+                    Char('j') => self.select_next(),
+                    Char('k') => self.select_previous(),
+                    Char('r') | KeyCode::Enter => self.revert_to_revision().await,
+                    Char('b') | KeyCode::Esc => {
+                        self.revisions = None;
+                        self.pointer = ListState::default();
+                        self.current_screen = CurrentScreen::CARDS;
+                    }
+                    _ => {}
+                },
+                CurrentScreen::LEECHES => match &key.code {
+                    Char('q') => self.should_quit = true,
+                    Char('j') => self.select_next(),
+                    Char('k') => self.select_previous(),
+                    Char('e') | KeyCode::Enter => {
+                        if let Some(card) = self.selected_leech() {
+                            self.open_editor(&card).await;
+                        }
+                    }
+                    Char('s') => self.toggle_leech_suspended().await,
+                    Char('c') => self.clear_leech().await,
+                    Char('b') | KeyCode::Esc => {
+                        self.leeches = None;
+                        self.pointer = ListState::default();
+                        self.current_screen = CurrentScreen::DECKS;
+                    }
+                    _ => {}
+                },
+                CurrentScreen::TRASH => match &key.code {
+                    Char('q') => self.should_quit = true,
+                    Char('j') => self.select_next(),
+                    Char('k') => self.select_previous(),
+                    Char('r') => {
+                        if let Some(item) = self.selected_trash_item() {
+                            match item.restore(&self.db_pool).await {
+                                Ok(renamed) => {
+                                    self.alert = Some(match RenamedDeck::describe(&renamed) {
+                                        Some(text) => AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            text,
+                                            AlertPriority::Yellow,
+                                        ),
+                                        None => AlertPopup::new(
+                                            std::time::Duration::new(5, 0),
+                                            "Restored from trash".to_string(),
+                                            AlertPriority::Green,
+                                        ),
+                                    });
+                                }
+                                Err(e) => {
+                                    tracing::error!("failed to restore from trash {}", e);
+                                    self.alert = Some(AlertPopup::new(
+                                        std::time::Duration::new(5, 0),
+                                        "Error: Failed to restore!".to_string(),
+                                        AlertPriority::Red,
+                                    ));
+                                }
+                            }
+                            self.reload_trash().await;
+                        }
+                    }
+                    Char('p') => {
+                        if let Some(item) = self.selected_trash_item() {
+                            self.current_screen = CurrentScreen::CONFIRM(ConfirmPopup {
+                                text: format!(
+                                    "Permanently delete {}?\n This cannot be undone!",
+                                    item.label()
+                                ),
+                                action: ConfirmAction::PurgeTrash(item),
+                            });
+                        }
+                    }
+                    Char('b') | KeyCode::Esc => {
+                        self.trash = None;
+                        self.pointer = ListState::default();
+                        self.current_screen = CurrentScreen::DECKS;
+                    }
+                    _ => {}
+                },
+                CurrentScreen::STATS => match &key.code {
+                    Char('q') => self.should_quit = true,
+                    Char('d') => {
+                        if let Some(stats) = &mut self.stats {
+                            if let Err(e) = stats.toggle_deck(&self.db_pool).await {
+                                tracing::error!("failed to load stats {}", e);
+                                self.alert = Some(AlertPopup::new(
+                                    std::time::Duration::new(5, 0),
+                                    "Error: Failed to load stats!".to_string(),
+                                    AlertPriority::Red,
+                                ));
+                            }
+                        }
+                    }
+                    Char('b') | KeyCode::Esc => {
+                        self.stats = None;
+                        self.current_screen = CurrentScreen::DECKS;
+                    }
+                    _ => {}
+                },
+                CurrentScreen::PRESETS => match &key.code {
+                    Char('q') => self.should_quit = true,
+                    Char('j') => self.select_next(),
+                    Char('k') => self.select_previous(),
+                    Char('n') => {
+                        self.prompt = Some(InputPrompt::new(
+                            "New preset",
+                            "Name of the preset; edit its options with [e]",
+                            "",
+                            PromptAction::NewPreset,
+                        ));
+                        self.current_screen = CurrentScreen::PROMPT;
+                    }
+                    Char('e') | KeyCode::Enter => {
+                        if let Some(preset) = self.selected_preset() {
+                            self.prompt = Some(InputPrompt::new(
+                                &format!("Edit preset '{}' (changes every deck using it)", preset.name),
+                                "scheduler=sm2|retention steps=<minutes,..|none> new= reviews= order= mix= \
+                                 retention=<0.7-0.99> leech=<lapses> leeches=suspend|tag mode=reveal|type|choice",
+                                &preset.to_string(),
+                                PromptAction::EditPreset(preset.id),
+                            ));
+                            self.current_screen = CurrentScreen::PROMPT;
+                        }
+                    }
+                    Char('d') => {
+                        if let Some(preset) = self.selected_preset() {
+                            self.current_screen = CurrentScreen::CONFIRM(ConfirmPopup {
+                                text: format!(
+                                    "Delete preset '{}'?\n Its decks go back to their own options.",
+                                    preset.name
+                                ),
+                                action: ConfirmAction::DeletePreset(preset.id),
+                            });
+                        }
+                    }
+                    Char('l') => {
+                        if let Some(preset) = self.selected_preset() {
+                            self.link_preset(Some(preset.id)).await;
+                        }
+                    }
+                    Char('o') => self.link_preset(None).await,
+                    Char('b') | KeyCode::Esc => {
+                        self.presets = None;
+                        self.pointer = ListState::default();
+                        self.current_screen = CurrentScreen::DECKS;
+                    }
+                    _ => {}
+                },
+                CurrentScreen::SIMULATION => match &key.code {
+                    Char('q') => self.should_quit = true,
+                    Char('b') | KeyCode::Esc => {
+                        self.simulation = None;
+                        self.current_screen = CurrentScreen::DECKS;
+                    }
+                    _ => {}
+                },
                 CurrentScreen::CONFIRM(popup) => match &key.code {
                     KeyCode::Char('y') => match popup.action {
-                        ConfirmAction::DeleteCard(card_id) => {
-                            Card::delete_from_db(&self.db_pool, card_id)
+                        ConfirmAction::DeleteCards(ref card_ids) => {
+                            let card_ids = card_ids.clone();
+                            self.delete_cards(&card_ids)
                                 .await
-                                .expect("failed to delete card from db");
-                            if let Some(deck) = &mut self.deck {
-                                deck.load_cards(&self.db_pool)
-                                    .await
-                                    .expect("failed to reload deck");
-                            };
+                                .expect("failed to delete cards from db");
+                            self.selected_cards.clear();
+                            self.reload_cards().await;
                             self.alert = Some(AlertPopup::new(
                                 std::time::Duration::new(5, 0),
-                                "Deleted card from database".to_string(),
+                                "Moved card(s) to trash; [u] to undo".to_string(),
                                 AlertPriority::Green,
                             ));
                             self.current_screen = CurrentScreen::CARDS;
                         }
                         ConfirmAction::DeleteDeck(deck_id) => {
-                            if let Some(deckset) = &mut self.deckset {
-                                deckset
-                                    .delete_deck_with_cards(&self.db_pool, deck_id)
-                                    .await
-                                    .expect("Failed to delete deck from db");
-                                self.alert = Some(AlertPopup::new(
-                                    std::time::Duration::new(5, 0),
-                                    "Deleted deck from database".to_string(),
-                                    AlertPriority::Green,
-                                ));
-                            }
+                            self.delete_deck(deck_id)
+                                .await
+                                .expect("Failed to delete deck from db");
+                            self.alert = Some(AlertPopup::new(
+                                std::time::Duration::new(5, 0),
+                                "Moved deck to trash; [u] to undo".to_string(),
+                                AlertPriority::Green,
+                            ));
                             self.current_screen = CurrentScreen::DECKS;
                         }
+                        ConfirmAction::PurgeTrash(ref item) => {
+                            let item = item.clone();
+                            self.current_screen = CurrentScreen::TRASH;
+                            match item.purge(&self.db_pool).await {
+                                Ok(_) => {
+                                    self.alert = Some(AlertPopup::new(
+                                        std::time::Duration::new(5, 0),
+                                        "Permanently deleted".to_string(),
+                                        AlertPriority::Green,
+                                    ));
+                                }
+                                Err(e) => {
+                                    tracing::error!("failed to purge from trash {}", e);
+                                    self.alert = Some(AlertPopup::new(
+                                        std::time::Duration::new(5, 0),
+                                        "Error: Failed to purge!".to_string(),
+                                        AlertPriority::Red,
+                                    ));
+                                }
+                            }
+                            self.reload_trash().await;
+                        }
+                        ConfirmAction::DeletePreset(id) => {
+                            self.current_screen = CurrentScreen::PRESETS;
+                            match Preset::delete(&self.db_pool, id).await {
+                                Ok(_) => {
+                                    self.alert = Some(AlertPopup::new(
+                                        std::time::Duration::new(5, 0),
+                                        "Deleted preset".to_string(),
+                                        AlertPriority::Green,
+                                    ));
+                                }
+                                Err(e) => {
+                                    tracing::error!("failed to delete preset {}", e);
+                                    self.alert = Some(AlertPopup::new(
+                                        std::time::Duration::new(5, 0),
+                                        "Error: Failed to delete preset!".to_string(),
+                                        AlertPriority::Red,
+                                    ));
+                                }
+                            }
+                            self.pointer = ListState::default();
+                            self.reload_presets().await;
+                        }
                     },
                     KeyCode::Char('n') | KeyCode::Esc => {
                         self.current_screen = match popup.action {
-                            ConfirmAction::DeleteCard(_) => CurrentScreen::CARDS,
+                            ConfirmAction::DeleteCards(_) => CurrentScreen::CARDS,
                             ConfirmAction::DeleteDeck(_) => CurrentScreen::DECKS,
+                            ConfirmAction::PurgeTrash(_) => CurrentScreen::TRASH,
+                            ConfirmAction::DeletePreset(_) => CurrentScreen::PRESETS,
                         };
                     }
                     _ => {}
@@ -728,6 +2619,10 @@ impl<'a> App<'a> {
         // Event handler
         let mut events = event_handler::EventHandler::default();
 
+        if let Err(e) = Preset::seed_from_config(&self.db_pool, &self.settings.presets).await {
+            tracing::error!("failed to store presets {}", e);
+        }
+
         match self.fetch_decks().await {
             Ok(_) => {}
             Err(e) => {
@@ -736,6 +2631,22 @@ impl<'a> App<'a> {
             }
         };
 
+        if let Err(e) =
+            TrashItem::purge_expired(&self.db_pool, self.settings.trash_retention_days).await
+        {
+            tracing::error!("failed to purge expired trash {}", e);
+        }
+
+        self.refresh_activity().await;
+
+        match NoteType::sync_from_config(&self.db_pool, &self.settings.note_types).await {
+            Ok(_) => match NoteType::load_all(&self.db_pool).await {
+                Ok(note_types) => self.note_types = note_types,
+                Err(e) => tracing::error!("failed to load note types {}", e),
+            },
+            Err(e) => tracing::error!("failed to store note types {}", e),
+        }
+
         while !self.should_quit {
             // Poll events
             let event = events.next().await?;
@@ -0,0 +1,77 @@
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Style};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+/// One of the themes bundled with syntect
+const THEME: &str = "base16-ocean.dark";
+
+/// The grammars and theme are bundled into the binary, so highlighting works offline.
+/// Loading them takes a moment, so it is done once, on first use.
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    &THEME_SET.get_or_init(ThemeSet::load_defaults).themes[THEME]
+}
+
+fn color(color: syntect::highlighting::Color) -> Color {
+    Color::Rgb(color.r, color.g, color.b)
+}
+
+/// Background of highlighted code
+pub fn background() -> Color {
+    theme().settings.background.map(color).unwrap_or(Color::Black)
+}
+
+/// Highlights `code` as `language` (a name or file extension, e.g. `rust` or `py`),
+/// one list of styled pieces per line; `None` if the language isn't known
+pub fn highlight(code: &str, language: &str) -> Option<Vec<Vec<(Style, String)>>> {
+    let syntaxes = syntaxes();
+    let syntax = syntaxes.find_syntax_by_token(language.trim())?;
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(code) {
+        let pieces = highlighter.highlight_line(line, syntaxes).ok()?;
+        lines.push(
+            pieces
+                .into_iter()
+                .map(|(style, text)| {
+                    let text = text.trim_end_matches(['\n', '\r']).to_string();
+                    (Style::default().fg(color(style.foreground)), text)
+                })
+                .filter(|(_, text)| !text.is_empty())
+                .collect(),
+        );
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_known_language() {
+        let lines = highlight("fn main() {\n    let x = 1;\n}\n", "rust").unwrap();
+        assert_eq!(3, lines.len());
+        let text: String = lines[1].iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!("    let x = 1;", text);
+        // keywords and numbers are coloured differently
+        let styles: Vec<Style> = lines[1].iter().map(|(style, _)| *style).collect();
+        assert!(styles.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        assert!(highlight("whatever", "not-a-language").is_none());
+    }
+}
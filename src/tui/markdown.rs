@@ -0,0 +1,451 @@
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+use super::highlight;
+use crate::domain::latex;
+
+/// How fenced code blocks are laid out. Code isn't wrapped: lines are cut to `width`
+/// columns, starting `scroll` columns in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CodeView {
+    pub line_numbers: bool,
+    pub scroll: usize,
+    pub width: Option<usize>,
+}
+
+/// Renders card text written in CommonMark (plus tables, strikethrough and `$` maths) as styled `Text`.
+/// Text without any markdown is returned as is, line breaks and all.
+pub fn render(text: &str) -> Text<'static> {
+    render_with(text, CodeView::default())
+}
+
+/// `render`, with code blocks laid out as `code_view` says
+pub fn render_with(text: &str, code_view: CodeView) -> Text<'static> {
+    if !is_markdown(text) {
+        return Text::from(text.to_string());
+    }
+    let mut renderer = Renderer {
+        code_view,
+        ..Renderer::default()
+    };
+    for event in Parser::new_ext(text, options()) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | Options::ENABLE_MATH
+}
+
+/// Whether parsing `text` finds anything besides plain paragraphs
+fn is_markdown(text: &str) -> bool {
+    Parser::new_ext(text, options()).any(|event| {
+        !matches!(
+            event,
+            Event::Start(Tag::Paragraph) | Event::End(TagEnd::Paragraph) | Event::Text(_) | Event::SoftBreak
+        )
+    })
+}
+
+fn code_style() -> Style {
+    Style::default().fg(Color::LightYellow).bg(Color::DarkGray)
+}
+
+fn math_style() -> Style {
+    Style::default().fg(Color::LightMagenta)
+}
+
+fn heading_style(level: HeadingLevel) -> Style {
+    let style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    match level {
+        HeadingLevel::H1 => style.add_modifier(Modifier::UNDERLINED),
+        _ => style,
+    }
+}
+
+#[derive(Debug, Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,    // the line being built
+    styles: Vec<Style>,           // open inline styles, innermost last
+    lists: Vec<Option<u64>>,      // next number of each open list; None for bullets
+    item_marker: Option<String>,  // starts the first line of a list item
+    quote_depth: usize,
+    code_block: Option<String>,   // text of the open code block
+    language: String,             // of the open code block
+    code_view: CodeView,
+    table: Option<Vec<Vec<String>>>,
+    cell: String,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some(code) = &mut self.code_block {
+                    code.push_str(&text);
+                } else if self.table.is_some() {
+                    self.cell.push_str(&text);
+                } else {
+                    self.push_text(&text, self.style());
+                }
+            }
+            Event::Code(code) => match self.table {
+                Some(_) => self.cell.push_str(&code),
+                None => self.push_span(Span::styled(code.to_string(), code_style())),
+            },
+            Event::InlineMath(source) => {
+                let math = latex::render_math(&source).unwrap_or_else(|| format!("${}$", source));
+                match self.table {
+                    Some(_) => self.cell.push_str(&math),
+                    None => self.push_span(Span::styled(math, self.style().patch(math_style()))),
+                }
+            }
+            Event::DisplayMath(source) => {
+                let math = latex::render_math(&source).unwrap_or_else(|| format!("$${}$$", source));
+                self.flush_line();
+                self.push_text(&math, self.style().patch(math_style()));
+                self.flush_line();
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.push_text(&html, self.style()),
+            Event::SoftBreak | Event::HardBreak => match self.table {
+                Some(_) => self.cell.push(' '),
+                None => self.flush_line(),
+            },
+            Event::Rule => {
+                self.blank_line();
+                self.push_span(Span::styled("─".repeat(20), Style::default().fg(Color::DarkGray)));
+                self.flush_line();
+            }
+            Event::TaskListMarker(done) => {
+                self.push_span(Span::raw(if done { "[x] " } else { "[ ] " }));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                if self.lists.is_empty() {
+                    self.blank_line();
+                } else {
+                    self.flush_line();
+                }
+            }
+            Tag::Heading { level, .. } => {
+                self.blank_line();
+                self.styles.push(heading_style(level));
+            }
+            Tag::BlockQuote(_) => {
+                self.blank_line();
+                self.quote_depth += 1;
+                self.styles.push(Style::default().add_modifier(Modifier::ITALIC));
+            }
+            Tag::CodeBlock(kind) => {
+                self.blank_line();
+                self.language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or_default().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                match self.lists.is_empty() {
+                    true => self.blank_line(),
+                    false => self.flush_line(),
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.item_marker = Some(format!("{}{}", indent, marker));
+            }
+            Tag::Emphasis => self.styles.push(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.styles.push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.styles.push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { .. } => self
+                .styles
+                .push(Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED)),
+            Tag::Table(_) => {
+                self.blank_line();
+                self.table = Some(Vec::new());
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(rows) = &mut self.table {
+                    rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => self.cell.clear(),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item => self.flush_line(),
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.flush_line();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_line();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.styles.pop();
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code_block.take().unwrap_or_default();
+                let language = std::mem::take(&mut self.language);
+                self.push_code(code.trim_end_matches('\n'), &language);
+            }
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => {
+                self.styles.pop();
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.cell);
+                if let Some(row) = self.table.as_mut().and_then(|rows| rows.last_mut()) {
+                    row.push(cell.trim().to_string());
+                }
+            }
+            TagEnd::Table => {
+                let rows = self.table.take().unwrap_or_default();
+                self.push_table(rows);
+            }
+            _ => {}
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles.iter().fold(Style::default(), |style, inner| style.patch(*inner))
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush_line();
+            }
+            if !line.is_empty() {
+                self.push_span(Span::styled(line.to_string(), style));
+            }
+        }
+    }
+
+    /// Adds `span` to the current line, starting it with the quote and list prefix if it is new
+    fn push_span(&mut self, span: Span<'static>) {
+        if self.spans.is_empty() {
+            if self.quote_depth > 0 {
+                self.spans.push(Span::styled(
+                    "│ ".repeat(self.quote_depth),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            match self.item_marker.take() {
+                Some(marker) => self.spans.push(Span::raw(marker)),
+                None if !self.lists.is_empty() => self.spans.push(Span::raw("  ".repeat(self.lists.len()))),
+                None => {}
+            }
+        }
+        self.spans.push(span);
+    }
+
+    fn flush_line(&mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    /// Separates a new block from the one before it
+    fn blank_line(&mut self) {
+        self.flush_line();
+        if self.lines.last().is_some_and(|line| !line.spans.is_empty()) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    /// Adds a code block, highlighted if its language is known, with every line cut to the
+    /// same visible columns so the block scrolls as a whole
+    fn push_code(&mut self, code: &str, language: &str) {
+        let (lines, background) = match highlight::highlight(code, language) {
+            Some(lines) if !language.is_empty() => (lines, highlight::background()),
+            _ => (
+                code.split('\n').map(|line| vec![(code_style(), line.to_string())]).collect(),
+                Color::DarkGray,
+            ),
+        };
+        let number_width = lines.len().to_string().len();
+        let gutter = match self.code_view.line_numbers {
+            true => number_width + 3,
+            false => 0,
+        };
+        let longest = lines
+            .iter()
+            .map(|line| line.iter().map(|(_, text)| text.chars().count()).sum::<usize>())
+            .max()
+            .unwrap_or(0);
+        let visible = self
+            .code_view
+            .width
+            .map(|width| width.saturating_sub(gutter + 2))
+            .unwrap_or(longest)
+            .min(longest.saturating_sub(self.code_view.scroll));
+        for (i, line) in lines.into_iter().enumerate() {
+            if self.code_view.line_numbers {
+                self.push_span(Span::styled(
+                    format!("{:>width$} │ ", i + 1, width = number_width),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            let mut pieces = clip(line, self.code_view.scroll, visible);
+            let shown: usize = pieces.iter().map(|(_, text)| text.chars().count()).sum();
+            pieces.push((Style::default(), " ".repeat(visible - shown)));
+            self.push_span(Span::styled(" ", Style::default().bg(background)));
+            for (style, text) in pieces {
+                self.push_span(Span::styled(text, style.bg(background)));
+            }
+            self.push_span(Span::styled(" ", Style::default().bg(background)));
+            self.flush_line();
+        }
+    }
+
+    fn push_table(&mut self, rows: Vec<Vec<String>>) {
+        let n_columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..n_columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let border = Style::default().fg(Color::DarkGray);
+        for (i, row) in rows.iter().enumerate() {
+            let style = match i {
+                0 => Style::default().add_modifier(Modifier::BOLD),
+                _ => Style::default(),
+            };
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    self.push_span(Span::styled(" │ ", border));
+                }
+                let cell = row.get(column).map(String::as_str).unwrap_or_default();
+                self.push_span(Span::styled(format!("{:<width$}", cell, width = width), style));
+            }
+            self.flush_line();
+            if i == 0 {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.push_span(Span::styled(rule.join("─┼─"), border));
+                self.flush_line();
+            }
+        }
+    }
+
+    fn finish(mut self) -> Text<'static> {
+        self.flush_line();
+        Text::from(self.lines)
+    }
+}
+
+/// The `len` columns of `line` starting at column `start`
+fn clip(line: Vec<(Style, String)>, start: usize, len: usize) -> Vec<(Style, String)> {
+    let mut clipped = Vec::new();
+    let mut column = 0usize;
+    for (style, text) in line {
+        let n = text.chars().count();
+        let from = start.max(column) - column;
+        let to = (start + len).min(column + n).saturating_sub(column);
+        if from < to {
+            clipped.push((style, text.chars().skip(from).take(to - from).collect()));
+        }
+        column += n;
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `text` without styling, one string per line
+    fn plain_lines(text: &Text) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_text_is_unchanged() {
+        let text = "line one\nline two\n\n  indented *not emphasis";
+        assert_eq!(Text::from(text.to_string()), render(text));
+    }
+
+    #[test]
+    fn test_inline_styles_and_lists() {
+        let text = render("**bold** and `code`\n\n- one\n- two\n\n1. first\n2. second");
+        assert_eq!(
+            vec!["bold and code", "", "• one", "• two", "", "1. first", "2. second"],
+            plain_lines(&text)
+        );
+        assert!(text.lines[0].spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(code_style(), text.lines[0].spans[2].style);
+    }
+
+    #[test]
+    fn test_code_block_scrolls_instead_of_wrapping() {
+        let text = "```\nlet long_name = 1;\nx\n```";
+        let view = CodeView {
+            line_numbers: true,
+            scroll: 4,
+            width: Some(12),
+        };
+        assert_eq!(vec!["1 │  long_n ", "2 │         "], plain_lines(&render_with(text, view)));
+        assert_eq!(
+            vec![" let long_name = 1; ", " x                  "],
+            plain_lines(&render(text))
+        );
+    }
+
+    #[test]
+    fn test_clip() {
+        let line = vec![(Style::default(), "abc".to_string()), (code_style(), "defg".to_string())];
+        assert_eq!(
+            vec![(Style::default(), "c".to_string()), (code_style(), "de".to_string())],
+            clip(line, 2, 3)
+        );
+    }
+
+    #[test]
+    fn test_maths_become_unicode() {
+        let text = render(r"Area: $\pi r^2$ and $x_{\weird}$");
+        assert_eq!(vec![r"Area: π r² and $x_{\weird}$"], plain_lines(&text));
+        assert_eq!(math_style(), text.lines[0].spans[1].style);
+    }
+
+    #[test]
+    fn test_table_and_quote() {
+        let text = render("| a | bb |\n|---|----|\n| ccc | d |\n\n> quoted");
+        assert_eq!(
+            vec!["a   │ bb", "────┼───", "ccc │ d ", "", "│ quoted"],
+            plain_lines(&text)
+        );
+    }
+}
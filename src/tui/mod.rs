@@ -1,5 +1,7 @@
 pub mod event_handler;
 pub mod app;
 pub mod utils;
+pub mod markdown;
+pub mod highlight;
 pub mod screens;
 pub mod panes;
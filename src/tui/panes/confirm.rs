@@ -1,6 +1,8 @@
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 use uuid::Uuid;
 
+use crate::domain::trash::TrashItem;
+
 use crate::tui::utils::create_centred_rect_by_percent;
 
 
@@ -8,8 +10,10 @@ use crate::tui::utils::create_centred_rect_by_percent;
 // `ConfirmAction` contains an action type and the relevant
 // info required to perform that action (e.g., a deck Id)
 pub enum ConfirmAction {
-    DeleteCard(Uuid),
+    DeleteCards(Vec<Uuid>),
     DeleteDeck(Uuid),
+    PurgeTrash(TrashItem),
+    DeletePreset(Uuid),
 }
 
 #[derive(Debug)]
@@ -0,0 +1,97 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+use crate::domain::activity::Activity;
+
+/// Weeks shown when there is room for a whole year
+const MAX_WEEKS: u16 = 53;
+/// Columns taken by the weekday labels
+const LABEL_WIDTH: u16 = 4;
+/// From no reviews to the busiest days
+const SHADES: [Color; 5] = [
+    Color::DarkGray,
+    Color::Rgb(14, 68, 41),
+    Color::Rgb(0, 109, 50),
+    Color::Rgb(38, 166, 65),
+    Color::Rgb(57, 211, 83),
+];
+
+/// Reviews per day over the last year, one column per week (Monday at the top), with
+/// months labelled above; as many weeks are shown as fit
+pub struct Heatmap<'a> {
+    pub activity: &'a Activity,
+}
+
+/// How dark a day with `reviews` is, relative to the busiest day's `max`
+fn shade(reviews: u64, max: u64) -> Color {
+    match reviews {
+        0 => SHADES[0],
+        n => SHADES[(1 + (n * 4).saturating_sub(1) / max.max(1)).min(4) as usize],
+    }
+}
+
+impl Widget for Heatmap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 8 || area.width <= LABEL_WIDTH {
+            return;
+        }
+        let weeks = ((area.width - LABEL_WIDTH) / 2).min(MAX_WEEKS);
+        let today = self.activity.today;
+        let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let first_monday = this_monday - Duration::weeks(weeks as i64 - 1);
+        let max = self
+            .activity
+            .days
+            .range(first_monday..)
+            .map(|(_, day)| day.reviews)
+            .max()
+            .unwrap_or(0);
+
+        let label_style = Style::default().fg(Color::Gray);
+        for (row, label) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+            buf.set_string(area.x, area.y + 1 + row, label, label_style);
+        }
+        let mut last_month = None;
+        for week in 0..weeks {
+            let x = area.x + LABEL_WIDTH + 2 * week;
+            let monday = first_monday + Duration::weeks(week as i64);
+            // Label a month above the first week starting in it, if the name fits
+            if last_month != Some(monday.month()) && x + 3 <= area.x + area.width {
+                if last_month.is_some() || monday.day() <= 7 {
+                    buf.set_string(x, area.y, month_name(monday), label_style);
+                }
+                last_month = Some(monday.month());
+            }
+            for weekday in 0..7u16 {
+                let day = monday + Duration::days(weekday as i64);
+                if day > today {
+                    break;
+                }
+                let reviews = self.activity.on(day).reviews;
+                buf.set_string(x, area.y + 1 + weekday, "■", Style::default().fg(shade(reviews, max)));
+            }
+        }
+    }
+}
+
+fn month_name(day: NaiveDate) -> String {
+    day.format("%b").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shades() {
+        assert_eq!(SHADES[0], shade(0, 0));
+        assert_eq!(SHADES[1], shade(1, 40));
+        assert_eq!(SHADES[2], shade(11, 40));
+        assert_eq!(SHADES[4], shade(40, 40));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod statusbar;
 pub mod alertpopup;
 pub mod confirm;
+pub mod heatmap;
+pub mod prompt;
 pub mod text_field;
\ No newline at end of file
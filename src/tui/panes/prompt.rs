@@ -0,0 +1,69 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+use uuid::Uuid;
+
+use crate::tui::utils::create_centred_rect_by_percent;
+
+#[derive(Debug, Clone)]
+// `PromptAction` describes what to do with the submitted text
+pub enum PromptAction {
+    FilterCards,
+    EditTags(Vec<Uuid>),
+    MoveCards(Vec<Uuid>),
+    Search { from_deck: bool },
+    Simulate { deck: Option<String> },
+    ExamDate { deck_id: Uuid, name: String },
+    DeckLimits { deck_id: Uuid, name: String },
+    NewPreset,
+    EditPreset(Uuid),
+}
+
+/// Single-line text input shown in a centred popup
+#[derive(Debug, Clone)]
+pub struct InputPrompt {
+    pub title: String,
+    pub hint: String,
+    pub text: String,
+    pub action: PromptAction,
+}
+
+impl InputPrompt {
+    pub fn new(title: &str, hint: &str, text: &str, action: PromptAction) -> Self {
+        Self {
+            title: title.to_string(),
+            hint: hint.to_string(),
+            text: text.to_string(),
+            action,
+        }
+    }
+
+    pub fn push_char(&mut self, ch: char) {
+        self.text.push(ch);
+    }
+
+    pub fn pop_char(&mut self) {
+        self.text.pop();
+    }
+}
+
+impl Widget for &InputPrompt {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized {
+            let popup_area = create_centred_rect_by_percent(50u16, 20u16, area);
+
+            let lines = vec![
+                Line::from(format!("> {}", self.text)),
+                Line::from(""),
+                Line::from(Span::styled(self.hint.clone(), Style::default().fg(Color::DarkGray))),
+            ];
+
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title(self.title.clone()))
+                .wrap(Wrap { trim: false })
+                .render(popup_area, buf);
+    }
+}
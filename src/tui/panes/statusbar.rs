@@ -5,12 +5,13 @@ use ratatui::{
     widgets::{Block, Paragraph, Widget},
 };
 
-use crate::tui::app::Mode;
+use crate::{domain::activity::DailyProgress, tui::app::Mode};
 
 // TODO: Am I really instantatiating a whole new instance of a struct for *each frame*?
 #[derive(Default, Debug)]
 pub struct StatusBar {
     pub mode: Mode,
+    pub progress: Option<DailyProgress>, // streak and today's reviews against the daily goal
 }
 
 impl Widget for &StatusBar {
@@ -31,11 +32,12 @@ impl Widget for &StatusBar {
             .block(Block::default())
             .render(layout[0], buf);
 
-        Paragraph::new(Span::styled(
-            "    LINE 0X/XX | filename.rs  ",
-            Style::new().bg(Color::LightBlue),
-        ))
-        .block(Block::default())
-        .render(layout[1], buf);
+        if let Some(progress) = &self.progress {
+            let color = if progress.is_met() { Color::Green } else { Color::Yellow };
+            Paragraph::new(Span::styled(format!("{} ", progress), Style::new().fg(color)))
+                .block(Block::default())
+                .right_aligned()
+                .render(layout[1], buf);
+        }
     }
 }
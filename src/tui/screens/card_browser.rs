@@ -0,0 +1,329 @@
+use std::{cmp::Ordering, collections::{HashMap, HashSet}};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Cell, Paragraph, Row, StatefulWidget, Table, TableState, Widget, Wrap},
+};
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    domain::card::{Card, REVERSE},
+    tui::{
+        markdown::{self, CodeView},
+        utils::highlight_matches,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardColumn {
+    Front,
+    Back,
+    Deck,
+    Created,
+    Modified,
+    Due,
+    Interval,
+    Ease,
+    Lapses,
+}
+
+impl CardColumn {
+    /// Every column, in display order; `[1]`-`[9]` toggle them in this order
+    pub const ALL: [CardColumn; 9] = [
+        CardColumn::Front,
+        CardColumn::Back,
+        CardColumn::Deck,
+        CardColumn::Created,
+        CardColumn::Modified,
+        CardColumn::Due,
+        CardColumn::Interval,
+        CardColumn::Ease,
+        CardColumn::Lapses,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            CardColumn::Front => "FRONT",
+            CardColumn::Back => "BACK",
+            CardColumn::Deck => "DECK",
+            CardColumn::Created => "CREATED",
+            CardColumn::Modified => "MODIFIED",
+            CardColumn::Due => "DUE",
+            CardColumn::Interval => "IVL",
+            CardColumn::Ease => "EASE",
+            CardColumn::Lapses => "LAPSES",
+        }
+    }
+
+    fn width(&self) -> Constraint {
+        match self {
+            CardColumn::Front | CardColumn::Back => Constraint::Fill(1),
+            CardColumn::Deck => Constraint::Length(14),
+            CardColumn::Created | CardColumn::Modified | CardColumn::Due => Constraint::Length(10),
+            CardColumn::Interval | CardColumn::Ease | CardColumn::Lapses => Constraint::Length(6),
+        }
+    }
+
+    pub fn cell_text(&self, card: &Card, deck_names: &HashMap<Uuid, String>) -> String {
+        let date = |d: Option<DateTime<Utc>>| {
+            d.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()
+        };
+        match self {
+            CardColumn::Front => card.front_text.clone().unwrap_or_default().replace('\n', " "),
+            CardColumn::Back => card.back_text.clone().unwrap_or_default().replace('\n', " "),
+            CardColumn::Deck => deck_name(card, deck_names),
+            CardColumn::Created => date(card.created),
+            CardColumn::Modified => date(card.modified),
+            CardColumn::Due => match card.due {
+                Some(_) => date(card.due),
+                None => "new".to_string(),
+            },
+            CardColumn::Interval => format!("{}d", card.interval_days),
+            CardColumn::Ease => format!("{:.0}%", card.ease * 100.0),
+            CardColumn::Lapses => card.lapses.to_string(),
+        }
+    }
+
+    fn compare(&self, a: &Card, b: &Card, deck_names: &HashMap<Uuid, String>) -> Ordering {
+        match self {
+            CardColumn::Front => a.front_text.cmp(&b.front_text),
+            CardColumn::Back => a.back_text.cmp(&b.back_text),
+            CardColumn::Deck => deck_name(a, deck_names).cmp(&deck_name(b, deck_names)),
+            CardColumn::Created => a.created.cmp(&b.created),
+            CardColumn::Modified => a.modified.cmp(&b.modified),
+            CardColumn::Due => a.due.cmp(&b.due),
+            CardColumn::Interval => a.interval_days.cmp(&b.interval_days),
+            CardColumn::Ease => a.ease.total_cmp(&b.ease),
+            CardColumn::Lapses => a.lapses.cmp(&b.lapses),
+        }
+    }
+}
+
+fn deck_name(card: &Card, deck_names: &HashMap<Uuid, String>) -> String {
+    card.deck_id
+        .and_then(|id| deck_names.get(&id).cloned())
+        .unwrap_or_default()
+}
+
+/// Table view of cards with sortable, toggleable columns and a detail pane
+#[derive(Debug, Clone)]
+pub struct CardBrowser {
+    pub visible: Vec<CardColumn>,
+    pub sort_column: CardColumn,
+    pub ascending: bool,
+    pub show_detail: bool,
+    pub state: TableState,
+}
+
+impl Default for CardBrowser {
+    fn default() -> Self {
+        Self {
+            visible: vec![
+                CardColumn::Front,
+                CardColumn::Back,
+                CardColumn::Deck,
+                CardColumn::Due,
+                CardColumn::Interval,
+                CardColumn::Ease,
+                CardColumn::Lapses,
+            ],
+            sort_column: CardColumn::Created,
+            ascending: true,
+            show_detail: false,
+            state: TableState::default(),
+        }
+    }
+}
+
+impl CardBrowser {
+    pub fn sort(&self, cards: &mut [Card], deck_names: &HashMap<Uuid, String>) {
+        cards.sort_by(|a, b| {
+            let ordering = self.sort_column.compare(a, b, deck_names);
+            if self.ascending { ordering } else { ordering.reverse() }
+        });
+    }
+
+    /// Sorts by the next column (in `CardColumn::ALL` order)
+    pub fn cycle_sort(&mut self) {
+        let i = CardColumn::ALL
+            .iter()
+            .position(|c| *c == self.sort_column)
+            .unwrap_or(0);
+        self.sort_column = CardColumn::ALL[(i + 1) % CardColumn::ALL.len()];
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.ascending = !self.ascending;
+    }
+
+    /// Shows or hides the `n`th column (1-based), always keeping one visible
+    pub fn toggle_column(&mut self, n: usize) {
+        let column = match n.checked_sub(1).and_then(|i| CardColumn::ALL.get(i)) {
+            Some(column) => *column,
+            None => return,
+        };
+        if self.visible.contains(&column) {
+            if self.visible.len() > 1 {
+                self.visible.retain(|c| *c != column);
+            }
+        } else {
+            self.visible = CardColumn::ALL
+                .iter()
+                .filter(|c| **c == column || self.visible.contains(c))
+                .cloned()
+                .collect();
+        }
+    }
+
+    pub fn toggle_detail(&mut self) {
+        self.show_detail = !self.show_detail;
+    }
+
+    /// Renders `rows`, given as (index into `cards`, matched chars of the front text)
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_table(
+        &mut self,
+        cards: &[Card],
+        rows: &[(usize, Vec<usize>)],
+        deck_names: &HashMap<Uuid, String>,
+        selected_cards: &HashSet<Uuid>,
+        block: Block,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let header = Row::new(
+            std::iter::once(Cell::from("")).chain(self.visible.iter().map(|column| {
+                let arrow = match (*column == self.sort_column, self.ascending) {
+                    (true, true) => " ▲",
+                    (true, false) => " ▼",
+                    _ => "",
+                };
+                Cell::from(format!("{}{}", column.title(), arrow))
+            })),
+        )
+        .style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan));
+
+        let table_rows: Vec<Row> = rows
+            .iter()
+            .filter_map(|(index, positions)| cards.get(*index).map(|card| (card, positions)))
+            .map(|(card, positions)| {
+                let is_selected = selected_cards.contains(&card.id);
+                let marker = Cell::from(if is_selected { "*" } else { " " });
+                let cells = self.visible.iter().map(|column| {
+                    let text = column.cell_text(card, deck_names);
+                    if *column == CardColumn::Front && !positions.is_empty() {
+                        Cell::from(highlight_matches(&text, positions))
+                    } else {
+                        Cell::from(text)
+                    }
+                });
+                let row = Row::new(std::iter::once(marker).chain(cells));
+                if is_selected { row.style(Style::default().fg(Color::Yellow)) } else { row }
+            })
+            .collect();
+
+        let widths: Vec<Constraint> = std::iter::once(Constraint::Length(1))
+            .chain(self.visible.iter().map(|column| column.width()))
+            .collect();
+
+        let table = Table::new(table_rows, widths)
+            .header(header)
+            .block(block)
+            .highlight_style(Style::new().light_blue().italic())
+            .highlight_symbol(">>");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+
+    /// Renders every field of `card`
+    pub fn render_detail(
+        card: &Card,
+        deck_names: &HashMap<Uuid, String>,
+        code_view: CodeView,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let code_view = CodeView {
+            width: Some(area.width.saturating_sub(2) as usize),
+            ..code_view
+        };
+        let label = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+        let mut text = Text::default();
+        text.push_line(Line::styled("FRONT", label));
+        text.extend(markdown::render_with(card.front_text.as_deref().unwrap_or_default(), code_view));
+        text.push_line(Line::from(""));
+        text.push_line(Line::styled("BACK", label));
+        text.extend(markdown::render_with(card.back_text.as_deref().unwrap_or_default(), code_view));
+        text.push_line(Line::from(""));
+        for (name, field) in [("HINT", &card.hint), ("NOTES", &card.notes), ("SOURCE", &card.source)] {
+            if let Some(value) = field.as_deref().filter(|value| !value.trim().is_empty()) {
+                text.push_line(Line::styled(name, label));
+                text.extend(markdown::render_with(value, code_view));
+                text.push_line(Line::from(""));
+            }
+        }
+        for column in CardColumn::ALL.iter().skip(2) {
+            text.push_line(Line::from(vec![
+                Span::styled(format!("{:<10}", column.title()), label),
+                Span::raw(column.cell_text(card, deck_names)),
+            ]));
+        }
+        text.push_line(Line::from(vec![
+            Span::styled(format!("{:<10}", "TAGS"), label),
+            Span::raw(card.tags.join(", ")),
+        ]));
+        if card.suspended {
+            text.push_line(Line::styled("SUSPENDED", Style::default().fg(Color::Red)));
+        }
+        if card.ordinal == REVERSE {
+            text.push_line(Line::styled("REVERSE CARD", Style::default().fg(Color::Cyan)));
+        }
+
+        Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title("CARD"))
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_column_keeps_order_and_one_visible() {
+        let mut browser = CardBrowser {
+            visible: vec![CardColumn::Front],
+            ..CardBrowser::default()
+        };
+        browser.toggle_column(1);
+        assert_eq!(vec![CardColumn::Front], browser.visible);
+        browser.toggle_column(9);
+        browser.toggle_column(3);
+        assert_eq!(vec![CardColumn::Front, CardColumn::Deck, CardColumn::Lapses], browser.visible);
+        browser.toggle_column(1);
+        assert_eq!(vec![CardColumn::Deck, CardColumn::Lapses], browser.visible);
+    }
+
+    #[test]
+    fn test_sort_by_column() {
+        let cards: Vec<Card> = [3, 1, 2]
+            .iter()
+            .map(|lapses| Card { lapses: *lapses, ..Card::default() })
+            .collect();
+        let mut browser = CardBrowser {
+            sort_column: CardColumn::Lapses,
+            ..CardBrowser::default()
+        };
+        let mut sorted = cards.clone();
+        browser.sort(&mut sorted, &HashMap::new());
+        assert_eq!(vec![1, 2, 3], sorted.iter().map(|c| c.lapses).collect::<Vec<i32>>());
+        browser.reverse_sort();
+        browser.sort(&mut sorted, &HashMap::new());
+        assert_eq!(vec![3, 2, 1], sorted.iter().map(|c| c.lapses).collect::<Vec<i32>>());
+    }
+}
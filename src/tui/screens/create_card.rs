@@ -3,14 +3,19 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::Span,
-    widgets::{Block, Borders, Paragraph, Widget},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
 };
 
 use sqlx::PgPool;
 
 use crate::{
-    domain::card::Card,
-    tui::{app::Mode, panes::text_field::TextField, utils::create_centred_rect_by_percent},
+    domain::{card::Card, note::Note, note_type::NoteType, tag::Tag},
+    tui::{
+        app::Mode,
+        markdown::{self, CodeView},
+        panes::text_field::TextField,
+        utils::create_centred_rect_by_percent,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -20,6 +25,16 @@ pub struct CreateCard<'a> {
     pub state: CurrentlyEditing,
     pub front_text: TextField<'a>,
     pub back_text: TextField<'a>,
+    pub hint: TextField<'a>,
+    pub notes: TextField<'a>,
+    pub source: TextField<'a>,
+    pub tags: TextField<'a>,
+    pub note_types: Vec<NoteType>, // the types [t] cycles through
+    pub note_type: Option<NoteType>, // set for a typed note, whose fields replace front and back
+    pub fields: Vec<TextField<'a>>, // one per field of `note_type`
+    pub reverse: bool, // also keep a back -> front card for this card's note
+    pub preview: bool, // show the text rendered as markdown instead of the text fields
+    pub code_view: CodeView,
     // pub front_text: Rc<RefCell<TextField<'a>>>,
     // pub back_text: Rc<RefCell<TextField<'a>>>,
     pub cursor: (u16, u16),
@@ -31,6 +46,11 @@ pub enum CurrentlyEditing {
     #[default]
     FrontText,
     BackText,
+    Hint,
+    Notes,
+    Source,
+    Tags,
+    Field(usize), // of a typed note
     Saving,
 }
 
@@ -44,6 +64,16 @@ impl<'a> Default for CreateCard<'a> {
             // back_text: Rc::new(RefCell::new(TextField::default())),
             front_text: TextField::default(),
             back_text: TextField::default(),
+            hint: TextField::default(),
+            notes: TextField::default(),
+            source: TextField::default(),
+            tags: TextField::default(),
+            note_types: Vec::new(),
+            note_type: None,
+            fields: Vec::new(),
+            reverse: false,
+            preview: false,
+            code_view: CodeView::default(),
             state: CurrentlyEditing::default(),
             cursor: (0u16, 0u16),
             db_pool: None,
@@ -61,6 +91,16 @@ impl<'a> From<&Card> for CreateCard<'a> {
             state: CurrentlyEditing::default(),
             front_text: TextField::from(card.front_text.clone().unwrap_or("".to_string()).as_str()),
             back_text: TextField::from(card.back_text.clone().unwrap_or("".to_string()).as_str()),
+            hint: TextField::from(card.hint.as_deref().unwrap_or_default()),
+            notes: TextField::from(card.notes.as_deref().unwrap_or_default()),
+            source: TextField::from(card.source.as_deref().unwrap_or_default()),
+            tags: TextField::from(card.tags.join(", ").as_str()),
+            note_types: Vec::new(),
+            note_type: None,
+            fields: Vec::new(),
+            reverse: false,
+            preview: false,
+            code_view: CodeView::default(),
             // front_text: Rc::new(RefCell::new(TextField::from(card.front_text.clone().unwrap_or("".to_string()).as_str()))),
             // back_text: Rc::new(RefCell::new(TextField::from(card.back_text.clone().unwrap_or("".to_string()).as_str()))),
             cursor: (0u16, card.front_text.clone().unwrap_or("".to_string()).len() as u16),
@@ -75,11 +115,32 @@ impl<'a> Widget for &mut CreateCard<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let popup_area: Rect = create_centred_rect_by_percent(50u16, 50u16, area);
 
+        let options = match &self.note_type {
+            Some(note_type) => format!(
+                " [t] type: {} | [p] preview: {} ",
+                note_type.name,
+                if self.preview { "on" } else { "off" }
+            ),
+            None => format!(
+                " [t] type: basic | [r] reverse card: {} | [p] preview: {} | `{{{{c1::text}}}}` makes a cloze ",
+                if self.reverse { "on" } else { "off" },
+                if self.preview { "on" } else { "off" }
+            ),
+        };
+        let order = match &self.note_type {
+            Some(note_type) => note_type.fields.join(" > "),
+            None => "front > back".to_string(),
+        };
         let block = Block::default()
             .title(Span::styled(
                 "Add a card",
                 Style::default().fg(Color::Yellow).bg(Color::Black),
             ))
+            .title(Span::styled(options, Style::default().fg(Color::Cyan).bg(Color::Black)))
+            .title(Span::styled(
+                format!(" [tab] {} > hint > notes > source > tags ", order),
+                Style::default().fg(Color::DarkGray).bg(Color::Black),
+            ))
             .borders(Borders::ALL)
             .style(Style::default().bg(Color::Black));
 
@@ -87,7 +148,10 @@ impl<'a> Widget for &mut CreateCard<'a> {
             .direction(Direction::Vertical)
             .constraints(vec![
                 Constraint::Percentage(50u16),
-                Constraint::Percentage(50u16),
+                Constraint::Percentage(10u16),
+                Constraint::Percentage(20u16),
+                Constraint::Percentage(10u16),
+                Constraint::Percentage(10u16),
             ])
             .margin(2)
             .split(popup_area);
@@ -97,8 +161,43 @@ impl<'a> Widget for &mut CreateCard<'a> {
 
         //POPUP
         Paragraph::default().block(block).render(popup_area, buf);
-        self.front_text.render(text_fields[0], buf);
-        self.back_text.render(text_fields[1], buf);
+        let sides = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50u16), Constraint::Percentage(50u16)])
+            .split(text_fields[0]);
+        if self.preview {
+            let (front, back) = self.preview_text();
+            for (title, text, area) in [("FRONT", front, sides[0]), ("BACK", back, sides[1])] {
+                let code_view = CodeView {
+                    width: Some(area.width.saturating_sub(2) as usize),
+                    ..self.code_view
+                };
+                Paragraph::new(markdown::render_with(&text, code_view))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
+        } else if let Some(note_type) = &self.note_type {
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Ratio(1, self.fields.len().max(1) as u32); self.fields.len()])
+                .split(text_fields[0]);
+            for ((name, field), area) in note_type.fields.iter().zip(self.fields.iter_mut()).zip(areas.iter()) {
+                let [label, input] = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Min(1)])
+                    .areas(*area);
+                Paragraph::new(Span::styled(name.as_str(), Style::default().fg(Color::Cyan))).render(label, buf);
+                field.render(input, buf);
+            }
+        } else {
+            self.front_text.render(sides[0], buf);
+            self.back_text.render(sides[1], buf);
+        }
+        self.hint.render(text_fields[1], buf);
+        self.notes.render(text_fields[2], buf);
+        self.source.render(text_fields[3], buf);
+        self.tags.render(text_fields[4], buf);
         
         // let tmp_front_text = Rc::clone(&self.front_text);
         // let tmp_back_text = Rc::clone(&self.back_text);
@@ -132,6 +231,11 @@ impl<'a> CreateCard<'a> {
         match self.state {
             CurrentlyEditing::FrontText => Some(&mut self.front_text),
             CurrentlyEditing::BackText => Some(&mut self.back_text),
+            CurrentlyEditing::Hint => Some(&mut self.hint),
+            CurrentlyEditing::Notes => Some(&mut self.notes),
+            CurrentlyEditing::Source => Some(&mut self.source),
+            CurrentlyEditing::Tags => Some(&mut self.tags),
+            CurrentlyEditing::Field(index) => self.fields.get_mut(index),
             _ => None,
         }
     }
@@ -142,22 +246,142 @@ impl<'a> CreateCard<'a> {
         self.db_pool = Some(db_pool);
     }
 
-    pub async fn try_save(&mut self, db_pool: &PgPool) -> Result<(), sqlx::Error> {
-        // WARN: text will not be updated here
-        tracing::info!("SAVING: {:?}", self.card);
+    /// Copies the text fields into `card`
+    fn fill_card(&mut self) {
         // let front_text = Rc::clone(&self.front_text).borrow().to_string();
         // let back_text = Rc::clone(&self.back_text).borrow().to_string();
         let ft = self.front_text.to_string();
-        let bt = self.front_text.to_string();
+        let bt = self.back_text.to_string();
         self.card.front_text = Some(ft);
         self.card.back_text = Some(bt);
-        self.card.save(db_pool).await
+        let optional = |field: &TextField| Some(field.to_string()).filter(|text| !text.trim().is_empty());
+        self.card.hint = optional(&self.hint);
+        self.card.notes = optional(&self.notes);
+        self.card.source = optional(&self.source);
+        self.card.set_tags(Tag::parse_names(&self.tags.to_string()));
+    }
+
+    /// Saves the card and its note; `author` is recorded on revisions of sibling cards
+    pub async fn try_save(&mut self, db_pool: &PgPool, author: &str) -> Result<(), sqlx::Error> {
+        // WARN: text will not be updated here
+        tracing::info!("SAVING: {:?}", self.card);
+        self.fill_card();
+        match self.note_type.clone() {
+            Some(note_type) => {
+                let fields = self.field_values();
+                Note::save_typed(db_pool, &mut self.card, note_type, fields, author).await?
+            }
+            None => Note::save_with_card(db_pool, &mut self.card, Some(self.reverse), author).await?,
+        }
+        Tag::set_for_card(db_pool, self.card.id, &self.card.tags).await
+    }
+
+    pub fn toggle_reverse(&mut self) {
+        self.reverse = !self.reverse;
+    }
+
+    pub fn toggle_preview(&mut self) {
+        self.preview = !self.preview;
+        self.code_view.scroll = 0;
+    }
+
+    /// Scrolls code blocks in the preview `columns` to the right, or left if negative
+    pub fn scroll_code(&mut self, columns: isize) {
+        self.code_view.scroll = self.code_view.scroll.saturating_add_signed(columns);
+    }
+
+    /// Shows the form of `note_type`, filled in with `values` by field name; `None` shows
+    /// the plain front and back
+    pub fn set_note_type(&mut self, note_type: Option<NoteType>, values: &[(String, String)]) {
+        self.fields = match &note_type {
+            Some(note_type) => note_type
+                .fields
+                .iter()
+                .map(|name| {
+                    let value = values.iter().find(|(field, _)| field == name).map(|(_, value)| value.as_str());
+                    TextField::from(value.unwrap_or_default())
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        self.state = match note_type {
+            Some(_) => CurrentlyEditing::Field(0),
+            None => CurrentlyEditing::FrontText,
+        };
+        self.note_type = note_type;
+    }
+
+    /// Switches to the next note type, keeping the values of fields with the same name
+    pub fn cycle_note_type(&mut self) {
+        let next = match &self.note_type {
+            None => self.note_types.first(),
+            Some(current) => self
+                .note_types
+                .iter()
+                .position(|note_type| note_type.id == current.id)
+                .and_then(|index| self.note_types.get(index + 1)),
+        };
+        let values = self.field_values();
+        self.set_note_type(next.cloned(), &values);
+    }
+
+    /// The fields of a typed note, as (name, value)
+    pub fn field_values(&self) -> Vec<(String, String)> {
+        match &self.note_type {
+            Some(note_type) => note_type
+                .fields
+                .iter()
+                .cloned()
+                .zip(self.fields.iter().map(|field| field.to_string()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether saving would leave the note with any cards: a typed note needs a field
+    /// shown on the front of one of its templates
+    pub fn generates_cards(&self) -> bool {
+        self.note_type
+            .as_ref()
+            .is_none_or(|note_type| !note_type.wanted_ordinals(&self.field_values()).is_empty())
+    }
+
+    /// The front and back shown in the preview; a typed note shows the card being edited,
+    /// or its first card if that template would now be blank
+    fn preview_text(&self) -> (String, String) {
+        match &self.note_type {
+            Some(note_type) => {
+                let values = self.field_values();
+                let wanted = note_type.wanted_ordinals(&values);
+                let ordinal = match wanted.contains(&self.card.ordinal) {
+                    true => self.card.ordinal,
+                    false => wanted.first().copied().unwrap_or(self.card.ordinal),
+                };
+                note_type.render(ordinal, &values).unwrap_or_default()
+            }
+            None => (self.front_text.to_string(), self.back_text.to_string()),
+        }
     }
 
     pub fn toggle_field(&mut self) {
         tracing::info!("TOGGLING CREATE CARD FIELD");
+        if self.note_type.is_some() {
+            self.state = match self.state {
+                CurrentlyEditing::Field(index) if index + 1 < self.fields.len() => CurrentlyEditing::Field(index + 1),
+                CurrentlyEditing::Field(_) => CurrentlyEditing::Hint,
+                CurrentlyEditing::Hint => CurrentlyEditing::Notes,
+                CurrentlyEditing::Notes => CurrentlyEditing::Source,
+                CurrentlyEditing::Source => CurrentlyEditing::Tags,
+                _ => CurrentlyEditing::Field(0),
+            };
+            return;
+        }
         self.state = match self.state {
             CurrentlyEditing::FrontText => CurrentlyEditing::BackText,
+            CurrentlyEditing::BackText => CurrentlyEditing::Hint,
+            CurrentlyEditing::Hint => CurrentlyEditing::Notes,
+            CurrentlyEditing::Notes => CurrentlyEditing::Source,
+            CurrentlyEditing::Source => CurrentlyEditing::Tags,
             _ => CurrentlyEditing::FrontText,
         };
     }
@@ -188,3 +412,27 @@ impl<'a> CreateCard<'a> {
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::domain::card::{FORWARD, REVERSE};
+
+    #[test]
+    fn test_saved_card_and_reverse_keep_their_sides() {
+        let mut create_card = CreateCard::from(&Card {
+            front_text: Some("hello".to_string()),
+            back_text: Some("hola".to_string()),
+            ..Card::default()
+        });
+        create_card.fill_card();
+        assert_eq!(Some("hello".to_string()), create_card.card.front_text);
+        assert_eq!(Some("hola".to_string()), create_card.card.back_text);
+
+        let note = Note::from_card(Uuid::new_v4(), &create_card.card, true);
+        assert_eq!((Some("hello".to_string()), Some("hola".to_string())), note.card_text(FORWARD));
+        assert_eq!((Some("hola".to_string()), Some("hello".to_string())), note.card_text(REVERSE));
+    }
+}
@@ -0,0 +1,110 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, ListState, Paragraph, StatefulWidget, Widget, Wrap,
+    },
+};
+use sqlx::PgPool;
+
+use crate::{domain::leech::Leech, tui::utils};
+
+/// Cards tagged as leeches, each with the reviews it failed, so they can be rewritten
+#[derive(Debug, Clone, Default)]
+pub struct LeechList {
+    pub leeches: Vec<Leech>,
+}
+
+impl LeechList {
+    pub async fn load(db: &PgPool) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            leeches: Leech::load_all(db).await?,
+        })
+    }
+
+    fn history_text(&self, index: usize) -> Text<'static> {
+        let leech = match self.leeches.get(index) {
+            Some(leech) => leech,
+            None => return Text::from("No leeches"),
+        };
+        let label = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+        let mut text = Text::default();
+        for (name, side) in [("FRONT", &leech.card.front_text), ("BACK", &leech.card.back_text)] {
+            text.push_line(Line::styled(name, label));
+            for line in side.as_deref().unwrap_or_default().lines() {
+                text.push_line(Line::from(format!("  {}", line)));
+            }
+            text.push_line(Line::from(""));
+        }
+        text.push_line(Line::styled(
+            format!(
+                "FAILED {} OF {} REVIEWS ({} lapses)",
+                leech.failures.len(),
+                leech.n_reviews,
+                leech.card.lapses
+            ),
+            label,
+        ));
+        for failure in &leech.failures {
+            let mut line = failure.time.format("%Y-%m-%d %H:%M").to_string();
+            if let Some(typed) = &failure.typed_answer {
+                line.push_str(&format!("  typed '{}'", typed.replace('\n', " ")));
+            }
+            if let Some(similarity) = failure.similarity {
+                line.push_str(&format!(" ({:.0}% match)", similarity * 100.0));
+            }
+            if failure.hint_used {
+                line.push_str("  with hint");
+            }
+            text.push_line(Line::styled(format!("  {}", line), Style::default().fg(Color::Red)));
+        }
+        text
+    }
+
+    pub fn render(&self, state: &mut ListState, area: Rect, buf: &mut Buffer) {
+        let title = Title::from("[ LEECHES ]".bold());
+        let instructions = Title::from(Line::from(vec![
+            "[ [e] edit, [s] suspend/unsuspend, [c] no longer a leech, [u/^r] undo/redo, [b] back ]".into(),
+        ]));
+        let block = Block::default()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(inner);
+
+        let entries: Vec<String> = self
+            .leeches
+            .iter()
+            .map(|leech| {
+                format!(
+                    "{} ({} lapses){}",
+                    leech.card.front_text.clone().unwrap_or_default().replace('\n', " "),
+                    leech.card.lapses,
+                    if leech.card.suspended { " [suspended]" } else { "" }
+                )
+            })
+            .collect();
+        let list = utils::styled_list(entries, Block::default().borders(Borders::RIGHT).title("CARDS"));
+        StatefulWidget::render(list, layout[0], buf, state);
+
+        Paragraph::new(self.history_text(state.selected().unwrap_or(0usize)))
+            .block(Block::default().padding(ratatui::widgets::Padding::horizontal(1)).title("HISTORY"))
+            .wrap(Wrap { trim: false })
+            .render(layout[1], buf);
+    }
+}
@@ -1,4 +1,12 @@
 pub mod main_screen;
+pub mod card_browser;
 pub mod create_card;
 pub mod create_deck;
-pub mod review_deck;
\ No newline at end of file
+pub mod leeches;
+pub mod presets;
+pub mod review_deck;
+pub mod revisions;
+pub mod search_results;
+pub mod simulation;
+pub mod stats;
+pub mod trash;
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Stylize,
+    symbols::border,
+    text::Line,
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, ListState, StatefulWidget,
+    },
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{domain::preset::Preset, tui::utils};
+
+/// Deck option presets, and the deck they can be linked to
+#[derive(Debug, Clone, Default)]
+pub struct PresetList {
+    pub presets: Vec<Preset>,
+    pub deck: Option<(Uuid, String, Option<Uuid>)>, // the deck [l] links, and its current preset
+    pub n_decks: HashMap<Uuid, usize>,              // decks linked to each preset
+}
+
+impl PresetList {
+    pub async fn load(
+        db: &PgPool,
+        deck: Option<(Uuid, String, Option<Uuid>)>,
+        n_decks: HashMap<Uuid, usize>,
+    ) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            presets: Preset::load_all(db).await?,
+            deck,
+            n_decks,
+        })
+    }
+
+    pub fn render(&self, state: &mut ListState, area: Rect, buf: &mut Buffer) {
+        let title = match &self.deck {
+            Some((_, name, _)) => format!("[ PRESETS: linking deck '{}' ]", name),
+            None => "[ PRESETS ]".to_string(),
+        };
+        let instructions = Title::from(Line::from(vec![
+            "[ [n] new, [e] edit, [d] delete, [l] link deck, [o] unlink deck, [b] back ]".into(),
+        ]));
+        let block = Block::default()
+            .title(Title::from(title.bold()).alignment(Alignment::Center))
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+
+        let linked = self.deck.as_ref().and_then(|(_, _, preset_id)| *preset_id);
+        let lines: Vec<String> = self
+            .presets
+            .iter()
+            .map(|preset| {
+                format!(
+                    "{}{} ({} deck(s)): {}",
+                    preset.name,
+                    if linked == Some(preset.id) { " ✓" } else { "" },
+                    self.n_decks.get(&preset.id).copied().unwrap_or(0),
+                    preset
+                )
+            })
+            .collect();
+        let list = utils::styled_list(utils::add_nums_to_text(lines), block);
+        StatefulWidget::render(list, area, buf, state);
+    }
+}
@@ -0,0 +1,805 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span, Text},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Paragraph, Widget, Wrap,
+    },
+};
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use sqlx::{types::chrono::{DateTime, NaiveDate, Utc}, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        activity::{day_of, start_of},
+        answer::{self, AnswerCheck, AnswerOptions},
+        card::Card,
+        choice::Choices,
+        cloze,
+        diff::DiffOp,
+        history::Command,
+        leech,
+        note::Note,
+        params,
+        preset::Preset,
+        view::View,
+    },
+    tui::markdown::{self, CodeView},
+};
+
+/// Longer answers are logged as this long, so walking away mid-review doesn't skew the time spent
+const MAX_ANSWER_TIME: Duration = Duration::from_secs(60);
+
+/// A review session over a list of cards; cards in their learning steps rejoin it once due
+#[derive(Debug, Clone)]
+pub struct ReviewDeck {
+    pub cards: Vec<Card>,
+    pub index: usize,
+    pub revealed: bool,
+    pub n_correct: usize,
+    pub type_answer_decks: HashSet<Uuid>, // cards from these decks are answered by typing
+    pub answer_options: AnswerOptions,
+    pub typed: String,
+    pub check: Option<AnswerCheck>, // set once a typed answer is submitted
+    pub choices: Vec<Choices>,      // one per card in a multiple-choice session, else empty
+    pub code_view: CodeView,
+    pub hint_shown: usize, // words (or characters, for one-word hints) of the hint revealed
+    pub seeds: HashMap<Uuid, i64>, // parameterised cards, shown with values drawn from these
+    pub stored_text: HashMap<Uuid, (Option<String>, Option<String>)>, // their text before that
+    pub shown_at: Instant, // when the current card was shown, to time the answer
+    pub exam_dates: HashMap<Uuid, NaiveDate>, // intervals of cards from these decks end before the exam
+    pub rollover_hour: u32,
+    pub deck_options: HashMap<Uuid, Preset>, // by deck, for the scheduler to use
+    pub learning: Vec<(DateTime<Utc>, Card, Option<Choices>)>, // waiting for their next step, with when
+}
+
+impl Widget for &ReviewDeck {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let learning = match self.learning.len() {
+            0 => String::new(),
+            n => format!(", {} learning", n),
+        };
+        let title = Title::from(
+            format!(
+                "[ REVIEW {}/{}{} ]",
+                (self.index + 1).min(self.cards.len()),
+                self.cards.len(),
+                learning
+            )
+            .bold(),
+        );
+        let instructions = match (self.is_finished(), self.revealed) {
+            (true, _) => "[ Press any key to return ]",
+            (false, _) if self.current_card().is_none() => "[ [b] to stop ]",
+            (false, false) if self.requires_typing() => "[ type the answer, [enter] to check, [esc] to stop ]",
+            (false, false) if self.is_choosing() => "[ [1-4] pick an answer, [?] hint, [b] to stop ]",
+            (false, false) => "[ [space] to reveal, [?] hint, [h/l] scroll code, [b] to stop ]",
+            (false, true) if self.check.is_some() => {
+                "[ [enter] accept suggestion, [y] correct, [n] incorrect, [b] to stop ]"
+            }
+            (false, true) if self.current_choices().is_some() => {
+                "[ [enter] next card, [y] correct, [n] incorrect, [b] to stop ]"
+            }
+            (false, true) => "[ [y] correct, [n] incorrect, [h/l] scroll code, [b] to stop ]",
+        };
+        let block = Block::default()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                Title::from(Line::from(instructions))
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let card = match self.current_card() {
+            Some(card) => card,
+            None if !self.is_finished() => {
+                Paragraph::new(self.waiting_text(Utc::now()))
+                    .style(Style::default().fg(Color::Gray))
+                    .centered()
+                    .render(inner, buf);
+                return;
+            }
+            None => {
+                Paragraph::new(format!(
+                    "Session complete: {}/{} correct",
+                    self.n_correct,
+                    self.cards.len()
+                ))
+                .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+                .centered()
+                .render(inner, buf);
+                return;
+            }
+        };
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .margin(1)
+            .split(inner);
+
+        let code_view = CodeView {
+            width: Some(layout[0].width as usize),
+            ..self.code_view
+        };
+        let mut front = markdown::render_with(card.front_text.as_deref().unwrap_or_default(), code_view);
+        if let Some(hint) = self.hint_text() {
+            front.push_line(Line::default());
+            front.push_line(Line::styled(
+                format!("Hint: {}", hint),
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            ));
+        }
+        Paragraph::new(front)
+            .block(Block::default().borders(Borders::BOTTOM))
+            .wrap(Wrap { trim: false })
+            .centered()
+            .render(layout[0], buf);
+
+        let back_area = match (self.requires_typing(), &self.check) {
+            (true, Some(check)) => {
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(vec![Constraint::Length(3), Constraint::Min(0)])
+                    .split(layout[1]);
+                Paragraph::new(check_text(check))
+                    .wrap(Wrap { trim: false })
+                    .centered()
+                    .render(split[0], buf);
+                split[1]
+            }
+            (true, None) if !self.revealed => {
+                Paragraph::new(Line::from(vec![
+                    Span::styled("> ", Style::default().fg(Color::Cyan)),
+                    Span::raw(self.typed.clone()),
+                    Span::styled("▏", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+                ]))
+                .wrap(Wrap { trim: false })
+                .centered()
+                .render(layout[1], buf);
+                layout[1]
+            }
+            _ => layout[1],
+        };
+
+        if let Some(choices) = self.current_choices() {
+            Paragraph::new(choices_text(choices))
+                .wrap(Wrap { trim: false })
+                .render(back_area, buf);
+            return;
+        }
+
+        if self.revealed {
+            let mut back = back_text(card, code_view);
+            if let Some(notes) = card.notes.as_deref().filter(|notes| !notes.trim().is_empty()) {
+                back.push_line(Line::default());
+                for line in markdown::render_with(notes, code_view).lines {
+                    back.push_line(line.patch_style(Style::default().fg(Color::Gray)));
+                }
+            }
+            if let Some(source) = card.source.as_deref().filter(|source| !source.trim().is_empty()) {
+                back.push_line(Line::default());
+                back.push_line(Line::styled(
+                    format!("Source: {}", source),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+            }
+            Paragraph::new(back)
+                .style(Style::default().fg(Color::Yellow))
+                .wrap(Wrap { trim: false })
+                .centered()
+                .render(back_area, buf);
+        }
+    }
+}
+
+/// The first `shown` words of `hint`, or characters if it is a single word,
+/// with the rest masked by underscores
+fn partial_hint(hint: &str, shown: usize) -> String {
+    let words: Vec<&str> = hint.split_whitespace().collect();
+    let mask = |text: &str| "_".repeat(text.chars().count());
+    match words.as_slice() {
+        [word] => word.chars().take(shown).collect::<String>() + &mask(word).chars().skip(shown).collect::<String>(),
+        _ => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| if i < shown { word.to_string() } else { mask(word) })
+            .collect::<Vec<String>>()
+            .join(" "),
+    }
+}
+
+/// The numbered options; once one is picked, the answer is green and a wrong pick red
+fn choices_text(choices: &Choices) -> Text<'static> {
+    let lines: Vec<Line> = choices
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let style = match choices.picked {
+                Some(_) if i == choices.correct => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                Some(picked) if i == picked => Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT),
+                _ => Style::default(),
+            };
+            Line::styled(format!("[{}] {}", i + 1, option.replace('\n', " ")), style)
+        })
+        .collect();
+    Text::from(lines)
+}
+
+/// The typed answer diffed against the expected one, and the suggested grade
+fn check_text(check: &AnswerCheck) -> Text<'static> {
+    let spans: Vec<Span> = check
+        .diff
+        .iter()
+        .map(|op| match op {
+            DiffOp::Same(c) => Span::styled(c.to_string(), Style::default().fg(Color::Green)),
+            DiffOp::Removed(c) => Span::styled(
+                c.to_string(),
+                Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT),
+            ),
+            DiffOp::Added(c) => Span::styled(
+                c.to_string(),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED),
+            ),
+        })
+        .collect();
+    let suggestion = match (check.is_exact(), check.suggested_grade()) {
+        (true, _) => "Exact match".to_string(),
+        (false, true) => format!("{:.0}% match, suggested: correct", check.similarity * 100.0),
+        (false, false) => format!("{:.0}% match, suggested: incorrect", check.similarity * 100.0),
+    };
+    Text::from(vec![Line::from(spans), Line::styled(suggestion, Style::default().bold())])
+}
+
+// Private-use characters around the cloze answer; markdown passes them through untouched,
+// so the answer can be found again in the rendered text
+const ANSWER_START: char = '\u{E000}';
+const ANSWER_END: char = '\u{E001}';
+
+/// `answer` between the markers, which go inside any whitespace or punctuation at its ends:
+/// a `$` or `*` then has the same kind of neighbour as without them, so markdown pairs it up alike
+fn mark_answer(answer: &str) -> String {
+    let is_edge = |c: char| c.is_whitespace() || c.is_ascii_punctuation();
+    let start = answer.len() - answer.trim_start_matches(is_edge).len();
+    let end = answer.trim_end_matches(is_edge).len();
+    match start < end {
+        true => format!(
+            "{}{}{}{}{}",
+            &answer[..start],
+            ANSWER_START,
+            &answer[start..end],
+            ANSWER_END,
+            &answer[end..]
+        ),
+        false => format!("{}{}{}", ANSWER_START, answer, ANSWER_END),
+    }
+}
+
+/// The back of `card`, with its cloze deletion highlighted if it is a cloze card
+fn back_text(card: &Card, code_view: CodeView) -> Text<'static> {
+    let back = card.back_text.clone().unwrap_or_default();
+    let number = card.ordinal.max(0) as u32;
+    if !cloze::numbers(&back).contains(&number) {
+        return markdown::render_with(&back, code_view);
+    }
+    let marked: String = cloze::render_back(&back, number)
+        .into_iter()
+        .map(|(piece, is_answer)| match is_answer {
+            true => mark_answer(&piece),
+            false => piece,
+        })
+        .collect();
+    let mut text = markdown::render_with(&marked, code_view);
+
+    let highlight = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut in_answer = false;
+    for line in text.lines.iter_mut() {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        for span in line.spans.drain(..) {
+            // The answer can start or end anywhere in a span, or span several
+            for (i, part) in span.content.split([ANSWER_START, ANSWER_END]).enumerate() {
+                if i > 0 {
+                    in_answer = !in_answer;
+                }
+                if !part.is_empty() {
+                    let style = if in_answer { span.style.patch(highlight) } else { span.style };
+                    spans.push(Span::styled(part.to_string(), style));
+                }
+            }
+        }
+        line.spans = spans;
+    }
+    text
+}
+
+impl ReviewDeck {
+    /// Starts a session over `cards`, burying siblings: only the first card of each note is kept
+    pub fn new(cards: Vec<Card>, type_answer_decks: HashSet<Uuid>, answer_options: AnswerOptions) -> Self {
+        let mut review = Self {
+            cards: Note::bury_siblings(cards),
+            index: 0usize,
+            revealed: false,
+            n_correct: 0usize,
+            type_answer_decks,
+            answer_options,
+            typed: String::new(),
+            check: None,
+            choices: Vec::new(),
+            code_view: CodeView::default(),
+            hint_shown: 0usize,
+            seeds: HashMap::new(),
+            stored_text: HashMap::new(),
+            shown_at: Instant::now(),
+            exam_dates: HashMap::new(),
+            rollover_hour: 0,
+            deck_options: HashMap::new(),
+            learning: Vec::new(),
+        };
+        review.generate_values(&mut rand::thread_rng());
+        review
+    }
+
+    /// Fills in fresh values for the parameterised cards
+    fn generate_values(&mut self, rng: &mut impl Rng) {
+        for card in self.cards.iter_mut() {
+            let seed = rng.gen::<i64>();
+            if let Some(generated) = params::generate(card, seed) {
+                self.seeds.insert(card.id, seed);
+                self.stored_text
+                    .insert(card.id, (card.front_text.clone(), card.back_text.clone()));
+                *card = generated;
+            }
+        }
+    }
+
+    /// `card` as stored, without the values drawn for this session
+    fn stored(&self, mut card: Card) -> Card {
+        if let Some((front_text, back_text)) = self.stored_text.get(&card.id) {
+            card.front_text.clone_from(front_text);
+            card.back_text.clone_from(back_text);
+        }
+        card
+    }
+
+    /// `card` with the values drawn for this session
+    fn shown(&self, card: Card) -> Card {
+        match self.seeds.get(&card.id) {
+            Some(seed) => params::generate(&card, *seed).unwrap_or(card),
+            None => card,
+        }
+    }
+
+    /// Turns the session into a multiple-choice quiz, with distractors drawn from `pool`
+    pub fn with_choices(mut self, pool: &[Card]) -> Self {
+        let mut rng = rand::thread_rng();
+        // Parameterised distractors get values of their own
+        let pool: Vec<Card> = pool
+            .iter()
+            .map(|card| params::generate(card, rng.gen()).unwrap_or_else(|| card.clone()))
+            .collect();
+        let pool = pool.as_slice();
+        self.choices = self.cards.iter().map(|card| Choices::new(card, pool, &mut rng)).collect();
+        self
+    }
+
+    /// Caps the intervals of cards from decks with an exam date, so they come up again before it
+    pub fn with_exam_dates(mut self, exam_dates: HashMap<Uuid, NaiveDate>, rollover_hour: u32) -> Self {
+        self.exam_dates = exam_dates;
+        self.rollover_hour = rollover_hour;
+        self
+    }
+
+    /// Schedules the cards of each deck with that deck's options
+    pub fn with_deck_options(mut self, deck_options: HashMap<Uuid, Preset>) -> Self {
+        self.deck_options = deck_options;
+        self
+    }
+
+    pub fn current_card(&self) -> Option<&Card> {
+        self.cards.get(self.index)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.cards.len() && self.learning.is_empty()
+    }
+
+    /// e.g. "Next learning card in 4m 10s", while only cards in their learning steps are left
+    fn waiting_text(&self, now: DateTime<Utc>) -> String {
+        let next = self.learning.iter().map(|(due, _, _)| *due).min().unwrap_or(now);
+        let seconds = (next - now).num_seconds().max(0);
+        format!("Next learning card in {}m {:02}s", seconds / 60, seconds % 60)
+    }
+
+    /// Holds the card at `index` back until its learning step is over, if that is within
+    /// today's study day; later steps wait for another session, and suspended cards for good
+    fn hold_for_learning(&mut self, index: usize, now: DateTime<Utc>) {
+        let card = &self.cards[index];
+        let end_of_day = start_of(day_of(now, self.rollover_hour) + chrono::Duration::days(1), self.rollover_hour);
+        let learning = card.state.is_learning() && !card.suspended;
+        if let Some(due) = card.due.filter(|due| learning && *due < end_of_day) {
+            let choices = self.choices.get(index).cloned().map(|mut choices| {
+                choices.picked = None;
+                choices
+            });
+            self.learning.push((due, card.clone(), choices));
+        }
+    }
+
+    /// Puts the cards whose learning step is over back into the session, right after the
+    /// current card; called on every tick while the review is open
+    pub fn requeue_due(&mut self, now: DateTime<Utc>) {
+        let (mut due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.learning)
+            .into_iter()
+            .partition(|(due, _, _)| *due <= now);
+        self.learning = waiting;
+        if due.is_empty() {
+            return;
+        }
+        if self.current_card().is_none() {
+            self.shown_at = Instant::now();
+        }
+        due.sort_by_key(|(due, _, _)| *due);
+        let at = (self.index + 1).min(self.cards.len());
+        for (offset, (_, card, choices)) in due.into_iter().enumerate() {
+            self.cards.insert(at + offset, card);
+            if let Some(choices) = choices {
+                self.choices.insert(at + offset, choices);
+            }
+        }
+    }
+
+    pub fn reveal(&mut self) {
+        self.revealed = true;
+    }
+
+    /// Whether the current card's deck requires typing the answer
+    pub fn requires_typing(&self) -> bool {
+        self.current_card()
+            .and_then(|card| card.deck_id)
+            .is_some_and(|deck_id| self.type_answer_decks.contains(&deck_id))
+    }
+
+    pub fn current_choices(&self) -> Option<&Choices> {
+        self.choices.get(self.index)
+    }
+
+    /// Whether the current multiple-choice question is waiting for a pick
+    pub fn is_choosing(&self) -> bool {
+        self.current_choices().is_some_and(|choices| choices.picked.is_none())
+    }
+
+    /// Picks option `index` (0-based) of the current question and reveals the answer
+    pub fn pick(&mut self, index: usize) {
+        if let Some(choices) = self.choices.get_mut(self.index) {
+            if choices.pick(index) {
+                self.revealed = true;
+            }
+        }
+    }
+
+    /// Whether keys currently go to the answer being typed
+    pub fn is_typing(&self) -> bool {
+        self.requires_typing() && !self.revealed
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.typed.push(c);
+    }
+
+    pub fn pop_char(&mut self) {
+        self.typed.pop();
+    }
+
+    /// Checks the typed answer against the current card and reveals it
+    pub fn submit_typed(&mut self) {
+        if let Some(card) = self.current_card() {
+            let expected = answer::expected_answer(card);
+            self.check = Some(answer::check(&self.typed, &expected, &self.answer_options));
+            self.revealed = true;
+        }
+    }
+
+    /// The grade suggested by the typed answer once checked, or by the picked option
+    pub fn suggested_grade(&self) -> Option<bool> {
+        match &self.check {
+            Some(check) => Some(check.suggested_grade()),
+            None => self.current_choices().and_then(Choices::is_correct),
+        }
+    }
+
+    /// How many steps it takes to reveal the whole hint of the current card
+    fn hint_steps(&self) -> usize {
+        let hint = self.current_card().and_then(|card| card.hint.as_deref()).unwrap_or_default();
+        match hint.split_whitespace().count() {
+            1 => hint.trim().chars().count(),
+            n => n,
+        }
+    }
+
+    /// Reveals one more word (or character) of the hint; the grade will record that it was used
+    pub fn reveal_hint(&mut self) -> bool {
+        if self.revealed || self.hint_shown >= self.hint_steps() {
+            return false;
+        }
+        self.hint_shown += 1;
+        true
+    }
+
+    /// The hint as revealed so far, once any of it has been
+    pub fn hint_text(&self) -> Option<String> {
+        let hint = self.current_card()?.hint.as_deref()?;
+        match self.hint_shown {
+            0 => None,
+            shown => Some(partial_hint(hint, shown)),
+        }
+    }
+
+    /// Scrolls code blocks `columns` to the right, or left if negative
+    pub fn scroll_code(&mut self, columns: isize) {
+        self.code_view.scroll = self.code_view.scroll.saturating_add_signed(columns);
+    }
+
+    /// Forgets what was typed, how code was scrolled and how much hint was shown, for a new card
+    fn reset_card_state(&mut self) {
+        self.typed.clear();
+        self.check = None;
+        self.code_view.scroll = 0;
+        self.hint_shown = 0;
+        self.shown_at = Instant::now();
+    }
+
+    /// Logs the result for the current card to `views`, reschedules it and moves to the next card.
+    /// Returns the command for undoing the grade.
+    pub async fn answer(&mut self, db: &PgPool, correct: bool) -> Result<Option<Command>, sqlx::Error> {
+        let mut card = match self.cards.get(self.index) {
+            Some(card) => card.clone(),
+            None => return Ok(None),
+        };
+        // History restores cards as stored, with their expressions rather than drawn values
+        let before = self.stored(card.clone());
+        let hint_used = self.hint_shown > 0;
+        let mut view = View::new(card.id, correct);
+        view.hint_used = hint_used;
+        view.seed = self.seeds.get(&card.id).copied();
+        view.duration_ms = Some(self.shown_at.elapsed().min(MAX_ANSWER_TIME).as_millis() as i32);
+        if let Some(check) = &self.check {
+            view.typed_answer = Some(self.typed.clone());
+            view.similarity = Some(check.similarity);
+        }
+        view.save(db).await?;
+        let now = Utc::now();
+        // Cards of decks without options of their own go by the defaults, as in `Deck::options`
+        let options = card
+            .deck_id
+            .and_then(|deck_id| self.deck_options.get(&deck_id))
+            .cloned()
+            .unwrap_or_default();
+        let mut schedule = options.next_schedule(&card.schedule(), correct, hint_used, now);
+        if let Some(exam) = card.deck_id.and_then(|deck_id| self.exam_dates.get(&deck_id)) {
+            schedule = schedule.capped_before(*exam, day_of(now, self.rollover_hour));
+        }
+        let lapses_before = card.lapses;
+        card.set_schedule(schedule);
+        card.save_schedule(db).await?;
+        if leech::becomes_leech(&card, lapses_before, options.leech_threshold) {
+            leech::mark(db, &mut card, options.leech_action).await?;
+        }
+        let after = self.stored(card.clone());
+        self.cards[self.index] = card;
+        self.hold_for_learning(self.index, now);
+        if correct {
+            self.n_correct += 1;
+        }
+        self.index += 1;
+        self.revealed = false;
+        self.reset_card_state();
+        Ok(Some(Command::GradeReview { view, before, after }))
+    }
+
+    /// Steps back to the previous card after its grade was undone, if it was graded in this session
+    pub fn undo_answer(&mut self, before: &Card, correct: bool) {
+        if self.index > 0 && self.cards[self.index - 1].id == before.id {
+            self.index -= 1;
+            self.cards[self.index] = self.shown(before.clone());
+            // It no longer has a learning step to come back for
+            self.learning.retain(|(_, card, _)| card.id != before.id);
+            while let Some(offset) = self.cards[self.index + 1..].iter().position(|card| card.id == before.id) {
+                let position = self.index + 1 + offset;
+                self.cards.remove(position);
+                if position < self.choices.len() {
+                    self.choices.remove(position);
+                }
+            }
+            if correct {
+                self.n_correct -= 1;
+            }
+            self.revealed = true;
+            self.reset_card_state();
+            if let Some(choices) = self.choices.get_mut(self.index) {
+                // Ask the question again rather than show the old pick
+                choices.picked = None;
+                self.revealed = false;
+            }
+        }
+    }
+
+    /// Steps forward again after the grade of the current card was redone
+    pub fn redo_answer(&mut self, after: &Card, correct: bool) {
+        if self.current_card().is_some_and(|card| card.id == after.id) {
+            self.cards[self.index] = self.shown(after.clone());
+            self.hold_for_learning(self.index, Utc::now());
+            if correct {
+                self.n_correct += 1;
+            }
+            self.index += 1;
+            self.revealed = false;
+            self.reset_card_state();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::card::REVERSE;
+    use crate::domain::scheduler::CardState;
+
+    fn cloze_back(back: &str) -> Vec<(String, bool)> {
+        let card = Card {
+            back_text: Some(back.to_string()),
+            ordinal: 1,
+            ..Card::default()
+        };
+        back_text(&card, CodeView::default())
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .map(|span| (span.content.to_string(), span.style.add_modifier.contains(Modifier::UNDERLINED)))
+            .collect()
+    }
+
+    #[test]
+    fn test_cloze_back_is_rendered_then_highlighted() {
+        assert_eq!(
+            vec![("costs $5 and $".to_string(), false), ("10".to_string(), true)],
+            cloze_back("costs $5 and {{c1::$10}}")
+        );
+        // maths spanning the deletion, and markdown around the answer
+        assert_eq!(
+            vec![("x + ".to_string(), false), ("y".to_string(), true)],
+            cloze_back("$x + {{c1::y}}$")
+        );
+        assert_eq!(
+            vec![("Paris".to_string(), true), (" is in France".to_string(), false)],
+            cloze_back("{{c1::**Paris**}} is in France")
+        );
+    }
+
+    #[test]
+    fn test_siblings_are_buried() {
+        let note_id = Some(Uuid::new_v4());
+        let forward = Card { note_id, ..Card::default() };
+        let reverse = Card { note_id, ordinal: REVERSE, ..Card::default() };
+        let other = Card { note_id: Some(Uuid::new_v4()), ..Card::default() };
+        let review = ReviewDeck::new(
+            vec![forward.clone(), reverse, other.clone()],
+            HashSet::new(),
+            AnswerOptions::default(),
+        );
+        let ids: Vec<Uuid> = review.cards.iter().map(|card| card.id).collect();
+        assert_eq!(vec![forward.id, other.id], ids);
+    }
+
+    #[test]
+    fn test_multiple_choice_pick_suggests_grade() {
+        let deck_id = Some(Uuid::new_v4());
+        let cards: Vec<Card> = ["uno", "dos", "tres"]
+            .iter()
+            .map(|back| Card {
+                back_text: Some(back.to_string()),
+                deck_id,
+                ..Card::default()
+            })
+            .collect();
+        let mut review = ReviewDeck::new(cards.clone(), HashSet::new(), AnswerOptions::default()).with_choices(&cards);
+        assert!(review.is_choosing());
+        let wrong = (review.choices[0].correct + 1) % 3;
+        review.pick(wrong);
+        assert!(review.revealed);
+        assert_eq!(Some(false), review.suggested_grade());
+    }
+
+    #[test]
+    fn test_hint_is_revealed_progressively() {
+        assert_eq!("to ___ ___", partial_hint("to the sea", 1));
+        assert_eq!("ca__", partial_hint("cafe", 2));
+
+        let card = Card {
+            hint: Some("sea".to_string()),
+            ..Card::default()
+        };
+        let mut review = ReviewDeck::new(vec![card], HashSet::new(), AnswerOptions::default());
+        assert_eq!(None, review.hint_text());
+        assert!(review.reveal_hint());
+        assert_eq!(Some("s__".to_string()), review.hint_text());
+        assert!(review.reveal_hint() && review.reveal_hint());
+        assert!(!review.reveal_hint());
+    }
+
+    #[test]
+    fn test_typed_answer_is_checked_against_back() {
+        let deck_id = Uuid::new_v4();
+        let card = Card {
+            back_text: Some("Guten Morgen".to_string()),
+            deck_id: Some(deck_id),
+            ..Card::default()
+        };
+        let mut review = ReviewDeck::new(
+            vec![card],
+            HashSet::from([deck_id]),
+            AnswerOptions::default(),
+        );
+        assert!(review.is_typing());
+        "guten morgn".chars().for_each(|c| review.push_char(c));
+        review.submit_typed();
+        assert!(!review.is_typing());
+        assert_eq!(Some(true), review.suggested_grade());
+    }
+
+    #[test]
+    fn test_learning_card_rejoins_session_when_due() {
+        let now = Utc::now();
+        let learning = Card {
+            state: CardState::Learning,
+            due: Some(now + chrono::Duration::minutes(10)),
+            ..Card::default()
+        };
+        let other = Card::default();
+        let mut review = ReviewDeck::new(vec![learning.clone(), other.clone()], HashSet::new(), AnswerOptions::default());
+        review.hold_for_learning(0, now);
+        review.index = 1;
+        review.requeue_due(now);
+        assert_eq!(2, review.cards.len());
+        // Comes back after the card being shown, once its step is over
+        review.requeue_due(now + chrono::Duration::minutes(11));
+        let ids: Vec<Uuid> = review.cards.iter().map(|card| card.id).collect();
+        assert_eq!(vec![learning.id, other.id, learning.id], ids);
+        assert!(review.learning.is_empty());
+
+        // Waiting for a step keeps the session open
+        review.index = 3;
+        review.hold_for_learning(2, now);
+        assert!(review.current_card().is_none() && !review.is_finished());
+        assert_eq!("Next learning card in 10m 00s", review.waiting_text(now));
+    }
+
+    #[test]
+    fn test_parameterised_card_keeps_stored_text() {
+        let card = Card {
+            front_text: Some("{{a=rand(2,12)}} + 1 = ?".to_string()),
+            back_text: Some("{{a+1}}".to_string()),
+            ..Card::default()
+        };
+        let review = ReviewDeck::new(vec![card.clone()], HashSet::new(), AnswerOptions::default());
+        let shown = review.current_card().unwrap().clone();
+        assert!(!shown.front_text.as_deref().unwrap().contains("{{"));
+        assert!(review.seeds.contains_key(&card.id));
+        assert_eq!(card.front_text, review.stored(shown.clone()).front_text);
+        assert_eq!(shown.back_text, review.shown(card).back_text);
+    }
+}
@@ -0,0 +1,167 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, ListState, Paragraph, StatefulWidget, Widget, Wrap,
+    },
+};
+use sqlx::PgPool;
+
+use crate::{
+    domain::{
+        card::Card,
+        diff::{line_diff, DiffOp},
+        revision::CardRevision,
+    },
+    tui::utils,
+};
+
+/// Edit history of a single card, each revision shown as a diff against the text that replaced it
+#[derive(Debug, Clone)]
+pub struct RevisionHistory {
+    pub card: Card,
+    pub revisions: Vec<CardRevision>, // newest first
+}
+
+impl RevisionHistory {
+    pub async fn load(db: &PgPool, card: Card) -> Result<Self, sqlx::Error> {
+        let revisions = CardRevision::load_for_card(db, card.id).await?;
+        Ok(Self { card, revisions })
+    }
+
+    /// (front, back) after the edit that replaced revision `index`:
+    /// the next newer revision, or the card as it is now
+    fn text_after(&self, index: usize) -> (Option<String>, Option<String>) {
+        match index.checked_sub(1).and_then(|i| self.revisions.get(i)) {
+            Some(newer) => (newer.front_text.clone(), newer.back_text.clone()),
+            None => (self.card.front_text.clone(), self.card.back_text.clone()),
+        }
+    }
+
+    /// The card with its text set back to revision `index`
+    pub fn reverted_card(&self, index: usize) -> Option<Card> {
+        self.revisions.get(index).map(|revision| Card {
+            front_text: revision.front_text.clone(),
+            back_text: revision.back_text.clone(),
+            ..self.card.clone()
+        })
+    }
+
+    fn diff_text(&self, index: usize) -> Text<'static> {
+        let revision = match self.revisions.get(index) {
+            Some(revision) => revision,
+            None => return Text::from("No revisions"),
+        };
+        let (front_after, back_after) = self.text_after(index);
+        let label = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+        let mut text = Text::default();
+        for (name, before, after) in [
+            ("FRONT", &revision.front_text, &front_after),
+            ("BACK", &revision.back_text, &back_after),
+        ] {
+            text.push_line(Line::styled(name, label));
+            let ops = line_diff(
+                before.as_deref().unwrap_or_default(),
+                after.as_deref().unwrap_or_default(),
+            );
+            for op in ops {
+                text.push_line(match op {
+                    DiffOp::Same(line) => Line::from(format!("  {}", line)),
+                    DiffOp::Removed(line) => Line::styled(format!("- {}", line), Style::default().fg(Color::Red)),
+                    DiffOp::Added(line) => Line::styled(format!("+ {}", line), Style::default().fg(Color::Green)),
+                });
+            }
+            text.push_line(Line::from(""));
+        }
+        text
+    }
+
+    pub fn render(&self, state: &mut ListState, area: Rect, buf: &mut Buffer) {
+        let title = Title::from(
+            format!(
+                "[ HISTORY OF '{}' ]",
+                self.card.front_text.clone().unwrap_or_default().replace('\n', " ")
+            )
+            .bold(),
+        );
+        let instructions = Title::from(Line::from(vec![
+            "[ [r] revert to revision, [u/^r] undo/redo, [b] back ]".into(),
+        ]));
+        let block = Block::default()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(inner);
+
+        let entries: Vec<String> = self
+            .revisions
+            .iter()
+            .map(|revision| {
+                format!(
+                    "{} {} ({})",
+                    revision.created.format("%Y-%m-%d %H:%M"),
+                    revision.author.clone().unwrap_or_default(),
+                    revision.source
+                )
+            })
+            .collect();
+        let list = utils::styled_list(entries, Block::default().borders(Borders::RIGHT).title("EDITED"));
+        StatefulWidget::render(list, layout[0], buf, state);
+
+        Paragraph::new(self.diff_text(state.selected().unwrap_or(0usize)))
+            .block(Block::default().padding(ratatui::widgets::Padding::horizontal(1)).title("CHANGES"))
+            .wrap(Wrap { trim: false })
+            .render(layout[1], buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::chrono::Utc;
+    use uuid::Uuid;
+
+    fn revision(card: &Card, front: &str) -> CardRevision {
+        CardRevision {
+            id: Uuid::new_v4(),
+            card_id: card.id,
+            front_text: Some(front.to_string()),
+            back_text: card.back_text.clone(),
+            source: "tui".to_string(),
+            author: None,
+            created: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_revision_compares_against_newer_text() {
+        let card = Card {
+            front_text: Some("v3".to_string()),
+            ..Card::default()
+        };
+        let history = RevisionHistory {
+            revisions: vec![revision(&card, "v2"), revision(&card, "v1")],
+            card,
+        };
+        assert_eq!(Some("v3".to_string()), history.text_after(0).0);
+        assert_eq!(Some("v2".to_string()), history.text_after(1).0);
+        assert_eq!(Some("v1".to_string()), history.reverted_card(1).unwrap().front_text);
+        assert!(history.reverted_card(2).is_none());
+    }
+}
@@ -0,0 +1,28 @@
+use sqlx::PgPool;
+
+use crate::domain::{card::Card, search::SearchQuery};
+
+/// Results of a global `SearchQuery`, browsed (and bulk-edited) in the CARDS list
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub query: SearchQuery,
+    pub cards: Vec<Card>,
+    pub from_deck: bool, // `b` returns to the deck's cards rather than DECKS
+}
+
+impl SearchResults {
+    pub async fn run(query: SearchQuery, db: &PgPool, from_deck: bool) -> Result<Self, sqlx::Error> {
+        let cards = query.run(db).await?;
+        Ok(Self {
+            query,
+            cards,
+            from_deck,
+        })
+    }
+
+    /// Re-runs the query, e.g. after cards have been edited
+    pub async fn reload(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        self.cards = self.query.run(db).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::{self, border},
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Widget,
+    },
+};
+
+use crate::domain::simulation::{DayLoad, SimParams};
+
+/// Expected daily reviews from a workload simulation
+#[derive(Debug, Clone)]
+pub struct SimulationScreen {
+    pub params: SimParams,
+    pub loads: Vec<DayLoad>,
+}
+
+impl SimulationScreen {
+    /// The busiest day by mean reviews, as (day, mean reviews), counting from 1
+    pub fn peak(&self) -> Option<(usize, f64)> {
+        self.loads
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.mean_reviews.total_cmp(&b.mean_reviews))
+            .map(|(day, load)| (day + 1, load.mean_reviews))
+    }
+
+    pub fn average(&self) -> f64 {
+        self.loads.iter().map(|load| load.mean_reviews).sum::<f64>() / self.loads.len().max(1) as f64
+    }
+
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let params = &self.params;
+        let deck = match &params.deck {
+            Some(deck) => format!("deck '{}'", deck),
+            None => "no deck".to_string(),
+        };
+        let title = Title::from(format!("[ SIMULATION: {} ]", deck).bold());
+        let block = Block::default()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                Title::from(Line::from("[ [b] back ]"))
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [summary_area, chart_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(3), Constraint::Min(0)])
+            .areas(inner);
+
+        let peak = match self.peak() {
+            Some((day, reviews)) => format!("peak {:.0} reviews on day {}", reviews, day),
+            None => "no reviews".to_string(),
+        };
+        let summary = vec![
+            Line::from(format!(
+                "{} extra card(s), {} new/day, {:.0}% retention, {} days, {} runs",
+                params.extra_cards,
+                params.new_per_day,
+                params.retention * 100.0,
+                params.days,
+                params.runs
+            )),
+            Line::from(format!("Expected: {:.0} reviews/day on average, {}", self.average(), peak)),
+        ];
+        Paragraph::new(summary).render(summary_area, buf);
+
+        let mean: Vec<(f64, f64)> = self
+            .loads
+            .iter()
+            .enumerate()
+            .map(|(day, load)| ((day + 1) as f64, load.mean_reviews))
+            .collect();
+        let p90: Vec<(f64, f64)> = self
+            .loads
+            .iter()
+            .enumerate()
+            .map(|(day, load)| ((day + 1) as f64, load.p90_reviews as f64))
+            .collect();
+        let top = p90.iter().map(|(_, reviews)| *reviews).fold(1.0, f64::max);
+        let datasets = vec![
+            Dataset::default()
+                .name("90th percentile")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&p90),
+            Dataset::default()
+                .name("mean")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&mean),
+        ];
+        let days = params.days as f64;
+        Chart::new(datasets)
+            .block(Block::default().borders(Borders::ALL).title("Reviews per day"))
+            .x_axis(
+                Axis::default()
+                    .title("day")
+                    .bounds([1.0, days])
+                    .labels(vec![
+                        Span::raw("1"),
+                        Span::raw(format!("{:.0}", (days / 2.0).ceil())),
+                        Span::raw(params.days.to_string()),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, top])
+                    .labels(vec![
+                        Span::raw("0"),
+                        Span::raw(format!("{:.0}", top / 2.0)),
+                        Span::raw(format!("{:.0}", top)),
+                    ]),
+            )
+            .render(chart_area, buf);
+    }
+}
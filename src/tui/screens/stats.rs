@@ -0,0 +1,180 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::{self, border},
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Axis, BarChart, Block, Borders, Chart, Dataset, GraphType, Sparkline, Widget,
+    },
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::stats::{Stats, STATS_DAYS};
+
+/// Charts of the review log, for one deck or all of them
+#[derive(Debug, Clone)]
+pub struct StatsScreen {
+    pub stats: Stats,
+    pub deck: Option<(Uuid, String)>, // the deck [d] switches to
+    pub per_deck: bool,
+    pub rollover_hour: u32,
+}
+
+impl StatsScreen {
+    /// Loads the statistics of all decks; `deck` can be switched to
+    pub async fn load(db: &PgPool, deck: Option<(Uuid, String)>, rollover_hour: u32) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            stats: Stats::load(db, None, rollover_hour).await?,
+            deck,
+            per_deck: false,
+            rollover_hour,
+        })
+    }
+
+    /// Switches between the statistics of the deck and of all decks
+    pub async fn toggle_deck(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let Some((deck_id, _)) = &self.deck else {
+            return Ok(());
+        };
+        let per_deck = !self.per_deck;
+        self.stats = Stats::load(db, per_deck.then_some(*deck_id), self.rollover_hour).await?;
+        self.per_deck = per_deck;
+        Ok(())
+    }
+
+    /// Reloads the statistics on display
+    pub async fn reload(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let deck_id = self.deck.as_ref().filter(|_| self.per_deck).map(|(deck_id, _)| *deck_id);
+        self.stats = Stats::load(db, deck_id, self.rollover_hour).await?;
+        Ok(())
+    }
+
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let scope = match (&self.deck, self.per_deck) {
+            (Some((_, name)), true) => format!("deck '{}'", name),
+            _ => "all decks".to_string(),
+        };
+        let title = Title::from(format!("[ STATS: {} ]", scope).bold());
+        let instructions = match self.deck {
+            Some(_) => "[ [d] deck / all decks, [b] back ]",
+            None => "[ [b] back ]",
+        };
+        let block = Block::default()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                Title::from(Line::from(instructions))
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, 3); 3])
+            .split(inner);
+        let cells: Vec<Rect> = rows
+            .iter()
+            .flat_map(|row| {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(*row)
+                    .to_vec()
+            })
+            .collect();
+
+        let stats = &self.stats;
+        daily_bars(
+            &format!("Reviews, last {} days ({})", STATS_DAYS, stats.total_reviews()),
+            &stats.reviews,
+            Color::Cyan,
+            cells[0],
+            buf,
+        );
+        self.render_retention(cells[1], buf);
+        daily_bars(
+            &format!("Due, next {} days", STATS_DAYS),
+            &stats.forecast,
+            Color::Yellow,
+            cells[2],
+            buf,
+        );
+        Sparkline::default()
+            .block(chart_block(&format!(
+                "Minutes spent, last {} days ({})",
+                STATS_DAYS,
+                stats.total_minutes()
+            )))
+            .data(&stats.minutes)
+            .style(Style::default().fg(Color::Magenta))
+            .render(cells[3], buf);
+        labelled_bars("Intervals", &stats.intervals, Color::Green, cells[4], buf);
+        labelled_bars("Ease", &stats.eases, Color::Blue, cells[5], buf);
+    }
+
+    fn render_retention(&self, area: Rect, buf: &mut Buffer) {
+        // Plotted oldest to newest, so days ago count down towards today on the right
+        let points: Vec<(f64, f64)> = self
+            .stats
+            .retention
+            .iter()
+            .map(|(ago, percentage)| ((STATS_DAYS - 1) as f64 - ago, *percentage))
+            .collect();
+        let dataset = Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&points);
+        let last = (STATS_DAYS - 1) as f64;
+        Chart::new(vec![dataset])
+            .block(chart_block("Retention (% correct)"))
+            .x_axis(
+                Axis::default()
+                    .bounds([0.0, last])
+                    .labels(vec![Span::raw(format!("-{}d", STATS_DAYS - 1)), Span::raw("today")]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, 100.0])
+                    .labels(vec![Span::raw("0"), Span::raw("50"), Span::raw("100")]),
+            )
+            .render(area, buf);
+    }
+}
+
+fn chart_block(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+/// One unlabelled bar per day, as wide as fits
+fn daily_bars(title: &str, values: &[u64], color: Color, area: Rect, buf: &mut Buffer) {
+    let data: Vec<(&str, u64)> = values.iter().map(|value| ("", *value)).collect();
+    let width = (area.width.saturating_sub(2) / values.len().max(1) as u16).saturating_sub(1).max(1);
+    BarChart::default()
+        .block(chart_block(title))
+        .data(&data)
+        .bar_width(width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(color))
+        .value_style(Style::default().fg(Color::Black).bg(color))
+        .render(area, buf);
+}
+
+/// One labelled bar per bucket
+fn labelled_bars(title: &str, values: &[(&str, u64)], color: Color, area: Rect, buf: &mut Buffer) {
+    let width = (area.width.saturating_sub(2) / values.len().max(1) as u16).saturating_sub(1).max(1);
+    BarChart::default()
+        .block(chart_block(title))
+        .data(values)
+        .bar_width(width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(color))
+        .value_style(Style::default().fg(Color::Black).bg(color))
+        .render(area, buf);
+}
@@ -0,0 +1,55 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Stylize,
+    symbols::border,
+    text::Line,
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, ListState, StatefulWidget,
+    },
+};
+use sqlx::PgPool;
+
+use crate::{domain::trash::TrashItem, tui::utils};
+
+/// Trashed decks and cards, which can be restored or purged for good
+#[derive(Debug, Clone, Default)]
+pub struct TrashBin {
+    pub items: Vec<TrashItem>,
+}
+
+impl TrashBin {
+    pub async fn load(db: &PgPool) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            items: TrashItem::load_all(db).await?,
+        })
+    }
+
+    pub async fn reload(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        self.items = TrashItem::load_all(db).await?;
+        Ok(())
+    }
+
+    pub fn render(&self, retention_days: i32, state: &mut ListState, area: Rect, buf: &mut Buffer) {
+        let title = Title::from(
+            format!("[ TRASH: {} item(s), purged after {} days ]", self.items.len(), retention_days).bold(),
+        );
+        let instructions = Title::from(Line::from(vec![
+            "[ [r] restore, [p] purge, [u/^r] undo/redo, [b] back ]".into(),
+        ]));
+        let block = Block::default()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::THICK);
+
+        let lines: Vec<String> = self.items.iter().map(|item| item.label()).collect();
+        let list = utils::styled_list(utils::add_nums_to_text(lines), block);
+        StatefulWidget::render(list, area, buf, state);
+    }
+}
@@ -3,7 +3,11 @@ use crossterm::terminal::{
 };
 use ratatui::{
     backend::CrosstermBackend,
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
     Terminal,
 };
 use std::io::{stdout, Stdout};
@@ -67,38 +71,57 @@ pub fn create_centred_rect_by_percent(percent_x: u16, percent_y: u16, area: Rect
         .split(centre_rect[1])[1] // Only take middle rectangles
 }
 
-pub fn add_nums_to_text(text: Vec<String>) -> Vec<String> {
+/// Line number column used by `add_nums_to_text`, e.g. `"12   "`
+pub fn num_prefix(num: usize) -> String {
     let nums_col_width = 4usize;
-    let nums: Vec<String> = (1..text.len() + 1).map(|val| val.to_string()).collect();
-    let nums: Vec<String> = nums
-        .iter()
-        .map(|s| format!("{}{}", s, " ".repeat(nums_col_width - s.len())))
-        .collect();
-    let list_text: Vec<String> = nums
-        .iter()
-        .zip(text.iter())
-        .map(|(a, b)| format!("{} {}", a, b))
-        .collect();
-    list_text
+    let s = num.to_string();
+    format!("{}{} ", s, " ".repeat(nums_col_width.saturating_sub(s.len())))
+}
+
+pub fn add_nums_to_text(text: Vec<String>) -> Vec<String> {
+    text.iter()
+        .enumerate()
+        .map(|(i, t)| format!("{}{}", num_prefix(i + 1), t))
+        .collect()
 }
 
-/// Implementation of KMP string search algorithm
+// Fuzzy match scoring, loosely following fzf's v1 algorithm
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL_OR_DIGIT: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// A text that matched the search string
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub index: usize,          // index into the searched text
+    pub score: i64,
+    pub positions: Vec<usize>, // char indices of matched characters
+}
+
+/// Fuzzy (subsequence) search over a list of strings, with ranked results
+///
+/// Matching is case-insensitive unless the search string contains an uppercase
+/// character ("smart case"). Results for each prefix of the search string are
+/// kept, so typing only re-scores the previous matches and deleting is free.
 #[derive(Debug, Clone)]
 pub struct Searcher {
     search_string: String,
-    text: Vec<String>,
-    table: Vec<usize>,
-    valid_strings: Vec<bool>,
+    text: Vec<Vec<char>>,
+    folded: Vec<Vec<char>>, // lowercased `text`
+    history: Vec<Vec<SearchMatch>>, // results for each prefix of `search_string`
 }
 
 impl Searcher {
     pub fn new(text: Vec<&str>) -> Self {
         let mut searcher = Self {
             search_string: String::new(),
-            //TODO: need to clarify &str or String
-            text: text.iter().map(|s| s.to_string()).collect(),
-            table: Vec::new(),
-            valid_strings: Vec::new(),
+            text: text.iter().map(|s| s.chars().collect()).collect(),
+            folded: text.iter().map(|s| s.chars().map(fold_char).collect()).collect(),
+            history: Vec::new(),
         };
         searcher.build();
         searcher
@@ -110,140 +133,215 @@ impl Searcher {
 
     /// Set search string to value directly
     pub fn set_search_string(&mut self, search_string: &str) {
-        self.search_string = search_string.to_string();
-        self.build_index();
+        self.search_string = String::new();
+        self.build();
+        for ch in search_string.chars() {
+            self.push_and_search(ch);
+        }
     }
 
-    /// Push character to search string (and rebuilds index table)
+    /// Push character to search string, narrowing the previous results
     pub fn push_and_search(&mut self, character: char) {
         self.search_string.push(character);
-        // TODO: cache table
-        self.build_index();
+        let query: Vec<char> = self.search_string.chars().collect();
+        let case_sensitive = query.iter().any(|c| c.is_uppercase());
+
+        let mut matches: Vec<SearchMatch> = self
+            .matches()
+            .iter()
+            .filter_map(|m| {
+                let haystack = if case_sensitive { &self.text[m.index] } else { &self.folded[m.index] };
+                fuzzy_match(&query, &self.text[m.index], haystack).map(|(score, positions)| SearchMatch {
+                    index: m.index,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        // Best score first, then shorter texts, then original order
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(self.text[a.index].len().cmp(&self.text[b.index].len()))
+                .then(a.index.cmp(&b.index))
+        });
+        self.history.push(matches);
     }
 
-    /// Pop character from search string (and rebuilds index table)
-    /// TODO: cache previous results for speed
+    /// Pop character from search string, restoring the previous results
     pub fn pop_and_search(&mut self) {
-        self.search_string.pop();
-        // 🤔
-        self.table.pop();
-        // Consider cacheing here for speed when deleting chars
-        for b in self.valid_strings.iter_mut() {
-            *b = true;
+        if self.search_string.pop().is_some() && self.history.len() > 1 {
+            self.history.pop();
         }
-        self.search();
     }
 
-    pub fn build_index(&mut self) {
-        tracing::info!("Building index table on {}", self.search_string);
-
-        self.table = vec![0usize; self.search_string.len() + 1];
-        if self.search_string.len() <= 1 {
-            return;
-        }
+    /// Reset results to every text, unranked
+    pub fn build(&mut self) {
+        let all: Vec<SearchMatch> = (0..self.text.len())
+            .map(|index| SearchMatch {
+                index,
+                score: 0,
+                positions: Vec::new(),
+            })
+            .collect();
+        self.history = vec![all];
+    }
 
-        let (mut pref_ind, mut suff_ind) = (0usize, 1usize);
-        let ss_bytes: Vec<u8> = self.search_string.bytes().collect();
-        while suff_ind < ss_bytes.len() {
-            tracing::info!("suff_ind is {}", suff_ind);
-            if ss_bytes[pref_ind] == ss_bytes[suff_ind] {
-                tracing::info!("match on {} and {}", ss_bytes[pref_ind], ss_bytes[suff_ind]);
-                self.table[suff_ind + 1] = self.table[suff_ind] + 1;
-                pref_ind += 1;
-                suff_ind += 1;
-            } else {
-                tracing::info!("no match");
-                while suff_ind < ss_bytes.len() && ss_bytes[pref_ind] != ss_bytes[suff_ind] {
-                    suff_ind += 1;
-                    tracing::info!("suf_ind incr to {}", suff_ind);
-                }
-            }
-        }
-        tracing::info!("Index table built: {:?}", self.table);
+    /// Current results, best match first
+    pub fn matches(&self) -> &[SearchMatch] {
+        self.history.last().map(|m| m.as_slice()).unwrap_or(&[])
     }
 
-    /// Build the index table etc.
-    pub fn build(&mut self) {
-        self.build_index();
-        self.valid_strings = vec![true; self.text.len()];
-        assert!(self.valid_strings.len() == self.text.len());
+    /// Maps a position in the results back to an index into the searched text
+    pub fn original_index(&self, position: usize) -> Option<usize> {
+        self.matches().get(position).map(|m| m.index)
     }
 
     pub fn get_text(&self) -> Vec<String> {
-        self.text
+        self.matches()
             .iter()
-            .zip(self.valid_strings.iter())
-            .filter(|(_, b)| **b)
-            .map(|(t, _)| t.clone())
+            .map(|m| self.text[m.index].iter().collect())
             .collect()
     }
+}
+
+fn fold_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
 
-    pub fn get_index_table(&self) -> Vec<usize> {
-        self.table.clone()
+fn bonus_at(text: &[char], i: usize) -> i64 {
+    let current = text[i];
+    let previous = if i == 0 { ' ' } else { text[i - 1] };
+    if !previous.is_alphanumeric() && current.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if (previous.is_lowercase() && current.is_uppercase())
+        || (!previous.is_numeric() && current.is_numeric())
+    {
+        BONUS_CAMEL_OR_DIGIT
+    } else {
+        0
     }
+}
 
-    /// Search
-    pub fn search(&mut self) {
-        tracing::info!("SEARCHING");
+/// Finds `query` as a subsequence of `haystack` (`text`, possibly case-folded),
+/// returning a score and the matched char positions
+fn fuzzy_match(query: &[char], text: &[char], haystack: &[char]) -> Option<(i64, Vec<usize>)> {
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
 
-        for (b, t) in self.valid_strings.iter_mut().zip(self.text.iter()) {
-            if b == &mut true {
-                tracing::info!("doing KMP for ss {} in {}", &self.search_string, &t);
-                *b = Self::kmp(&self.search_string, t, &self.table);
+    // Forward scan for the first position where the whole query has matched
+    let mut q = 0usize;
+    let mut end = None;
+    for (i, c) in haystack.iter().enumerate() {
+        if *c == query[q] {
+            q += 1;
+            if q == query.len() {
+                end = Some(i);
+                break;
             }
         }
-
-        let _ = self
-            .valid_strings
-            .iter_mut()
-            .zip(self.text.iter())
-            .map(|(is_in_play, text)| {
-                if is_in_play == &mut true {
-                    tracing::info!("doing KMP for ss {} in {}", &self.search_string, &text);
-                    *is_in_play = Self::kmp(&self.search_string, text, &self.table);
-                }
-            });
     }
+    let end = end?;
 
-    fn kmp(search_string: &str, text: &str, index_table: &[usize]) -> bool {
-        tracing::info!(
-            "performing kmp search, searching for `{}` in `{}`...",
-            search_string,
-            text
-        );
-        let (mut ss_ptr, mut txt_ptr) = (0usize, 0usize);
-        let search_bytes = search_string.as_bytes();
-        let text_bytes = text.as_bytes();
-
-        // TODO: return pointers to matching chars to highlight
-        // let match_chars: Vec<&u8> = Vec::new();
-
-        if search_bytes.is_empty() {
-            return true;
+    // Backward scan from there to find the shortest window
+    let mut q = query.len() - 1;
+    let mut start = 0usize;
+    for i in (0..=end).rev() {
+        if haystack[i] == query[q] {
+            if q == 0 {
+                start = i;
+                break;
+            }
+            q -= 1;
         }
+    }
 
-        while txt_ptr < text_bytes.len() && ss_ptr < search_bytes.len() {
-            if search_bytes[ss_ptr] == text_bytes[txt_ptr] {
-                ss_ptr += 1;
-                txt_ptr += 1;
-                if ss_ptr >= search_bytes.len() {
-                    tracing::info!("found string {} in {}", search_string, text);
-                    return true;
-                }
-            } else if ss_ptr == 0 {
-                txt_ptr += 1;
+    // Score the window
+    let (mut score, mut q, mut consecutive, mut first_bonus) = (0i64, 0usize, 0i64, 0i64);
+    let mut in_gap = false;
+    let mut positions: Vec<usize> = Vec::with_capacity(query.len());
+    for (i, c) in haystack.iter().enumerate().take(end + 1).skip(start) {
+        if q < query.len() && *c == query[q] {
+            let mut bonus = bonus_at(text, i);
+            if consecutive == 0 {
+                first_bonus = bonus;
             } else {
-                ss_ptr = index_table[ss_ptr - 1];
+                bonus = bonus.max(first_bonus).max(BONUS_CONSECUTIVE);
             }
+            if q == 0 {
+                bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+            }
+            score += SCORE_MATCH + bonus;
+            positions.push(i);
+            consecutive += 1;
+            in_gap = false;
+            q += 1;
+        } else {
+            score += if in_gap { SCORE_GAP_EXTENSION } else { SCORE_GAP_START };
+            consecutive = 0;
+            first_bonus = 0;
+            in_gap = true;
         }
-        tracing::info!("result: not found");
-        false
     }
+    Some((score, positions))
+}
+
+/// Renders `text` with the chars at `positions` highlighted
+pub fn highlight_matches(text: &str, positions: &[usize]) -> Line<'static> {
+    let highlight = Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let spans: Vec<Span> = text
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if positions.contains(&i) {
+                Span::styled(c.to_string(), highlight)
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect();
+    Line::from(spans)
 }
 
+/// Numbered list lines for the current results, with matched chars highlighted
+pub fn searcher_lines(searcher: &Searcher) -> Vec<Line<'static>> {
+    searcher
+        .matches()
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let text: String = searcher.text[m.index].iter().collect();
+            let mut line = highlight_matches(&text, &m.positions);
+            line.spans.insert(0, Span::raw(num_prefix(i + 1)));
+            line
+        })
+        .collect()
+}
+
+/// Splits a search bar off the bottom of `area`, renders it, and returns the rest
+pub fn render_search_bar(searcher: &Searcher, area: Rect, buf: &mut Buffer) -> Rect {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Percentage(100), Constraint::Min(3)])
+        .split(area);
+    let (main_area, search_bar_area) = (layout[0], layout[1]);
+
+    Paragraph::new(searcher.get_search_string())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{} match(es), [enter] to open", searcher.matches().len())),
+        )
+        .render(search_bar_area, buf);
+    main_area
+}
 
-pub fn styled_list(text: Vec<String>, block: ratatui::widgets::Block) -> ratatui::widgets::List {
-    ratatui::widgets::List::new(text)
+pub fn styled_list<'a, T>(items: Vec<T>, block: ratatui::widgets::Block<'a>) -> ratatui::widgets::List<'a>
+where
+    T: Into<ratatui::widgets::ListItem<'a>>,
+{
+    ratatui::widgets::List::new(items)
         .block(block)
         // .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
         .highlight_style(ratatui::style::Stylize::italic(ratatui::style::Stylize::light_blue(ratatui::style::Style::new())))
@@ -255,15 +353,67 @@ pub fn styled_list(text: Vec<String>, block: ratatui::widgets::Block) -> ratatui
 mod tests {
     use super::*;
 
+    fn ranked(searcher: &Searcher) -> Vec<String> {
+        searcher.get_text()
+    }
+
     #[test]
-    fn test_index_table_correct() {
-        let mut searcher = Searcher {
-            search_string: "abcdab".to_string(),
-            text: Vec::new(),
-            table: Vec::new(),
-            valid_strings: Vec::new(),
-        };
-        searcher.build();
-        assert_eq!(vec![0, 0, 0, 0, 0, 1, 2], searcher.get_index_table());
+    fn test_subsequence_match_is_ranked() {
+        let mut searcher = Searcher::new(vec!["a big cat", "abc", "axbxc", "nothing"]);
+        searcher.set_search_string("abc");
+        // consecutive beats word boundaries beats scattered
+        assert_eq!(vec!["abc", "a big cat", "axbxc"], ranked(&searcher));
+        assert_eq!(vec![0usize, 1, 2], searcher.matches()[0].positions);
+        assert_eq!(Some(1), searcher.original_index(0));
+    }
+
+    #[test]
+    fn test_case_insensitive_unicode() {
+        let mut searcher = Searcher::new(vec!["Éclair", "eclair", "ÜBER"]);
+        searcher.set_search_string("éc");
+        assert_eq!(vec!["Éclair"], ranked(&searcher));
+        searcher.set_search_string("über");
+        assert_eq!(vec!["ÜBER"], ranked(&searcher));
+    }
+
+    #[test]
+    fn test_uppercase_query_is_case_sensitive() {
+        let mut searcher = Searcher::new(vec!["Deck", "deck"]);
+        searcher.set_search_string("D");
+        assert_eq!(vec!["Deck"], ranked(&searcher));
+    }
+
+    #[test]
+    fn test_incremental_matches_fresh_search() {
+        let text = vec!["spanish verbs", "french nouns", "spaced repetition", "verbose"];
+        let mut incremental = Searcher::new(text.clone());
+        for ch in "spve".chars() {
+            incremental.push_and_search(ch);
+        }
+        let mut fresh = Searcher::new(text);
+        fresh.set_search_string("spve");
+        assert_eq!(fresh.matches(), incremental.matches());
+
+        // Deleting restores the previous, wider results
+        incremental.pop_and_search();
+        incremental.pop_and_search();
+        let mut fresh = Searcher::new(vec!["spanish verbs", "french nouns", "spaced repetition", "verbose"]);
+        fresh.set_search_string("sp");
+        assert_eq!(fresh.matches(), incremental.matches());
+    }
+
+    #[test]
+    fn test_empty_search_returns_everything() {
+        let mut searcher = Searcher::new(vec!["one", "two"]);
+        searcher.push_and_search('x');
+        assert!(searcher.matches().is_empty());
+        searcher.pop_and_search();
+        searcher.pop_and_search();
+        assert_eq!(vec!["one", "two"], ranked(&searcher));
+    }
+
+    #[test]
+    fn test_num_prefix() {
+        assert_eq!(vec!["1    a", "2    b"], add_nums_to_text(vec!["a".to_string(), "b".to_string()]));
     }
 }